@@ -19,8 +19,7 @@ fn main() -> Result<()> {
     let fe = FeDevice::open_ro(adapter, device)?;
     println!("{}", &fe);
 
-    let mut status = FeStatus::default();
-    status.read(&fe)?;
+    let status = FeStatus::from_device(&fe)?;
     println!("Status: {}", &status);
 
     Ok(())
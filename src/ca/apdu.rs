@@ -5,31 +5,191 @@
 //! Protocol Data Unit (APDU) structure to send application data between
 //! module and host or between modules.
 #![allow(dead_code)]
-use {super::CaDevice, anyhow::Result};
+use {
+    super::{asn1, cas, datetime, mmi, rm, spdu, tpdu, CaDevice},
+    crate::DvbError,
+    anyhow::{Context, Result},
+};
 
 pub const APDU_TAG_SIZE: usize = 3;
 
+/// en50221 Table 58: Resource Identifier values
+pub const RESOURCE_MANAGER: u32 = 0x0001_0041;
+pub const APPLICATION_INFORMATION: u32 = 0x0002_0041;
+pub const CONDITIONAL_ACCESS_SUPPORT: u32 = 0x0003_0041;
+pub const DATE_TIME: u32 = 0x0024_0041;
+pub const MMI: u32 = 0x0040_0041;
+
+/// Resources provided by the host and announced in the Resource Manager profile
+pub const HOST_RESOURCES: &[u32] = &[
+    RESOURCE_MANAGER,
+    APPLICATION_INFORMATION,
+    CONDITIONAL_ACCESS_SUPPORT,
+    DATE_TIME,
+    MMI,
+];
+
+/// en50221 Table 58: Application Information object tags
+const APPLICATION_INFO_ENQ: u32 = 0x9F8020;
+
+/// Resource identifier without version bits
+#[inline]
+fn resource_class(resource_id: u32) -> u32 {
+    resource_id & !0x3F
+}
+
+/// Returns host resource of the same class as `resource_id` regardless of the version
+fn host_resource(resource_id: u32) -> Option<u32> {
+    HOST_RESOURCES
+        .iter()
+        .copied()
+        .find(|&v| resource_class(v) == resource_class(resource_id))
+}
+
+/// Opened session between host and module
+#[derive(Debug, Clone, Copy)]
+pub struct CaSession {
+    pub session_id: u16,
+    pub resource_id: u32,
+}
+
 /// Init session and returns session identifier
-pub fn init(_ca: &mut CaDevice, _resource_id: u32) -> Result<u16> {
-    unimplemented!()
+pub fn init(ca: &mut CaDevice, resource_id: u32) -> Result<u16> {
+    if host_resource(resource_id).is_none() {
+        return Err(anyhow!(
+            "CA APDU: resource 0x{:08X} is not supported",
+            resource_id
+        ));
+    }
+
+    let session_id = (1..=u16::MAX)
+        .find(|&id| ca.sessions.iter().all(|s| s.session_id != id))
+        .context("CA APDU: no free sessions")?;
+
+    ca.sessions.push(CaSession {
+        session_id,
+        resource_id,
+    });
+
+    Ok(session_id)
 }
 
 /// Sends enquiry object to the CAM and allocate session object data
-pub fn open(_ca: &mut CaDevice, _session_id: u16) -> Result<()> {
-    unimplemented!()
+pub fn open(ca: &mut CaDevice, session_id: u16) -> Result<()> {
+    let session = find(ca, session_id)?;
+
+    match host_resource(session.resource_id) {
+        Some(RESOURCE_MANAGER) => rm::open(ca, session_id),
+        // application_info reply is not used
+        Some(APPLICATION_INFORMATION) => send(ca, session_id, APPLICATION_INFO_ENQ, &[]),
+        Some(CONDITIONAL_ACCESS_SUPPORT) => cas::open(ca, session_id),
+        Some(DATE_TIME) => {
+            datetime::open(ca, session_id);
+            Ok(())
+        }
+        Some(MMI) => {
+            mmi::open(ca, session_id);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
 }
 
 /// Close session
-pub fn close(_ca: &mut CaDevice, _session_id: u16) -> Result<()> {
-    unimplemented!()
+pub fn close(ca: &mut CaDevice, session_id: u16) -> Result<()> {
+    let session = find(ca, session_id)?;
+
+    match host_resource(session.resource_id) {
+        Some(RESOURCE_MANAGER) => rm::close(ca),
+        Some(CONDITIONAL_ACCESS_SUPPORT) => cas::close(ca),
+        Some(DATE_TIME) => datetime::close(ca),
+        Some(MMI) => mmi::close(ca),
+        _ => {}
+    }
+
+    ca.sessions.retain(|s| s.session_id != session_id);
+
+    Ok(())
 }
 
 /// Process CAM responses
-pub fn handle(_ca: &mut CaDevice, _session_id: u16, _msg: &[u8]) -> Result<()> {
-    unimplemented!()
+pub fn handle(ca: &mut CaDevice, session_id: u16, msg: &[u8]) -> Result<()> {
+    if msg.len() < APDU_TAG_SIZE + 1 {
+        return Err(anyhow!("CA APDU: message is too short"));
+    }
+
+    let tag = u32::from_be_bytes([0, msg[0], msg[1], msg[2]]);
+    let (len, skip) =
+        asn1::decode(&msg[APDU_TAG_SIZE..]).context("CA APDU: invalid length")?;
+    let begin = APDU_TAG_SIZE + skip;
+    let body = msg
+        .get(begin..begin + len)
+        .context("CA APDU: invalid message size")?;
+
+    let session = find(ca, session_id)?;
+
+    match host_resource(session.resource_id) {
+        Some(RESOURCE_MANAGER) => rm::handle(ca, session_id, tag, body),
+        Some(CONDITIONAL_ACCESS_SUPPORT) => cas::handle(ca, session_id, tag, body),
+        Some(DATE_TIME) => datetime::handle(ca, session_id, tag, body),
+        Some(MMI) => mmi::handle(ca, session_id, tag, body),
+        _ => Ok(()),
+    }
 }
 
 /// Periodically checks resource status
-pub fn manage(_ca: &mut CaDevice, _session_id: u16) -> Result<()> {
-    unimplemented!()
+pub fn manage(ca: &mut CaDevice) -> Result<()> {
+    datetime::manage(ca)
+}
+
+/// Sends APDU to the module in the session
+pub fn send(ca: &CaDevice, session_id: u16, tag: u32, data: &[u8]) -> Result<()> {
+    let mut buffer: Vec<u8> = Vec::with_capacity(spdu::SPDU_HEADER_SIZE + 8 + data.len());
+
+    buffer.push(spdu::ST_SESSION_NUMBER);
+    buffer.push(2);
+    buffer.extend_from_slice(&session_id.to_be_bytes());
+
+    buffer.extend_from_slice(&tag.to_be_bytes()[1..]);
+    asn1::encode(data.len() as u16, &mut buffer);
+    buffer.extend_from_slice(data);
+
+    tpdu::send(ca, ca.slot.slot_num as u8, tpdu::TT_DATA_LAST, &buffer)
+}
+
+fn find(ca: &CaDevice, session_id: u16) -> Result<CaSession> {
+    ca.sessions
+        .iter()
+        .find(|s| s.session_id == session_id)
+        .copied()
+        .ok_or_else(|| DvbError::CaSessionMismatch(session_id).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::ca::tests::{ca_device, open_session, parse_apdu},
+        spdu::{SS_NOT_ALLOCATED, SS_OK},
+    };
+
+    #[test]
+    fn open_host_resource() {
+        let mut ca = ca_device();
+
+        let (status, session_id, sent) = open_session(&mut ca, RESOURCE_MANAGER);
+        assert_eq!(status, SS_OK);
+        assert_eq!(find(&ca, session_id).unwrap().resource_id, RESOURCE_MANAGER);
+        // session is dispatched to the Resource Manager
+        assert_eq!(sent.len(), 1);
+        assert_eq!(parse_apdu(&sent[0]), (session_id, rm::PROFILE_ENQ, vec![]));
+
+        // resource version is not checked
+        let (status, _, _) = open_session(&mut ca, MMI + 1);
+        assert_eq!(status, SS_OK);
+
+        let (status, _, _) = open_session(&mut ca, 0x0099_0041);
+        assert_eq!(status, SS_NOT_ALLOCATED);
+        assert_eq!(ca.sessions.len(), 2);
+    }
 }
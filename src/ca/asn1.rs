@@ -12,3 +12,23 @@ pub fn encode(value: u16, out: &mut Vec<u8>) {
         out.push(value as u8);
     }
 }
+
+/// Decodes length field and returns value with number of bytes used by the field
+pub fn decode(data: &[u8]) -> Option<(usize, usize)> {
+    let first = *data.first()?;
+
+    if first & SIZE_INDICATOR == 0 {
+        return Some((usize::from(first), 1));
+    }
+
+    let size = usize::from(first & !SIZE_INDICATOR);
+    if size == 0 || size > 2 || data.len() <= size {
+        return None;
+    }
+
+    let value = data[1..=size]
+        .iter()
+        .fold(0, |value, &b| (value << 8) | usize::from(b));
+
+    Some((value, size + 1))
+}
@@ -0,0 +1,352 @@
+//! Conditional Access Support
+//!
+//! en50221 8.4.3
+//! The CA Support resource is used by the host to get the list of CA
+//! systems supported by the module and to send CA_PMT objects for programs
+//! to descramble. The module confirms descrambling with ca_pmt_reply.
+#![allow(dead_code)]
+
+use {
+    super::{apdu, CaDevice},
+    crate::{
+        si::{Descriptor, Pmt},
+        DvbError,
+    },
+    anyhow::{Context, Result},
+    std::{
+        collections::VecDeque,
+        time::{Duration, Instant},
+    },
+};
+
+pub use ca_cas_tag::*;
+
+/// en50221 Table 58: CA Support object tags
+mod ca_cas_tag {
+    pub const CA_INFO_ENQ: u32 = 0x9F8030;
+    pub const CA_INFO: u32 = 0x9F8031;
+    pub const CA_PMT: u32 = 0x9F8032;
+    pub const CA_PMT_REPLY: u32 = 0x9F8033;
+}
+
+/// Descriptor tag of the CA_descriptor
+const CA_DESCRIPTOR_TAG: u8 = 0x09;
+
+/// Time to wait for the ca_pmt_reply on the query
+const CA_PMT_REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// en50221 Table 25: ca_pmt_list_management values
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaPmtListManagement {
+    More = 0x00,
+    First = 0x01,
+    Last = 0x02,
+    /// Single program, replaces all previous programs
+    Only = 0x03,
+    /// Program is added to the list
+    Add = 0x04,
+    /// Program from the list is updated
+    Update = 0x05,
+}
+
+/// en50221 Table 26: ca_pmt_cmd_id values
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaPmtCmd {
+    OkDescrambling = 0x01,
+    OkMmi = 0x02,
+    Query = 0x03,
+    NotSelected = 0x04,
+}
+
+/// Appends CA descriptors with program_info_length or ES_info_length.
+/// If `ca_system_ids` is not empty only descriptors with listed CA_system_id are used
+fn encode_ca_descriptors(
+    descriptors: &[Descriptor],
+    ca_system_ids: &[u16],
+    cmd: CaPmtCmd,
+    buffer: &mut Vec<u8>,
+) -> Result<()> {
+    let begin = buffer.len();
+    buffer.extend_from_slice(&[0, 0]);
+
+    let mut empty = true;
+    for d in descriptors.iter().filter(|d| d.tag == CA_DESCRIPTOR_TAG && d.data.len() >= 4) {
+        let ca_system_id = u16::from_be_bytes([d.data[0], d.data[1]]);
+        if !ca_system_ids.is_empty() && !ca_system_ids.contains(&ca_system_id) {
+            continue;
+        }
+
+        if empty {
+            buffer.push(cmd as u8);
+            empty = false;
+        }
+        buffer.push(d.tag);
+        buffer.push(d.data.len() as u8);
+        buffer.extend_from_slice(&d.data);
+    }
+
+    let len = buffer.len() - begin - 2;
+    ensure!(len <= 0x0FFF, "CA: CA descriptors are too long");
+    buffer[begin..begin + 2].copy_from_slice(&(0xF000 | len as u16).to_be_bytes());
+
+    Ok(())
+}
+
+/// Builds CA_PMT object body from the Program Map Table.
+/// Only CA descriptors are included. If `ca_system_ids` is not empty
+/// only descriptors with listed CA_system_id are used
+pub fn encode_ca_pmt(
+    pmt: &Pmt,
+    list_management: CaPmtListManagement,
+    cmd: CaPmtCmd,
+    ca_system_ids: &[u16],
+) -> Result<Vec<u8>> {
+    let mut buffer = Vec::with_capacity(256);
+
+    buffer.push(list_management as u8);
+    buffer.extend_from_slice(&pmt.program_number.to_be_bytes());
+    // current_next_indicator is always 1
+    buffer.push(0xC1 | ((pmt.version_number & 0x1F) << 1));
+    encode_ca_descriptors(&pmt.descriptors, ca_system_ids, cmd, &mut buffer)?;
+
+    for stream in &pmt.streams {
+        buffer.push(stream.stream_type);
+        buffer.extend_from_slice(&(0xE000 | (stream.pid & 0x1FFF)).to_be_bytes());
+        encode_ca_descriptors(&stream.descriptors, ca_system_ids, cmd, &mut buffer)?;
+    }
+
+    Ok(buffer)
+}
+
+/// en50221 Table 27: CA_enable values
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaEnable {
+    /// Descrambling possible
+    Possible,
+    /// Descrambling possible under conditions (purchase dialogue)
+    PurchaseDialogue,
+    /// Descrambling possible under conditions (technical dialogue)
+    TechnicalDialogue,
+    /// Descrambling not possible (because no entitlement)
+    NoEntitlement,
+    /// Descrambling not possible (for technical reasons)
+    TechnicalReasons,
+    /// Reserved value
+    Reserved(u8),
+}
+
+impl CaEnable {
+    fn parse(value: u8) -> Option<Self> {
+        // CA_enable_flag
+        if value & 0x80 == 0 {
+            return None;
+        }
+
+        Some(match value & 0x7F {
+            0x01 => CaEnable::Possible,
+            0x02 => CaEnable::PurchaseDialogue,
+            0x03 => CaEnable::TechnicalDialogue,
+            0x71 => CaEnable::NoEntitlement,
+            0x73 => CaEnable::TechnicalReasons,
+            v => CaEnable::Reserved(v),
+        })
+    }
+
+    /// Returns `true` if module is able to descramble
+    pub fn is_possible(&self) -> bool {
+        matches!(
+            self,
+            CaEnable::Possible | CaEnable::PurchaseDialogue | CaEnable::TechnicalDialogue
+        )
+    }
+}
+
+/// Descrambling status of the elementary stream
+#[derive(Debug, Clone)]
+pub struct CaPmtReplyStream {
+    pub elementary_pid: u16,
+    /// `None` if status is defined at program level only
+    pub ca_enable: Option<CaEnable>,
+}
+
+/// Module reply to the CA_PMT with query or ok_descrambling command
+#[derive(Debug, Clone)]
+pub struct CaPmtReply {
+    pub program_number: u16,
+    pub version_number: u8,
+    pub current_next_indicator: bool,
+    /// Program level status. `None` if status defined per elementary stream
+    pub ca_enable: Option<CaEnable>,
+    pub streams: Vec<CaPmtReplyStream>,
+}
+
+impl CaPmtReply {
+    /// Parses ca_pmt_reply object body
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        ensure!(data.len() >= 4, "CA: ca_pmt_reply is too short");
+
+        let mut reply = CaPmtReply {
+            program_number: u16::from_be_bytes([data[0], data[1]]),
+            version_number: (data[2] >> 1) & 0x1F,
+            current_next_indicator: data[2] & 0x01 != 0,
+            ca_enable: CaEnable::parse(data[3]),
+            streams: Vec::new(),
+        };
+
+        for item in data[4..].chunks(3) {
+            ensure!(item.len() == 3, "CA: ca_pmt_reply has invalid size");
+
+            reply.streams.push(CaPmtReplyStream {
+                elementary_pid: u16::from_be_bytes([item[0] & 0x1F, item[1]]),
+                ca_enable: CaEnable::parse(item[2]),
+            });
+        }
+
+        Ok(reply)
+    }
+
+    /// Returns status of the elementary stream with fallback to the program level status
+    pub fn stream_ca_enable(&self, elementary_pid: u16) -> Option<CaEnable> {
+        self.streams
+            .iter()
+            .find(|s| s.elementary_pid == elementary_pid)
+            .and_then(|s| s.ca_enable)
+            .or(self.ca_enable)
+    }
+}
+
+/// CA Support session state
+#[derive(Debug, Default)]
+pub struct CasState {
+    session_id: Option<u16>,
+    /// CA_system_id list from the module
+    ca_system_ids: Vec<u16>,
+    /// Replies not yet taken by the application
+    pmt_replies: VecDeque<CaPmtReply>,
+    /// Programs sent to the module with CA_PMT
+    programs: Vec<u16>,
+}
+
+impl CasState {
+    /// Returns number of replies not yet taken
+    pub fn reply_count(&self) -> usize {
+        self.pmt_replies.len()
+    }
+
+    /// Takes replies received after the first `at` replies
+    pub fn split_replies(&mut self, at: usize) -> VecDeque<CaPmtReply> {
+        self.pmt_replies.split_off(at.min(self.pmt_replies.len()))
+    }
+}
+
+pub fn open(ca: &mut CaDevice, session_id: u16) -> Result<()> {
+    ca.cas = CasState {
+        session_id: Some(session_id),
+        ..Default::default()
+    };
+
+    apdu::send(ca, session_id, CA_INFO_ENQ, &[])
+}
+
+pub fn close(ca: &mut CaDevice) {
+    ca.cas = CasState::default();
+}
+
+/// Process CA Support object received from the module
+pub fn handle(ca: &mut CaDevice, _session_id: u16, tag: u32, data: &[u8]) -> Result<()> {
+    match tag {
+        CA_INFO => {
+            ca.cas.ca_system_ids = data
+                .chunks_exact(2)
+                .map(|v| u16::from_be_bytes([v[0], v[1]]))
+                .collect();
+        }
+        CA_PMT_REPLY => {
+            let reply = CaPmtReply::parse(data)?;
+            ca.cas.pmt_replies.push_back(reply);
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+impl CaDevice {
+    /// Returns CA_system_id list supported by the module
+    pub fn ca_system_ids(&self) -> &[u16] {
+        &self.cas.ca_system_ids
+    }
+
+    /// Takes the oldest ca_pmt_reply received by `poll`
+    pub fn take_pmt_reply(&mut self) -> Option<CaPmtReply> {
+        self.cas.pmt_replies.pop_front()
+    }
+
+    fn take_pmt_reply_for(&mut self, program_number: u16) -> Option<CaPmtReply> {
+        let index = self
+            .cas
+            .pmt_replies
+            .iter()
+            .position(|r| r.program_number == program_number)?;
+        self.cas.pmt_replies.remove(index)
+    }
+
+    /// Sends CA_PMT object built from the Program Map Table
+    pub fn send_ca_pmt(
+        &mut self,
+        pmt: &Pmt,
+        list_management: CaPmtListManagement,
+        cmd: CaPmtCmd,
+    ) -> Result<()> {
+        let session_id = self.cas.session_id.context("CA: CA support session is not opened")?;
+        let data = encode_ca_pmt(pmt, list_management, cmd, &self.cas.ca_system_ids)?;
+
+        apdu::send(self, session_id, CA_PMT, &data)?;
+
+        match list_management {
+            CaPmtListManagement::Only | CaPmtListManagement::First => {
+                self.cas.programs = vec![pmt.program_number];
+            }
+            _ if !self.cas.programs.contains(&pmt.program_number) => {
+                self.cas.programs.push(pmt.program_number);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Starts descrambling of the program
+    ///
+    /// First program is sent with `Only` list management, next programs
+    /// with `Add` and programs sent before with `Update`.
+    /// On `Query` waits for the module reply, otherwise returns reply
+    /// if the module sent it on the next poll
+    pub fn descramble_program(&mut self, pmt: &Pmt, cmd: CaPmtCmd) -> Result<Option<CaPmtReply>> {
+        let list_management = if self.cas.programs.is_empty() {
+            CaPmtListManagement::Only
+        } else if self.cas.programs.contains(&pmt.program_number) {
+            CaPmtListManagement::Update
+        } else {
+            CaPmtListManagement::Add
+        };
+
+        self.send_ca_pmt(pmt, list_management, cmd)?;
+
+        if cmd != CaPmtCmd::Query {
+            self.poll()?;
+            return Ok(self.take_pmt_reply_for(pmt.program_number));
+        }
+
+        let deadline = Instant::now() + CA_PMT_REPLY_TIMEOUT;
+        loop {
+            if let Some(reply) = self.take_pmt_reply_for(pmt.program_number) {
+                return Ok(Some(reply));
+            }
+            if Instant::now() >= deadline {
+                return Err(DvbError::Timeout).context("CA: waiting for ca_pmt_reply");
+            }
+            self.poll()?;
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Date-Time
+//!
+//! en50221 8.5.1
+//! The module requests current time with date_time_enq. The host replies
+//! with date_time once or periodically with the requested interval.
+#![allow(dead_code)]
+
+use {
+    super::{apdu, CaDevice},
+    crate::util,
+    anyhow::{Context, Result},
+    std::time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+pub use ca_datetime_tag::*;
+
+/// en50221 Table 58: Date-Time object tags
+mod ca_datetime_tag {
+    pub const DATE_TIME_ENQ: u32 = 0x9F8440;
+    pub const DATE_TIME: u32 = 0x9F8441;
+}
+
+/// Date-Time session state
+#[derive(Debug, Default)]
+pub struct DateTimeState {
+    session_id: Option<u16>,
+    /// Interval of the periodic date_time objects. `None` to send once
+    interval: Option<Duration>,
+    /// Time of the last date_time object
+    last_sent: Option<Instant>,
+}
+
+/// Encodes UTC_time: 16 bit MJD and 24 bit BCD time
+fn encode_utc_time(time: SystemTime) -> Result<[u8; 5]> {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .context("CA: system time before 1970")?
+        .as_secs();
+
+    let mjd = util::unix_days_to_mjd(secs / 86_400);
+    let secs = secs % 86_400;
+
+    let bcd = |v: u64| ((v / 10) << 4 | (v % 10)) as u8;
+
+    Ok([
+        (mjd >> 8) as u8,
+        mjd as u8,
+        bcd(secs / 3_600),
+        bcd(secs / 60 % 60),
+        bcd(secs % 60),
+    ])
+}
+
+pub fn open(ca: &mut CaDevice, session_id: u16) {
+    ca.datetime = DateTimeState {
+        session_id: Some(session_id),
+        ..Default::default()
+    };
+}
+
+pub fn close(ca: &mut CaDevice) {
+    ca.datetime = DateTimeState::default();
+}
+
+fn send_date_time(ca: &mut CaDevice, session_id: u16) -> Result<()> {
+    let utc_time = encode_utc_time((ca.clock)())?;
+    apdu::send(ca, session_id, DATE_TIME, &utc_time)?;
+    ca.datetime.last_sent = Some(Instant::now());
+
+    Ok(())
+}
+
+/// Process Date-Time object received from the module
+pub fn handle(ca: &mut CaDevice, session_id: u16, tag: u32, data: &[u8]) -> Result<()> {
+    if tag == DATE_TIME_ENQ {
+        // response_interval in seconds, 0 - reply once
+        ca.datetime.interval = match data.first() {
+            Some(&v) if v != 0 => Some(Duration::from_secs(u64::from(v))),
+            _ => None,
+        };
+        send_date_time(ca, session_id)?;
+    }
+
+    Ok(())
+}
+
+/// Sends periodic date_time object
+pub fn manage(ca: &mut CaDevice) -> Result<()> {
+    let state = &ca.datetime;
+
+    if let (Some(session_id), Some(interval), Some(last_sent)) =
+        (state.session_id, state.interval, state.last_sent)
+    {
+        if last_sent.elapsed() >= interval {
+            send_date_time(ca, session_id)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl CaDevice {
+    /// Sets source of the current time for the Date-Time resource.
+    /// Default is `SystemTime::now`
+    pub fn set_clock(&mut self, clock: fn() -> SystemTime) {
+        self.clock = clock;
+    }
+}
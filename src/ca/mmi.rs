@@ -0,0 +1,193 @@
+//! Man-Machine Interface
+//!
+//! en50221 8.6
+//! The MMI resource allows the module to interact with the user: display
+//! text, menus and lists, and request input with enquiry objects.
+#![allow(dead_code)]
+
+use {
+    super::{apdu, CaDevice},
+    crate::{si::decode_dvb_string, DvbError},
+    anyhow::{Context, Result},
+    std::time::{Duration, Instant},
+};
+
+pub use ca_mmi_tag::*;
+
+/// Time to wait for the module response
+const MMI_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// en50221 Table 58: MMI object tags
+mod ca_mmi_tag {
+    pub const MMI_CLOSE: u32 = 0x9F8800;
+    pub const MMI_DISPLAY_CONTROL: u32 = 0x9F8801;
+    pub const MMI_DISPLAY_REPLY: u32 = 0x9F8802;
+    pub const MMI_TEXT_LAST: u32 = 0x9F8803;
+    pub const MMI_TEXT_MORE: u32 = 0x9F8804;
+    pub const MMI_KEYPAD_CONTROL: u32 = 0x9F8805;
+    pub const MMI_KEYPRESS: u32 = 0x9F8806;
+    pub const MMI_ENQ: u32 = 0x9F8807;
+    pub const MMI_ANSW: u32 = 0x9F8808;
+    pub const MMI_MENU_LAST: u32 = 0x9F8809;
+    pub const MMI_MENU_MORE: u32 = 0x9F880A;
+    pub const MMI_MENU_ANSW: u32 = 0x9F880B;
+    pub const MMI_LIST_LAST: u32 = 0x9F880C;
+    pub const MMI_LIST_MORE: u32 = 0x9F880D;
+}
+
+/// en50221 8.6.5.3: display_control_cmd set_mmi_mode
+const DISPLAY_CMD_SET_MMI_MODE: u8 = 0x01;
+/// en50221 8.6.5.4: display_reply_id mmi_mode_ack
+const DISPLAY_REPLY_MMI_MODE_ACK: u8 = 0x01;
+/// en50221 8.6.5.8: answ_id answer
+const ANSW_ID_ANSWER: u8 = 0x01;
+/// Length of the answer is unknown
+const ANSWER_LENGTH_UNKNOWN: u8 = 0xFF;
+
+/// Enquiry object: the module requests user input
+#[derive(Debug, Clone)]
+pub struct MmiEnq {
+    /// Input should not be displayed, for example PIN
+    pub blind: bool,
+    /// Expected number of characters in the answer (0xFF if unknown)
+    pub expected_answer_length: u8,
+    /// Prompt text, decoded with EN 300 468 Annex A character tables
+    pub text: String,
+}
+
+/// MMI session state
+#[derive(Debug, Default)]
+pub struct MmiState {
+    session_id: Option<u16>,
+    /// Enquiry waiting for the answer
+    enq: Option<MmiEnq>,
+    /// Number of received objects
+    received: u32,
+    /// Tag of the last received object
+    last_tag: u32,
+}
+
+impl MmiState {
+    /// Returns number of received objects
+    pub fn received(&self) -> u32 {
+        self.received
+    }
+}
+
+pub fn open(ca: &mut CaDevice, session_id: u16) {
+    ca.mmi = MmiState {
+        session_id: Some(session_id),
+        ..Default::default()
+    };
+}
+
+pub fn close(ca: &mut CaDevice) {
+    ca.mmi = MmiState::default();
+}
+
+/// Process MMI object received from the module
+pub fn handle(ca: &mut CaDevice, session_id: u16, tag: u32, data: &[u8]) -> Result<()> {
+    ca.mmi.received = ca.mmi.received.wrapping_add(1);
+    ca.mmi.last_tag = tag;
+
+    match tag {
+        MMI_DISPLAY_CONTROL if data.len() >= 2 && data[0] == DISPLAY_CMD_SET_MMI_MODE => {
+            let reply = [DISPLAY_REPLY_MMI_MODE_ACK, data[1]];
+            apdu::send(ca, session_id, MMI_DISPLAY_REPLY, &reply)?;
+        }
+        MMI_ENQ => {
+            if data.len() < 2 {
+                return Err(anyhow!("CA MMI: invalid enq size"));
+            }
+
+            ca.mmi.enq = Some(MmiEnq {
+                blind: data[0] & 0x01 != 0,
+                expected_answer_length: data[1],
+                text: decode_dvb_string(&data[2..]),
+            });
+        }
+        MMI_CLOSE => {
+            ca.mmi.enq = None;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+impl CaDevice {
+    /// Returns enquiry from the module waiting for the user input
+    pub fn pending_enq(&self) -> Option<&MmiEnq> {
+        self.mmi.enq.as_ref()
+    }
+
+    /// Sends answer to the pending enquiry
+    fn send_answ(&mut self, answer: &[u8]) -> Result<()> {
+        let session_id = self.mmi.session_id.context("CA: MMI session is not opened")?;
+        self.mmi.enq = None;
+
+        let mut data = Vec::with_capacity(1 + answer.len());
+        data.push(ANSW_ID_ANSWER);
+        data.extend_from_slice(answer);
+
+        apdu::send(self, session_id, MMI_ANSW, &data)
+    }
+
+    /// Submits PIN to the module
+    ///
+    /// Waits for the PIN enquiry from the module, sends the answer and
+    /// checks the module reaction. Only blind enquiries are answered,
+    /// enquiry without the blind flag is not a PIN request.
+    ///
+    /// MMI has no PIN result status. PIN is taken as rejected with
+    /// `DvbError::CaPinRejected` only if the next object from the module is
+    /// a new blind enquiry, it remains available with `pending_enq`.
+    /// Any other object or no reply in the MMI timeout is taken as accepted
+    pub fn enter_pin(&mut self, pin: &str) -> Result<()> {
+        ensure!(
+            !pin.is_empty() && pin.bytes().all(|b| b.is_ascii_digit()),
+            "CA: PIN should contain digits only"
+        );
+
+        let deadline = Instant::now() + MMI_TIMEOUT;
+        let enq = loop {
+            if let Some(enq) = &self.mmi.enq {
+                break enq;
+            }
+            if Instant::now() >= deadline {
+                return Err(DvbError::Timeout).context("CA: waiting for PIN request");
+            }
+            self.poll()?;
+        };
+
+        ensure!(enq.blind, "CA: pending enquiry is not a PIN request");
+
+        if enq.expected_answer_length != 0
+            && enq.expected_answer_length != ANSWER_LENGTH_UNKNOWN
+        {
+            ensure!(
+                pin.len() == usize::from(enq.expected_answer_length),
+                "CA: PIN should be {} digits",
+                enq.expected_answer_length
+            );
+        }
+
+        self.send_answ(pin.as_bytes())?;
+
+        // module may reply with a next object or do nothing on success
+        let received = self.mmi.received;
+        let deadline = Instant::now() + MMI_TIMEOUT;
+        while self.mmi.received == received && Instant::now() < deadline {
+            self.poll()?;
+        }
+
+        let repeated = self.mmi.received != received
+            && self.mmi.last_tag == MMI_ENQ
+            && matches!(&self.mmi.enq, Some(enq) if enq.blind);
+        if repeated {
+            return Err(DvbError::CaPinRejected.into());
+        }
+
+        Ok(())
+    }
+}
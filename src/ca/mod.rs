@@ -1,26 +1,61 @@
 #![allow(dead_code)]
 mod apdu;
 mod asn1;
+mod cas;
+mod datetime;
+mod mmi;
+mod rm;
 mod spdu;
 pub mod sys;
 mod tpdu;
 
 use {
     anyhow::{Context, Result},
-    nix::{ioctl_none, ioctl_read},
+    nix::{ioctl_none, ioctl_read, ioctl_write_ptr, unistd::close},
     std::{
         fs::{File, OpenOptions},
         os::unix::{
             fs::OpenOptionsExt,
-            io::{AsRawFd, RawFd},
+            io::{AsRawFd, IntoRawFd, RawFd},
         },
         thread,
-        time::Duration,
+        time::{Duration, Instant, SystemTime},
     },
     sys::*,
+    crate::{ioctl, util, DvbDevice, DvbError},
 };
 
-const CA_DELAY: Duration = Duration::from_millis(100);
+pub use {
+    cas::{
+        encode_ca_pmt, CaEnable, CaPmtCmd, CaPmtListManagement, CaPmtReply, CaPmtReplyStream,
+    },
+    mmi::MmiEnq,
+};
+
+/// Default path template of the CA device
+pub const CA_PATH_TEMPLATE: &str = "/dev/dvb/adapter{adapter}/ca{device}";
+
+/// Default interval between slot state checks: after reset, on open and in `poll`
+pub const CA_DELAY: Duration = Duration::from_millis(100);
+
+/// Default interval between poll TPDUs on the idle link
+pub const CA_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time to wait for the module response on the transport layer
+const TPDU_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Event reported by `poll_once`
+#[derive(Debug, Clone)]
+pub enum CaEvent {
+    /// Module is inserted and ready
+    ModuleReady,
+    /// Module was removed. Sessions are closed
+    ModuleRemoved,
+    /// Enquiry from the module waiting for the user input
+    MmiEnq(MmiEnq),
+    /// Module reply to the CA_PMT
+    PmtReply(CaPmtReply),
+}
 
 #[derive(Debug)]
 pub struct CaDevice {
@@ -29,6 +64,29 @@ pub struct CaDevice {
 
     file: File,
     slot: CaSlotInfo,
+    slot_count: u32,
+
+    /// module was present on the last slot check
+    module_present: bool,
+    /// transport connection is established
+    tc_active: bool,
+    /// interval between poll TPDUs
+    poll_interval: Duration,
+    /// interval between slot state checks
+    slot_delay: Duration,
+    /// time of the last poll TPDU
+    last_poll: Instant,
+    /// poll TPDU sent by `poll_once` and waiting for the module reply
+    poll_sent: Option<Instant>,
+    /// incomplete SPDU received with TT_DATA_MORE
+    tpdu_buffer: Vec<u8>,
+    sessions: Vec<apdu::CaSession>,
+    rm: rm::RmState,
+    cas: cas::CasState,
+    datetime: datetime::DateTimeState,
+    mmi: mmi::MmiState,
+    /// source of the current time for the Date-Time resource
+    clock: fn() -> SystemTime,
 }
 
 impl AsRawFd for CaDevice {
@@ -38,8 +96,35 @@ impl AsRawFd for CaDevice {
     }
 }
 
+impl IntoRawFd for CaDevice {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
+impl DvbDevice for CaDevice {
+    #[inline]
+    fn adapter(&self) -> u32 {
+        self.adapter
+    }
+
+    #[inline]
+    fn device(&self) -> u32 {
+        self.device
+    }
+}
+
 impl CaDevice {
-    /// Sends reset command to CA device
+    /// Closes device and reports error unlike drop
+    pub fn close(self) -> Result<()> {
+        close(self.into_raw_fd()).context("CA: close")?;
+
+        Ok(())
+    }
+
+    /// Sends reset command to CA device.
+    /// Module needs time to initialize, see `wait_reset_complete`
     #[inline]
     pub fn reset(&mut self) -> Result<()> {
         // CA_RESET
@@ -49,11 +134,46 @@ impl CaDevice {
             b'o',
             128
         );
-        unsafe { ca_reset(self.as_raw_fd()) }.context("CA: failed to reset")?;
+        ioctl::retry_eintr(|| unsafe { ca_reset(self.as_raw_fd()) })
+            .context("CA: failed to reset")?;
 
         Ok(())
     }
 
+    /// Waits until the module is ready after `reset`, checking slot state
+    /// every slot delay. Sessions are closed, transport connection is
+    /// established again on the next `poll`.
+    ///
+    /// Some drivers report the slot as empty while the module restarts,
+    /// so the module may be absent until `timeout`. Returns
+    /// `DvbError::CaModuleNotFound` if the slot is still empty at `timeout`
+    /// and `DvbError::CaResetTimeout` if the module is present but not ready
+    pub fn wait_reset_complete(&mut self, timeout: Duration) -> Result<()> {
+        self.reset_link();
+        // poll initializes the link when module becomes ready
+        self.slot.flags = CA_CI_MODULE_NOT_FOUND;
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let info = self.get_slot_info_for(self.slot.slot_num)?;
+            if info.flags & CA_CI_MODULE_READY != 0 {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                let error = if info.flags & CA_CI_MODULE_PRESENT == 0 {
+                    DvbError::CaModuleNotFound
+                } else {
+                    DvbError::CaResetTimeout
+                };
+                return Err(error).context("CA: wait reset");
+            }
+            thread::sleep(self.slot_delay.min(deadline - now));
+        }
+    }
+
     /// Gets CA capabilities
     #[inline]
     pub fn get_caps(&self, caps: &mut CaCaps) -> Result<()> {
@@ -65,7 +185,7 @@ impl CaDevice {
             129,
             CaCaps
         );
-        unsafe { ca_get_cap(self.as_raw_fd(), caps as *mut _) }
+        ioctl::retry_eintr(|| unsafe { ca_get_cap(self.as_raw_fd(), caps as *mut _) })
             .context("CA: failed to get caps")?;
 
         Ok(())
@@ -74,6 +194,20 @@ impl CaDevice {
     /// Gets CA slot information
     #[inline]
     pub fn get_slot_info(&mut self) -> Result<()> {
+        self.slot = self.get_slot_info_for(self.slot.slot_num)?;
+
+        Ok(())
+    }
+
+    /// Gets information for any slot of the CA device
+    pub fn get_slot_info_for(&self, slot: u32) -> Result<CaSlotInfo> {
+        ensure!(slot < self.slot_count, "CA: slot {} not found", slot);
+
+        let mut info = CaSlotInfo {
+            slot_num: slot,
+            ..Default::default()
+        };
+
         // CA_GET_SLOT_INFO
         ioctl_read!(
             #[inline]
@@ -82,15 +216,165 @@ impl CaDevice {
             130,
             CaSlotInfo
         );
-        unsafe { ca_get_slot_info(self.as_raw_fd(), &mut self.slot as *mut _) }
+        ioctl::retry_eintr(|| unsafe {
+            ca_get_slot_info(self.as_raw_fd(), &mut info as *mut _)
+        })
             .context("CA: failed to get slot info")?;
 
+        Ok(info)
+    }
+
+    /// Gets information for all slots of the CA device
+    pub fn get_slots(&self) -> Result<Vec<CaSlotInfo>> {
+        (0..self.slot_count)
+            .map(|slot| self.get_slot_info_for(slot))
+            .collect()
+    }
+
+    /// Returns total number of slots on the CA device
+    #[inline]
+    pub fn get_slot_count(&self) -> u32 {
+        self.slot_count
+    }
+
+    /// Sends raw message to the CAM (high level CA_CI interface)
+    ///
+    /// `msg` is a message no more 256 bytes length
+    pub fn send_msg(&self, msg: &[u8]) -> Result<()> {
+        let mut cmd = CaMsg::default();
+        ensure!(
+            msg.len() <= cmd.msg.len(),
+            "CA: message is too large ({} > {})",
+            msg.len(),
+            cmd.msg.len()
+        );
+
+        cmd.msg[0..msg.len()].copy_from_slice(msg);
+        cmd.length = msg.len() as u32;
+
+        // CA_SEND_MSG
+        ioctl_write_ptr!(
+            #[inline]
+            ca_send_msg,
+            b'o',
+            133,
+            CaMsg
+        );
+        ioctl::retry_eintr(|| unsafe { ca_send_msg(self.as_raw_fd(), &cmd as *const _) })
+            .context("CA: failed to send message")?;
+
         Ok(())
     }
 
-    /// Attempts to open a CA device
+    /// Receives raw message from the CAM (high level CA_CI interface)
+    pub fn get_msg(&self) -> Result<Vec<u8>> {
+        let mut cmd = CaMsg::default();
+
+        // CA_GET_MSG
+        ioctl_read!(
+            #[inline]
+            ca_get_msg,
+            b'o',
+            132,
+            CaMsg
+        );
+        ioctl::retry_eintr(|| unsafe { ca_get_msg(self.as_raw_fd(), &mut cmd as *mut _) })
+            .context("CA: failed to get message")?;
+
+        let len = cmd.length as usize;
+        ensure!(len <= cmd.msg.len(), "CA: invalid message length {}", len);
+
+        Ok(cmd.msg[0..len].to_vec())
+    }
+
+    /// Attempts to open a CA device. Device is opened even if the module is not inserted
+    #[inline]
     pub fn open(adapter: u32, device: u32, slot: u32) -> Result<CaDevice> {
-        let path = format!("/dev/dvb/adapter{}/ca{}", adapter, device);
+        Self::open_with_template(adapter, device, slot, CA_PATH_TEMPLATE)
+    }
+
+    /// Attempts to open a CA device and waits for the module in the slot.
+    ///
+    /// - `None` - device is opened even if the module is not inserted
+    /// - `Some(timeout)` - waits for the module no more than `timeout`,
+    ///   returns `DvbError::CaModuleNotFound` if module is not inserted.
+    ///   Zero timeout fails immediately on the empty slot
+    pub fn open_with_wait(
+        adapter: u32,
+        device: u32,
+        slot: u32,
+        wait_for_module: Option<Duration>,
+    ) -> Result<CaDevice> {
+        let ca = Self::open(adapter, device, slot)?;
+
+        if let Some(timeout) = wait_for_module {
+            let deadline = Instant::now() + timeout;
+
+            loop {
+                let info = ca.get_slot_info_for(slot)?;
+                if info.flags & CA_CI_MODULE_PRESENT != 0 {
+                    break;
+                }
+                if Instant::now() >= deadline {
+                    return Err(DvbError::CaModuleNotFound).context("CA: open");
+                }
+                thread::sleep(ca.slot_delay);
+            }
+        }
+
+        Ok(ca)
+    }
+
+    /// Attempts to open a CA device with the path built from `template`,
+    /// for example `/dev/dvb/adapter{adapter}/ca{device}`.
+    /// Template should contain both placeholders
+    pub fn open_with_template(
+        adapter: u32,
+        device: u32,
+        slot: u32,
+        template: &str,
+    ) -> Result<CaDevice> {
+        Self::open_with_slot_delay(adapter, device, slot, template, CA_DELAY)
+    }
+
+    /// Creates device on the opened file without slot setup
+    fn with_file(adapter: u32, device: u32, file: File, slot_delay: Duration) -> CaDevice {
+        CaDevice {
+            adapter,
+            device,
+
+            file,
+            slot: CaSlotInfo::default(),
+            slot_count: 0,
+
+            module_present: false,
+            tc_active: false,
+            poll_interval: CA_POLL_INTERVAL,
+            slot_delay,
+            last_poll: Instant::now(),
+            poll_sent: None,
+            tpdu_buffer: Vec::new(),
+            sessions: Vec::new(),
+            rm: rm::RmState::default(),
+            cas: cas::CasState::default(),
+            datetime: datetime::DateTimeState::default(),
+            mmi: mmi::MmiState::default(),
+            clock: SystemTime::now,
+        }
+    }
+
+    /// Same as `open_with_template` with interval between slot state checks.
+    /// Slow modules need longer time to settle after reset
+    pub fn open_with_slot_delay(
+        adapter: u32,
+        device: u32,
+        slot: u32,
+        template: &str,
+        slot_delay: Duration,
+    ) -> Result<CaDevice> {
+        ensure!(!slot_delay.is_zero(), "CA: slot delay should not be zero");
+
+        let path = util::device_path(template, adapter, device).context("CA: open")?;
         let file = OpenOptions::new()
             .read(true)
             .write(true)
@@ -98,17 +382,11 @@ impl CaDevice {
             .open(&path)
             .with_context(|| format!("CA: failed to open device {}", &path))?;
 
-        let mut ca = CaDevice {
-            adapter,
-            device,
-
-            file,
-            slot: CaSlotInfo::default(),
-        };
+        let mut ca = CaDevice::with_file(adapter, device, file, slot_delay);
 
         ca.reset()?;
 
-        thread::sleep(CA_DELAY);
+        thread::sleep(ca.slot_delay);
 
         let mut caps = CaCaps::default();
 
@@ -119,9 +397,11 @@ impl CaDevice {
                 break;
             }
 
-            thread::sleep(CA_DELAY);
+            thread::sleep(ca.slot_delay);
         }
 
+        ca.slot_count = caps.slot_num;
+
         if slot >= caps.slot_num {
             return Err(anyhow!("CA: slot {} not found", slot));
         }
@@ -129,7 +409,7 @@ impl CaDevice {
         ca.slot.slot_num = slot;
         ca.get_slot_info()?;
 
-        if ca.slot.slot_type != CA_CI_LINK {
+        if ca.slot.slot_type != CA_CI_LINK && ca.slot.slot_type != CA_CI {
             return Err(anyhow!("CA: incompatible interface"));
         }
 
@@ -139,30 +419,213 @@ impl CaDevice {
         Ok(ca)
     }
 
+    /// Returns interval between poll TPDUs
+    #[inline]
+    pub fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Sets interval between poll TPDUs. Module stops working if
+    /// the host does not poll it, so interval should be short enough.
+    /// Default is CA_POLL_INTERVAL
+    pub fn set_poll_interval(&mut self, interval: Duration) -> Result<()> {
+        ensure!(
+            !interval.is_zero() && interval < TPDU_TIMEOUT,
+            "CA: poll interval should be in range (0, {:?})",
+            TPDU_TIMEOUT
+        );
+
+        self.poll_interval = interval;
+
+        Ok(())
+    }
+
+    /// Returns interval between slot state checks
+    #[inline]
+    pub fn slot_delay(&self) -> Duration {
+        self.slot_delay
+    }
+
+    /// Sets interval between slot state checks. `poll` waits for the module
+    /// message no more than this interval before the link is established.
+    /// Default is CA_DELAY
+    pub fn set_slot_delay(&mut self, delay: Duration) -> Result<()> {
+        ensure!(!delay.is_zero(), "CA: slot delay should not be zero");
+
+        self.slot_delay = delay;
+
+        Ok(())
+    }
+
+    /// Checks module state and processes messages from the module
+    ///
+    /// Should be called periodically. On the active link sends poll TPDU
+    /// once per poll interval and waits for the module reply.
+    ///
+    /// Returns `DvbError::CaModuleNotFound` if the slot is empty and
+    /// `DvbError::CaModuleRemoved` once when the module is pulled out
+    pub fn poll(&mut self) -> Result<()> {
+        self.poll_timer()?;
+
+        if self.slot.slot_type != CA_CI_LINK || self.slot.flags & CA_CI_MODULE_READY == 0 {
+            return Ok(());
+        }
+
+        if !self.tc_active {
+            if util::wait_readable(self.as_raw_fd(), Some(self.slot_delay)).context("CA: poll")? {
+                self.poll_event()?;
+            }
+            return Ok(());
+        }
+
+        let result = self.poll_link();
+        self.check_link(result)
+    }
+
+    /// Processes available data from the module without waiting and returns events
+    ///
+    /// Non-blocking variant of `poll` for the event loops: CA device fd could be
+    /// polled together with other devices. Should be called when fd is readable
+    /// and at least once per poll interval. Module replies to the poll TPDU and
+    /// to the data request are processed on the next call. Replies taken as
+    /// `CaEvent::PmtReply` are not available with `take_pmt_reply`
+    pub fn poll_once(&mut self) -> Result<Vec<CaEvent>> {
+        let was_ready = self.slot.flags & CA_CI_MODULE_READY != 0;
+        let mmi_received = self.mmi.received();
+        let pmt_replies = self.cas.reply_count();
+
+        let result = self.poll_timer().and_then(|_| self.poll_link_once());
+        let result = self.check_link(result);
+
+        let mut events = Vec::new();
+
+        // empty slot is not an error for the event loop
+        let result = match result.as_ref().err().and_then(|e| e.downcast_ref()) {
+            Some(DvbError::CaModuleRemoved) => {
+                events.push(CaEvent::ModuleRemoved);
+                Ok(())
+            }
+            Some(DvbError::CaModuleNotFound) => Ok(()),
+            _ => result,
+        };
+
+        if !was_ready && self.slot.flags & CA_CI_MODULE_READY != 0 {
+            events.push(CaEvent::ModuleReady);
+        }
+        if self.mmi.received() != mmi_received {
+            if let Some(enq) = self.pending_enq() {
+                events.push(CaEvent::MmiEnq(enq.clone()));
+            }
+        }
+        events.extend(self.cas.split_replies(pmt_replies).into_iter().map(CaEvent::PmtReply));
+
+        result.map(|_| events)
+    }
+
+    /// Processes readable data and sends poll TPDU on the poll interval
+    fn poll_link_once(&mut self) -> Result<()> {
+        if self.slot.slot_type != CA_CI_LINK || self.slot.flags & CA_CI_MODULE_READY == 0 {
+            return Ok(());
+        }
+
+        if util::wait_readable(self.as_raw_fd(), Some(Duration::ZERO)).context("CA: poll")? {
+            self.poll_sent = None;
+            // reply to TT_RCV is processed on the next call
+            if tpdu::read(self, Duration::ZERO)? {
+                self.poll_sent = Some(Instant::now());
+            }
+        }
+
+        if !self.tc_active {
+            return Ok(());
+        }
+
+        match self.poll_sent {
+            Some(sent) if sent.elapsed() >= TPDU_TIMEOUT => {
+                return Err(DvbError::CaTpduTimeout.into());
+            }
+            Some(_) => {}
+            None if self.last_poll.elapsed() >= self.poll_interval => {
+                self.last_poll = Instant::now();
+                tpdu::send(self, self.slot.slot_num as u8, tpdu::TT_DATA_LAST, &[])?;
+                self.poll_sent = Some(self.last_poll);
+            }
+            None => {}
+        }
+
+        apdu::manage(self)
+    }
+
+    /// Drops transport connection if the module does not reply
+    fn check_link(&mut self, result: Result<()>) -> Result<()> {
+        let error = result.as_ref().err().and_then(|e| e.downcast_ref());
+        if let Some(DvbError::CaTpduTimeout) = error {
+            self.reset_link();
+            // transport connection will be created again on the next poll
+            self.slot.flags = CA_CI_MODULE_NOT_FOUND;
+        }
+
+        result
+    }
+
+    /// Sends poll TPDU on the poll interval and processes the module reply
+    fn poll_link(&mut self) -> Result<()> {
+        if let Some(delay) = self.poll_interval.checked_sub(self.last_poll.elapsed()) {
+            thread::sleep(delay);
+        }
+        self.last_poll = Instant::now();
+
+        tpdu::send(self, self.slot.slot_num as u8, tpdu::TT_DATA_LAST, &[])?;
+
+        // module should reply to each TPDU
+        if !util::wait_readable(self.as_raw_fd(), Some(TPDU_TIMEOUT)).context("CA: poll")? {
+            return Err(DvbError::CaTpduTimeout.into());
+        }
+
+        self.poll_event()?;
+
+        apdu::manage(self)
+    }
+
+    /// Drops transport connection and all sessions
+    fn reset_link(&mut self) {
+        self.tc_active = false;
+        self.poll_sent = None;
+        self.tpdu_buffer.clear();
+        self.sessions.clear();
+        self.rm = rm::RmState::default();
+        self.cas = cas::CasState::default();
+        self.datetime = datetime::DateTimeState::default();
+        self.mmi = mmi::MmiState::default();
+    }
+
     fn poll_timer(&mut self) -> Result<()> {
         let flags = self.slot.flags;
 
         self.get_slot_info()?;
 
-        match self.slot.flags {
-            CA_CI_MODULE_PRESENT => {
-                if flags == CA_CI_MODULE_READY {
-                    // TODO: de-init
-                }
-                return Ok(());
+        let is_ready = self.slot.flags & CA_CI_MODULE_READY != 0;
+        let was_ready = flags & CA_CI_MODULE_READY != 0;
+
+        let is_present = self.slot.flags & CA_CI_MODULE_PRESENT != 0;
+        let was_present = std::mem::replace(&mut self.module_present, is_present);
+
+        if !is_ready {
+            if was_ready || (was_present && !is_present) {
+                self.reset_link();
             }
-            CA_CI_MODULE_READY => {
-                if flags != CA_CI_MODULE_READY {
-                    tpdu::init(self, self.slot.slot_num as u8)?;
+            if !is_present {
+                if was_present {
+                    return Err(DvbError::CaModuleRemoved.into());
                 }
+                return Err(DvbError::CaModuleNotFound.into());
             }
-            CA_CI_MODULE_NOT_FOUND => {
-                return Err(anyhow!("CA: module not found"));
-            }
-            _ => {
-                return Err(anyhow!("CA: invalid slot flags"));
-            }
-        };
+            return Ok(());
+        }
+
+        if !was_ready && self.slot.slot_type == CA_CI_LINK {
+            tpdu::init(self, self.slot.slot_num as u8)?;
+        }
 
         // TODO: check queue?
 
@@ -170,8 +633,102 @@ impl CaDevice {
     }
 
     fn poll_event(&mut self) -> Result<()> {
-        // TODO: tpdu read
+        if tpdu::read(self, TPDU_TIMEOUT)? {
+            return Err(DvbError::CaTpduTimeout.into());
+        }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub(super) mod tests {
+    use {
+        super::*,
+        std::{
+            io::{Read, Seek, SeekFrom},
+            sync::atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    /// CA device on the temporary file, written TPDUs are read with `sent_spdu`
+    pub(super) fn ca_device() -> CaDevice {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "libdvb-ca-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut ca = CaDevice::with_file(0, 0, file, CA_DELAY);
+        ca.tc_active = true;
+        ca
+    }
+
+    /// Returns SPDUs written by the host since the previous call
+    pub(super) fn sent_spdu(ca: &CaDevice) -> Vec<Vec<u8>> {
+        let mut file = &ca.file;
+        let mut data = Vec::new();
+        file.seek(SeekFrom::Start(0)).unwrap();
+        file.read_to_end(&mut data).unwrap();
+        file.set_len(0).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+
+        let mut result = Vec::new();
+        let mut rest = &data[..];
+        while !rest.is_empty() {
+            // slot_id, t_c_id, tag, length, t_c_id
+            assert_eq!(rest[2], tpdu::TT_DATA_LAST);
+            let (len, skip) = asn1::decode(&rest[3..]).unwrap();
+            let begin = 3 + skip + 1;
+            let end = 3 + skip + len;
+            result.push(rest[begin..end].to_vec());
+            rest = &rest[end..];
+        }
+
+        result
+    }
+
+    /// Returns APDU sent by the host: session id, tag and body
+    pub(super) fn parse_apdu(spdu: &[u8]) -> (u16, u32, Vec<u8>) {
+        assert_eq!(spdu[0], spdu::ST_SESSION_NUMBER);
+        let session_id = u16::from_be_bytes([spdu[2], spdu[3]]);
+        let tag = u32::from_be_bytes([0, spdu[4], spdu[5], spdu[6]]);
+        let (len, skip) = asn1::decode(&spdu[7..]).unwrap();
+        (session_id, tag, spdu[7 + skip..7 + skip + len].to_vec())
+    }
+
+    /// Builds APDU sent by the module in the session
+    pub(super) fn module_apdu(session_id: u16, tag: u32, data: &[u8]) -> Vec<u8> {
+        let mut spdu = vec![spdu::ST_SESSION_NUMBER, 2];
+        spdu.extend_from_slice(&session_id.to_be_bytes());
+        spdu.extend_from_slice(&tag.to_be_bytes()[1..]);
+        asn1::encode(data.len() as u16, &mut spdu);
+        spdu.extend_from_slice(data);
+        spdu
+    }
+
+    /// Sends open_session_request for `resource_id` from the module.
+    /// Returns session status, session id and SPDUs sent by the host after the response
+    pub(super) fn open_session(ca: &mut CaDevice, resource_id: u32) -> (u8, u16, Vec<Vec<u8>>) {
+        let mut request = vec![spdu::ST_OPEN_SESSION_REQUEST, 4];
+        request.extend_from_slice(&resource_id.to_be_bytes());
+        spdu::handle(ca, 0, &request).unwrap();
+
+        let mut sent = sent_spdu(ca);
+        let response = sent.remove(0);
+        assert_eq!(response[0], spdu::ST_OPEN_SESSION_RESPONSE);
+        assert_eq!(response[3..7], resource_id.to_be_bytes());
+
+        (response[2], u16::from_be_bytes([response[7], response[8]]), sent)
+    }
+}
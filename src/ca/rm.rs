@@ -0,0 +1,118 @@
+//! Resource Manager
+//!
+//! en50221 8.4.1
+//! The Resource Manager session is opened by the module first. Host and
+//! module exchange lists of the provided resources with profile objects,
+//! after that the module opens sessions to the host resources.
+#![allow(dead_code)]
+
+use {
+    super::{apdu, CaDevice},
+    anyhow::Result,
+};
+
+pub use ca_rm_tag::*;
+
+/// en50221 Table 58: Resource Manager object tags
+mod ca_rm_tag {
+    pub const PROFILE_ENQ: u32 = 0x9F8010;
+    pub const PROFILE: u32 = 0x9F8011;
+    pub const PROFILE_CHANGED: u32 = 0x9F8012;
+}
+
+/// Resource Manager session state
+#[derive(Debug, Default)]
+pub struct RmState {
+    session_id: Option<u16>,
+    /// Resource identifiers provided by the module
+    module_resources: Vec<u32>,
+}
+
+pub fn open(ca: &mut CaDevice, session_id: u16) -> Result<()> {
+    ca.rm = RmState {
+        session_id: Some(session_id),
+        ..Default::default()
+    };
+
+    apdu::send(ca, session_id, PROFILE_ENQ, &[])
+}
+
+pub fn close(ca: &mut CaDevice) {
+    ca.rm = RmState::default();
+}
+
+/// Process Resource Manager object received from the module
+pub fn handle(ca: &mut CaDevice, session_id: u16, tag: u32, data: &[u8]) -> Result<()> {
+    match tag {
+        PROFILE_ENQ => {
+            let profile: Vec<u8> = apdu::HOST_RESOURCES
+                .iter()
+                .flat_map(|id| id.to_be_bytes())
+                .collect();
+            apdu::send(ca, session_id, PROFILE, &profile)?;
+        }
+        PROFILE => {
+            ca.rm.module_resources = data
+                .chunks_exact(4)
+                .map(|v| u32::from_be_bytes([v[0], v[1], v[2], v[3]]))
+                .collect();
+            apdu::send(ca, session_id, PROFILE_CHANGED, &[])?;
+        }
+        PROFILE_CHANGED => {
+            apdu::send(ca, session_id, PROFILE_ENQ, &[])?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+impl CaDevice {
+    /// Returns resource identifiers provided by the module.
+    /// List is received on the Resource Manager session
+    pub fn module_resources(&self) -> &[u32] {
+        &self.rm.module_resources
+    }
+
+    /// Returns resource identifiers provided by the host
+    pub fn host_resources(&self) -> &'static [u32] {
+        apdu::HOST_RESOURCES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::ca::{
+            spdu,
+            tests::{ca_device, module_apdu, open_session, parse_apdu, sent_spdu},
+        },
+    };
+
+    #[test]
+    fn profile_exchange() {
+        let mut ca = ca_device();
+
+        let (status, session_id, sent) = open_session(&mut ca, apdu::RESOURCE_MANAGER);
+        assert_eq!(status, spdu::SS_OK);
+        assert_eq!(parse_apdu(&sent[0]), (session_id, PROFILE_ENQ, vec![]));
+
+        // module asks for the host resources
+        spdu::handle(&mut ca, 0, &module_apdu(session_id, PROFILE_ENQ, &[])).unwrap();
+        let sent = sent_spdu(&ca);
+        let profile: Vec<u8> = apdu::HOST_RESOURCES.iter().flat_map(|v| v.to_be_bytes()).collect();
+        assert_eq!(parse_apdu(&sent[0]), (session_id, PROFILE, profile));
+
+        // module replies with own resources
+        let module_profile = [0x00, 0x01, 0x00, 0x41, 0x00, 0x02, 0x00, 0x41];
+        spdu::handle(&mut ca, 0, &module_apdu(session_id, PROFILE, &module_profile)).unwrap();
+        assert_eq!(ca.module_resources(), [0x0001_0041, 0x0002_0041]);
+        let sent = sent_spdu(&ca);
+        assert_eq!(parse_apdu(&sent[0]), (session_id, PROFILE_CHANGED, vec![]));
+
+        spdu::handle(&mut ca, 0, &module_apdu(session_id, PROFILE_CHANGED, &[])).unwrap();
+        let sent = sent_spdu(&ca);
+        assert_eq!(parse_apdu(&sent[0]), (session_id, PROFILE_ENQ, vec![]));
+    }
+}
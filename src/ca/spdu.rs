@@ -8,6 +8,7 @@
 
 use {
     super::{apdu, tpdu, CaDevice},
+    crate::DvbError,
     anyhow::{Context, Result},
     std::convert::TryInto,
 };
@@ -34,7 +35,7 @@ mod ca_spdu_status {
 }
 
 fn assert_size(spdu: &[u8], size: usize) -> Result<()> {
-    if spdu.len() >= size && usize::from(spdu[2]) == size - 2 {
+    if spdu.len() >= size && usize::from(spdu[1]) == size - 2 {
         Ok(())
     } else {
         Err(anyhow!("CA SPDU: invalid size"))
@@ -50,12 +51,15 @@ fn handle_open_session_request(ca: &mut CaDevice, slot_id: u8, spdu: &[u8]) -> R
     assert_size(spdu, 6)?;
 
     let resource_id = u32::from_be_bytes(spdu[2..=5].try_into().unwrap());
-    let session_id = apdu::init(ca, resource_id)?;
+    let (status, session_id) = match apdu::init(ca, resource_id) {
+        Ok(session_id) => (SS_OK, session_id),
+        Err(_) => (SS_NOT_ALLOCATED, 0),
+    };
 
     let response: [u8; 9] = [
         ST_OPEN_SESSION_RESPONSE,
         7,
-        SS_OK,
+        status,
         spdu[2],
         spdu[3],
         spdu[4],
@@ -64,7 +68,13 @@ fn handle_open_session_request(ca: &mut CaDevice, slot_id: u8, spdu: &[u8]) -> R
         session_id as u8,
     ];
 
-    tpdu::send(ca, slot_id, tpdu::TT_DATA_LAST, &response)
+    tpdu::send(ca, slot_id, tpdu::TT_DATA_LAST, &response)?;
+
+    if status == SS_OK {
+        apdu::open(ca, session_id)?;
+    }
+
+    Ok(())
 }
 
 fn handle_close_session_request(ca: &mut CaDevice, slot_id: u8, spdu: &[u8]) -> Result<()> {
@@ -117,6 +127,6 @@ pub fn handle(ca: &mut CaDevice, slot_id: u8, spdu: &[u8]) -> Result<()> {
             .context("ST_CREATE_SESSION_RESPONSE failed"),
         ST_CLOSE_SESSION_RESPONSE => handle_close_session_response(ca, slot_id, spdu)
             .context("ST_CLOSE_SESSION_RESPONSE failed"),
-        tag => Err(anyhow!("CA SPDU: invalid tag 0x{:02X}", tag)),
+        tag => Err(DvbError::CaInvalidSpduTag(tag).into()),
     }
 }
@@ -1,4 +1,4 @@
-use std::mem;
+use std::{fmt, mem};
 
 pub use {ca_descr_type::*, ca_slot_flags::*, ca_slot_type::*};
 
@@ -25,7 +25,7 @@ mod ca_slot_flags {
 
 /// CA slot interface types and info
 #[repr(C)]
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Copy, Clone)]
 pub struct CaSlotInfo {
     /// slot number
     pub slot_num: u32,
@@ -35,6 +35,8 @@ pub struct CaSlotInfo {
     pub flags: u32,
 }
 
+assert_size!(CaSlotInfo, 12);
+
 mod ca_descr_type {
     /// European Common Descrambler (ECD) hardware
     pub const CA_ECD: u32 = 1;
@@ -54,6 +56,8 @@ pub struct CaDescrInfo {
     pub descr_type: u32,
 }
 
+assert_size!(CaDescrInfo, 8);
+
 /// CA slot interface capabilities
 #[repr(C)]
 #[derive(Default, Debug)]
@@ -68,6 +72,39 @@ pub struct CaCaps {
     pub descr_type: u32,
 }
 
+assert_size!(CaCaps, 16);
+
+impl fmt::Display for CaCaps {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Slots: {}", self.slot_num)?;
+
+        write!(f, "Slot types:")?;
+        for (flag, name) in [
+            (CA_CI, "CI"),
+            (CA_CI_LINK, "CI-LINK"),
+            (CA_CI_PHYS, "CI-PHYS"),
+            (CA_DESCR, "DESCR"),
+            (CA_SC, "SC"),
+        ] {
+            if self.slot_type & flag != 0 {
+                write!(f, " {}", name)?;
+            }
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Descramblers: {}", self.descr_num)?;
+
+        write!(f, "Descrambler types:")?;
+        for (flag, name) in [(CA_ECD, "ECD"), (CA_NDS, "NDS"), (CA_DSS, "DSS")] {
+            if self.descr_type & flag != 0 {
+                write!(f, " {}", name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// a message to/from a CI-CAM
 #[repr(C)]
 #[derive(Debug)]
@@ -82,9 +119,20 @@ pub struct CaMsg {
     pub msg: [u8; 256],
 }
 
+assert_size!(CaMsg, 268);
+
 impl Default for CaMsg {
     #[inline]
     fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+impl CaMsg {
+    /// Creates zero-initialized structure for the ioctl call.
+    /// All fields are integers or arrays of integers, so zero is valid value
+    #[inline]
+    pub fn zeroed() -> Self {
         unsafe { mem::zeroed::<Self>() }
     }
 }
@@ -101,6 +149,8 @@ pub struct CaDescr {
     pub cw: [u8; 8],
 }
 
+assert_size!(CaDescr, 16);
+
 #[repr(C)]
 #[derive(Default, Debug)]
 pub struct CaPid {
@@ -109,8 +159,31 @@ pub struct CaPid {
     pub index: i32,
 }
 
+assert_size!(CaPid, 8);
+
 // pub const CA_GET_DESCR_INFO: IoctlInt = io_read::<CaDescrInfo>(b'o', 131);
-// pub const CA_GET_MSG: IoctlInt = io_read::<CaMsg>(b'o', 132);
-// pub const CA_SEND_MSG: IoctlInt = io_write::<CaMsg>(b'o', 133);
 // pub const CA_SET_DESCR: IoctlInt = io_write::<CaDescr>(b'o', 134);
 // pub const CA_SET_PID: IoctlInt = io_write::<CaPid>(b'o', 135);
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tests::is_zeroed};
+
+    #[test]
+    fn zeroed() {
+        let msg = CaMsg::default();
+        assert!(is_zeroed(&msg));
+        assert_eq!(msg.length, 0);
+    }
+
+    /// Sizes and alignment of the structures in linux/dvb/ca.h
+    #[test]
+    fn kernel_abi() {
+        assert_eq!((mem::size_of::<CaSlotInfo>(), mem::align_of::<CaSlotInfo>()), (12, 4));
+        assert_eq!((mem::size_of::<CaDescrInfo>(), mem::align_of::<CaDescrInfo>()), (8, 4));
+        assert_eq!((mem::size_of::<CaCaps>(), mem::align_of::<CaCaps>()), (16, 4));
+        assert_eq!((mem::size_of::<CaMsg>(), mem::align_of::<CaMsg>()), (268, 4));
+        assert_eq!((mem::size_of::<CaDescr>(), mem::align_of::<CaDescr>()), (16, 4));
+        assert_eq!((mem::size_of::<CaPid>(), mem::align_of::<CaPid>()), (8, 4));
+    }
+}
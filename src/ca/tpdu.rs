@@ -13,8 +13,13 @@
 
 use {
     super::{asn1, spdu, CaDevice},
+    crate::util,
     anyhow::{Context, Result},
-    std::io::{IoSlice, Write},
+    std::{
+        io::{ErrorKind, IoSlice, Read, Write},
+        os::unix::io::AsRawFd,
+        time::Duration,
+    },
 };
 
 pub use ca_tpdu_tag::*;
@@ -36,47 +41,108 @@ mod ca_tpdu_tag {
     pub const TT_DATA_MORE: u8 = 0xA1;
 }
 
-pub fn _read(ca: &mut CaDevice, data: &[u8]) -> Result<()> {
-    // TODO: read packet
+/// en50221 A.4.1.11: Status byte with data available indicator
+const SB_DATA_AVAILABLE: u8 = 0x80;
+
+/// Reads pending TPDUs from the CA device and process them.
+/// While the module reports data available requests it with TT_RCV
+/// and waits for the reply no more than `timeout`.
+/// Returns `true` if the reply to the last TT_RCV is not received yet
+pub fn read(ca: &mut CaDevice, timeout: Duration) -> Result<bool> {
+    let mut buffer = [0u8; TPDU_SIZE_MAX];
+    let mut rcv_sent = false;
+
+    loop {
+        let wait = if rcv_sent { timeout } else { Duration::ZERO };
+        if !util::wait_readable(ca.as_raw_fd(), Some(wait)).context("CA TPDU: read")? {
+            return Ok(rcv_sent);
+        }
+
+        let size = match (&ca.file).read(&mut buffer) {
+            Ok(v) => v,
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(rcv_sent),
+            Err(e) => return Err(e).context("CA TPDU: read failed"),
+        };
+
+        // module has nothing to send
+        if size == 0 {
+            return Ok(false);
+        }
+
+        rcv_sent = handle(ca, &buffer[..size])?;
+        if rcv_sent {
+            send(ca, buffer[0], TT_RCV, &[])?;
+        }
+    }
+}
 
+/// Process TPDU received from the link layer:
+/// slot_id, t_c_id, R_TPDU and optional status.
+/// Returns true if the module has more data to send
+fn handle(ca: &mut CaDevice, data: &[u8]) -> Result<bool> {
     if data.len() < 4 {
         return Err(anyhow!("CA TPDU: invalid packet size"));
     }
 
-    if data[1] == 0
-    /* TODO: || data[1] > ca.slots.len() */
-    {
-        return Err(anyhow!("CA TPDU: invalid slot id {}", data[1]));
+    let slot_id = data[0];
+    if data[1] == 0 {
+        return Err(anyhow!("CA TPDU: invalid transport connection id {}", data[1]));
     }
 
-    let slot_id = data[1] - 1;
     let tag = data[2];
+    let (len, skip) = asn1::decode(&data[3..]).context("CA TPDU: invalid length")?;
+    let begin = 3 + skip;
+    let end = begin + len;
+    if len == 0 || end > data.len() {
+        return Err(anyhow!("CA TPDU: invalid packet size"));
+    }
+
+    // skip t_c_id
+    let body = &data[begin + 1..end];
+    let status = &data[end..];
+
+    let mut data_available = status.len() >= 4
+        && status[0] == TT_SB
+        && status[3] & SB_DATA_AVAILABLE != 0;
 
     match tag {
         /* Create Transport Connection reply */
         TT_CTC_REPLY => {
-            // TODO: slot is active now
+            ca.tc_active = true;
         }
         /* Delete Transport Connection reply */
         TT_DTC_REPLY => {
-            //
+            ca.tc_active = false;
+            ca.tpdu_buffer.clear();
+        }
+        /* Module requests to delete Transport Connection */
+        TT_DELETE_TC => {
+            ca.tc_active = false;
+            ca.tpdu_buffer.clear();
+            send(ca, slot_id, TT_DTC_REPLY, &[])?;
         }
 
         TT_DATA_MORE => {
-            // TODO: save data to buffer
+            ca.tpdu_buffer.extend_from_slice(body);
         }
         TT_DATA_LAST => {
-            // TODO: save data to buffer
-            spdu::handle(ca, slot_id, &[])?;
+            ca.tpdu_buffer.extend_from_slice(body);
+            let spdu = std::mem::take(&mut ca.tpdu_buffer);
+            if !spdu.is_empty() {
+                spdu::handle(ca, slot_id, &spdu)?;
+            }
         }
 
-        TT_SB => {}
+        /* Reply to the poll. Without data available bit module has nothing to send */
+        TT_SB => {
+            data_available = matches!(body.first(), Some(sb) if sb & SB_DATA_AVAILABLE != 0);
+        }
         _ => {
             return Err(anyhow!("CA TPDU: invalid tag 0x{:02X}", tag));
         }
     }
 
-    Ok(())
+    Ok(data_available)
 }
 
 /// Writes TPDU to the CA device
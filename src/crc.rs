@@ -0,0 +1,59 @@
+//! CRC-32/MPEG-2 used in the PSI/SI sections
+
+/// CRC-32/MPEG-2 lookup table, polynomial 0x04C11DB7
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+/// Calculates CRC-32/MPEG-2 used in the PSI/SI sections.
+/// Result for a section including its CRC_32 field is 0
+///
+/// ```
+/// use libdvb_rs::crc::crc32_mpeg;
+///
+/// assert_eq!(crc32_mpeg(b"123456789"), 0x0376_E6E7);
+/// ```
+pub fn crc32_mpeg(data: &[u8]) -> u32 {
+    data.iter().fold(0xFFFF_FFFF, |crc, &b| {
+        (crc << 8) ^ CRC32_TABLE[((crc >> 24) as u8 ^ b) as usize]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_answer() {
+        assert_eq!(crc32_mpeg(b"123456789"), 0x0376_E6E7);
+        assert_eq!(crc32_mpeg(&[]), 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn residue() {
+        let mut data = b"123456789".to_vec();
+        data.extend_from_slice(&crc32_mpeg(&data).to_be_bytes());
+        assert_eq!(crc32_mpeg(&data), 0);
+
+        data[0] ^= 0x01;
+        assert_ne!(crc32_mpeg(&data), 0);
+    }
+}
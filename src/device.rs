@@ -0,0 +1,22 @@
+use {
+    crate::util,
+    anyhow::Result,
+    std::{os::unix::io::AsRawFd, time::Duration},
+};
+
+/// Common interface of the DVB devices
+pub trait DvbDevice: AsRawFd {
+    /// Returns adapter number: /dev/dvb/adapterN
+    fn adapter(&self) -> u32;
+
+    /// Returns device number on the adapter, e.g. /dev/dvb/adapter0/frontendN
+    fn device(&self) -> u32;
+
+    /// Waits until device is ready for reading.
+    /// Waits no more than `timeout` or infinitely if timeout is `None`.
+    /// Returns `false` on timeout
+    #[inline]
+    fn wait_readable(&self, timeout: Option<Duration>) -> Result<bool> {
+        util::wait_readable(self.as_raw_fd(), timeout)
+    }
+}
@@ -0,0 +1,107 @@
+use {
+    super::{section_error, DmxDevice, DmxSctFilterParams},
+    crate::{DvbDevice, DvbError, IoctlBackend, SysBackend},
+    anyhow::{Context, Result},
+    std::{
+        os::unix::io::{AsRawFd, RawFd},
+        time::Duration,
+    },
+    tokio::io::unix::AsyncFd,
+};
+
+/// Demux device driven by the tokio reactor.
+/// Available with the `tokio` feature
+///
+/// ```text
+/// let mut dmx = AsyncDmxDevice::new(DmxDevice::open_ro(0, 0)?)?;
+/// dmx.set_filter(&filter)?;
+/// loop {
+///     let section = dmx.next_section().await?;
+///     // ...
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AsyncDmxDevice<B = SysBackend> {
+    inner: AsyncFd<DmxDevice<B>>,
+    timeout: Option<Duration>,
+}
+
+impl<B> AsRawFd for AsyncDmxDevice<B> {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl<B> DvbDevice for AsyncDmxDevice<B> {
+    #[inline]
+    fn adapter(&self) -> u32 {
+        self.inner.get_ref().adapter()
+    }
+
+    #[inline]
+    fn device(&self) -> u32 {
+        self.inner.get_ref().device()
+    }
+}
+
+impl<B: IoctlBackend> AsyncDmxDevice<B> {
+    /// Registers demux device in the current tokio runtime.
+    /// Device should be opened in non-blocking mode
+    pub fn new(dmx: DmxDevice<B>) -> Result<Self> {
+        let inner = AsyncFd::new(dmx).context("DMX: register in tokio runtime")?;
+
+        Ok(AsyncDmxDevice {
+            inner,
+            timeout: None,
+        })
+    }
+
+    /// Returns reference to the demux device
+    #[inline]
+    pub fn get_ref(&self) -> &DmxDevice<B> {
+        self.inner.get_ref()
+    }
+
+    /// Deregisters device from the tokio runtime and returns it
+    #[inline]
+    pub fn into_inner(self) -> DmxDevice<B> {
+        self.inner.into_inner()
+    }
+
+    /// Sets SCT filter. `timeout` of the filter is applied to `next_section`
+    pub fn set_filter(&mut self, filter: &DmxSctFilterParams) -> Result<()> {
+        self.get_ref().set_filter(filter)?;
+        self.timeout = match filter.timeout {
+            0 => None,
+            v => Some(Duration::from_millis(v.into())),
+        };
+
+        Ok(())
+    }
+
+    async fn read_section(&self) -> Result<Vec<u8>> {
+        loop {
+            let mut guard = self.inner.readable().await.context("DMX: read section")?;
+
+            // readiness is cleared by try_io on WouldBlock
+            match guard.try_io(|inner| inner.get_ref().read_section_nowait()) {
+                Ok(result) => return result.map_err(section_error),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// Waits for the next section from the started SCT filter and returns it.
+    /// On filter timeout returns `DvbError::Timeout`.
+    /// If sections were lost returns `DvbError::BufferOverflow`, reading could be continued.
+    pub async fn next_section(&self) -> Result<Vec<u8>> {
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.read_section())
+                .await
+                .map_err(|_| DvbError::Timeout)
+                .context("DMX: read section")?,
+            None => self.read_section().await,
+        }
+    }
+}
@@ -1,36 +1,189 @@
 use {
     anyhow::{Context, Result},
-    nix::{ioctl_write_int_bad, ioctl_none_bad, ioctl_write_ptr, request_code_none},
+    nix::{request_code_none, request_code_write, unistd::close},
     std::{
         fs::{File, OpenOptions},
+        io::{self, ErrorKind, Read},
         os::unix::{
             fs::{OpenOptionsExt},
-            io::{AsRawFd, RawFd},
+            io::{AsRawFd, IntoRawFd, RawFd},
         },
+        mem, ptr,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+        time::Duration,
     },
     sys::*,
+    crate::{util, DvbDevice, DvbError, IoctlBackend, SysBackend},
 };
 
+/// Default path template of the demux device
+pub const DMX_PATH_TEMPLATE: &str = "/dev/dvb/adapter{adapter}/demux{device}";
+
+/// Maximum size of the section including header
+pub const DMX_SECTION_SIZE_MAX: usize = 4096;
+
+/// Buffer size is rounded up to a multiple of this value
+pub const DMX_BUFFER_SIZE_ALIGN: u32 = 4096;
+
+/// Maximum size of the circular buffer accepted by `set_buffer_size`
+pub const DMX_BUFFER_SIZE_MAX: u32 = 64 * 1024 * 1024;
+
+/// Checks that `pid` is in range 0..=DMX_PID_MAX or is `DMX_PID_ALL` if `allow_all` is set
+fn check_pid(pid: u16, allow_all: bool) -> Result<()> {
+    ensure!(
+        pid <= DMX_PID_MAX || (allow_all && pid == DMX_PID_ALL),
+        "invalid PID {}",
+        pid
+    );
+
+    Ok(())
+}
+
+/// Checks buffer size and rounds it up to the `DMX_BUFFER_SIZE_ALIGN`
+pub(crate) fn align_buffer_size(size: u32) -> Result<u32> {
+    ensure!(size != 0, "buffer size should not be zero");
+    ensure!(
+        size <= DMX_BUFFER_SIZE_MAX,
+        "buffer size {} is too large (max {})",
+        size,
+        DMX_BUFFER_SIZE_MAX
+    );
+
+    Ok((size + DMX_BUFFER_SIZE_ALIGN - 1) & !(DMX_BUFFER_SIZE_ALIGN - 1))
+}
+
+/// Converts section read error to `DvbError` if possible
+pub(crate) fn section_error(e: io::Error) -> anyhow::Error {
+    let e = match e.raw_os_error() {
+        Some(::nix::libc::ETIMEDOUT) => DvbError::Timeout.into(),
+        Some(::nix::libc::EOVERFLOW) => DvbError::BufferOverflow.into(),
+        _ => anyhow::Error::from(e),
+    };
+
+    e.context("DMX: read section")
+}
 
 pub mod sys;
 
-/// A reference to the demux device and device information
+#[cfg(feature = "tokio")]
+mod asyncio;
+#[cfg(feature = "tokio")]
+pub use asyncio::AsyncDmxDevice;
+
+/// Options to open the demux device
+///
+/// ```text
+/// let dmx = DmxOptions::new()
+///     .write(true)
+///     .buffer_size(188 * 1024)
+///     .open(0, 0)?;
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct DmxOptions {
+    is_write: bool,
+    buffer_size: Option<u32>,
+    path_template: Option<String>,
+}
+
+impl DmxOptions {
+    /// Creates options to open device in read-only mode with default buffer size
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens device in read-write mode if `is_write` is true
+    #[inline]
+    pub fn write(mut self, is_write: bool) -> Self {
+        self.is_write = is_write;
+        self
+    }
+
+    /// Sets the size of the circular buffer used for filtered data
+    #[inline]
+    pub fn buffer_size(mut self, size: u32) -> Self {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    /// Sets device path template with `{adapter}` and `{device}` placeholders.
+    /// Default is `DMX_PATH_TEMPLATE`
+    #[inline]
+    pub fn path_template(mut self, template: &str) -> Self {
+        self.path_template = Some(template.to_owned());
+        self
+    }
+
+    /// Attempts to open demux device with options
+    pub fn open(&self, adapter: u32, device: u32) -> Result<DmxDevice> {
+        let template = self.path_template.as_deref().unwrap_or(DMX_PATH_TEMPLATE);
+        let mut dmx = DmxDevice::open_with_template(adapter, device, template, self.is_write)?;
+
+        if let Some(size) = self.buffer_size {
+            dmx.set_buffer_size(size)?;
+        }
+
+        Ok(dmx)
+    }
+}
+
+/// A reference to the demux device and device information.
+/// ioctl calls are performed with backend `B`
 #[derive(Debug)]
-pub struct DmxDevice {
+pub struct DmxDevice<B = SysBackend> {
+    adapter: u32,
+    device: u32,
+
     file: File,
-    buffer_size: u32
+    backend: B,
+    buffer_size: u32,
+
+    /// PIDs of the TS filter
+    pids: Mutex<Vec<u16>>,
+    /// Filter is started with `start` or `DMX_IMMEDIATE_START`
+    running: AtomicBool,
 }
 
-impl AsRawFd for DmxDevice {
+impl<B> AsRawFd for DmxDevice<B> {
     #[inline]
     fn as_raw_fd(&self) -> RawFd {
         self.file.as_raw_fd()
     }
 }
 
+impl<B> IntoRawFd for DmxDevice<B> {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
+impl<B> DvbDevice for DmxDevice<B> {
+    #[inline]
+    fn adapter(&self) -> u32 {
+        self.adapter
+    }
+
+    #[inline]
+    fn device(&self) -> u32 {
+        self.device
+    }
+}
+
 impl DmxDevice {
-    fn open(adapter: u32, device: u32, is_write: bool) -> Result<Self> {
-        let path = format!("/dev/dvb/adapter{}/demux{}", adapter, device);
+    /// Attempts to open demux device with the path built from `template`,
+    /// for example `/dev/dvb/adapter{adapter}/demux{device}`.
+    /// Template should contain both placeholders
+    pub fn open_with_template(
+        adapter: u32,
+        device: u32,
+        template: &str,
+        is_write: bool,
+    ) -> Result<Self> {
+        let path = util::device_path(template, adapter, device).context("DMX: open")?;
         let file = OpenOptions::new()
             .read(true)
             .write(is_write)
@@ -38,22 +191,43 @@ impl DmxDevice {
             .open(&path)
             .with_context(|| format!("DMX: failed to open device {}", &path))?;
 
-        Ok(DmxDevice {
-            file,
-            buffer_size: 2 * 4096
-        })
+        Ok(DmxDevice::with_backend(adapter, device, file, SysBackend))
     }
 
-    /// Attempts to open frontend device in read-only mode
+    /// Attempts to open demux device in read-only mode
     #[inline]
     pub fn open_ro(adapter: u32, device: u32) -> Result<Self> {
-        Self::open(adapter, device, false)
+        Self::open_with_template(adapter, device, DMX_PATH_TEMPLATE, false)
     }
 
-    /// Attempts to open frontend device in read-write mode
+    /// Attempts to open demux device in read-write mode
     #[inline]
     pub fn open_rw(adapter: u32, device: u32) -> Result<Self> {
-        Self::open(adapter, device, true)
+        Self::open_with_template(adapter, device, DMX_PATH_TEMPLATE, true)
+    }
+}
+
+impl<B: IoctlBackend> DmxDevice<B> {
+    /// Creates demux device over the opened `file` with ioctl `backend`
+    pub fn with_backend(adapter: u32, device: u32, file: File, backend: B) -> Self {
+        DmxDevice {
+            adapter,
+            device,
+
+            file,
+            backend,
+            buffer_size: 2 * 4096,
+
+            pids: Mutex::new(Vec::new()),
+            running: AtomicBool::new(false),
+        }
+    }
+
+    /// Closes device and reports error unlike drop
+    pub fn close(self) -> Result<()> {
+        close(self.into_raw_fd()).context("DMX: close")?;
+
+        Ok(())
     }
 
     /// Attempts to set demux PES filter parameters.
@@ -64,35 +238,59 @@ impl DmxDevice {
     /// whether the filter should be a “one-shot” filter, i.e. if the filtering operation should be stopped
     /// after the first section is received, and whether the filtering operation should be started immediately
     /// (without waiting for a DMX_START ioctl call).
+    ///
+    /// If a filter was previously set-up, this filter will be canceled, and the receive buffer will be flushed.
+    /// Filter with `DMX_IMMEDIATE_START` is running after the call and following `start`
+    /// does nothing, use `force_start` to restart it.
+    ///
+    /// `DMX_OUT_DECODER` output accepts only audio, video and PCR PES types of one PID.
     pub fn set_pes_filter(&self, filter: &DmxPesFilterParams) -> Result<()> {
+        if filter.output == DMX_OUT_DECODER {
+            ensure!(
+                filter.pes_type.is_decoder_type(),
+                "DMX: set PES filter: {:?} is not supported by the decoder output",
+                filter.pes_type
+            );
+        }
+        check_pid(filter.pid, filter.output != DMX_OUT_DECODER).context("DMX: set PES filter")?;
+
         // DMX_SET_PES_FILTER
-        ioctl_write_ptr!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            44,
-            DmxPesFilterParams
-        );
+        let request = request_code_write!(b'o', 44, mem::size_of::<DmxPesFilterParams>());
 
-        unsafe { ioctl_call(self.as_raw_fd(), filter as *const _) }.context("DMX: set PES filter")?;
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, filter as *const _ as _) }
+            .context("DMX: set PES filter")?;
+
+        *self.pids.lock().unwrap() = vec![filter.pid];
+        self.set_running(filter.flags);
 
         Ok(())
     }
 
+    /// Replaces PES filter on a running demux.
+    /// Stops filtering, sets new filter, drops any stale data left in the receive buffer
+    /// and starts filtering again if it is not started with `DMX_IMMEDIATE_START`.
+    pub fn replace_pes_filter(&self, filter: &DmxPesFilterParams) -> Result<()> {
+        self.stop()?;
+        self.set_pes_filter(filter)?;
+        self.flush()?;
+        self.start()
+    }
 
     /// Tries to add multiple PIDs to a transport stream filter previously set up with 
     /// set_pes_filter and output equal to DMX_OUT_TSDEMUX_TAP.
     pub fn add_pid(&self, pid: u16) -> Result<()> {
+        check_pid(pid, true).context("DMX: add PID")?;
+
         // DMX_ADD_PID
-        ioctl_write_ptr!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            51,
-            u16
-        );
+        let request = request_code_write!(b'o', 51, mem::size_of::<u16>());
+
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &pid as *const _ as _) }
+            .context("DMX: add PID")?;
 
-        unsafe { ioctl_call(self.as_raw_fd(), &pid as *const _) }.context("DMX: add PID")?;
+        let mut pids = self.pids.lock().unwrap();
+        if !pids.contains(&pid) {
+            pids.push(pid);
+        }
 
         Ok(())
     }
@@ -101,20 +299,46 @@ impl DmxDevice {
     /// e. g. a filter previously set up with output equal to DMX_OUT_TSDEMUX_TAP, 
     /// created via either set_pes_filter or add_pid.
     pub fn remove_pid(&self, pid: u16) -> Result<()> {
+        check_pid(pid, true).context("DMX: remove PID")?;
+
         // DMX_REMOVE_PID
-        ioctl_write_ptr!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            52,
-            u16
-        );
+        let request = request_code_write!(b'o', 52, mem::size_of::<u16>());
 
-        unsafe { ioctl_call(self.as_raw_fd(), &pid as *const _) }.context("DMX: remove PID")?;
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &pid as *const _ as _) }
+            .context("DMX: remove PID")?;
+
+        self.pids.lock().unwrap().retain(|&v| v != pid);
 
         Ok(())
     }
 
+    /// Replaces PIDs of the TS filter previously set up with set_pes_filter
+    /// and output equal to DMX_OUT_TSDEMUX_TAP.
+    /// Adds missing PIDs first and then removes PIDs not in the `pids`
+    pub fn set_pids(&self, pids: &[u16]) -> Result<()> {
+        let current = self.current_pids();
+        ensure!(!current.is_empty(), "DMX: set PIDs: TS filter is not set");
+
+        for &pid in pids {
+            if !current.contains(&pid) {
+                self.add_pid(pid)?;
+            }
+        }
+
+        for pid in current {
+            if !pids.contains(&pid) {
+                self.remove_pid(pid)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns PIDs of the TS filter set with set_pes_filter, add_pid and set_pids
+    pub fn current_pids(&self) -> Vec<u16> {
+        self.pids.lock().unwrap().clone()
+    }
+
     /// Attempts to set demux SCT filter parameters.
     /// A timeout may be defined stating number of seconds to wait for a section to be loaded.
     /// A value of 0 means that no timeout should be applied.
@@ -124,17 +348,90 @@ impl DmxDevice {
     /// (without waiting for a DMX_START ioctl call).
     /// 
     /// If a filter was previously set-up, this filter will be canceled, and the receive buffer will be flushed.
+    /// Filter with `DMX_IMMEDIATE_START` is running after the call and following `start`
+    /// does nothing, use `force_start` to restart it.
     pub fn set_filter(&self, filter: &DmxSctFilterParams) -> Result<()> {
+        check_pid(filter.pid, false).context("DMX: set SCT filter")?;
+
         // DMX_SET_FILTER
-        ioctl_write_ptr!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            43,
-            DmxSctFilterParams
-        );
+        let request = request_code_write!(b'o', 43, mem::size_of::<DmxSctFilterParams>());
+
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, filter as *const _ as _) }
+            .context("DMX: set SCT filter")?;
+
+        self.pids.lock().unwrap().clear();
+        self.set_running(filter.flags);
+
+        Ok(())
+    }
+
+    /// Replaces SCT filter on a running demux.
+    /// Stops filtering, sets new filter, drops any stale data left in the receive buffer
+    /// and starts filtering again if it is not started with `DMX_IMMEDIATE_START`.
+    pub fn replace_section_filter(&self, filter: &DmxSctFilterParams) -> Result<()> {
+        self.stop()?;
+        self.set_filter(filter)?;
+        self.flush()?;
+        self.start()
+    }
+
+    /// Sets one-shot SCT filter, waits for the first matched section and returns it.
+    /// `DMX_ONESHOT` flag is always applied to the filter.
+    /// Waits no more than `filter.timeout` milliseconds or infinitely if timeout is 0.
+    /// On timeout returns `DvbError::Timeout`.
+    pub fn read_one_section(&self, filter: &DmxSctFilterParams) -> Result<Vec<u8>> {
+        let mut filter = *filter;
+        filter.flags.insert(DmxFilterFlags::DMX_ONESHOT);
+
+        self.replace_section_filter(&filter)?;
+        self.read_section(filter.timeout)
+    }
+
+    /// Waits for the next section from the started SCT filter and returns it.
+    /// Waits no more than `timeout` milliseconds or infinitely if timeout is 0.
+    /// On timeout returns `DvbError::Timeout`.
+    /// If sections were lost returns `DvbError::BufferOverflow`, reading could be continued.
+    pub fn read_section(&self, timeout: u32) -> Result<Vec<u8>> {
+        let timeout = if timeout == 0 {
+            None
+        } else {
+            Some(Duration::from_millis(timeout.into()))
+        };
+
+        if !util::wait_readable(self.as_raw_fd(), timeout).context("DMX: read section")? {
+            return Err(DvbError::Timeout).context("DMX: read section");
+        }
+
+        self.read_section_nowait().map_err(section_error)
+    }
+
+    /// Reads the next section if it is available in the receive buffer.
+    /// Returns `ErrorKind::WouldBlock` if there is no data
+    pub(crate) fn read_section_nowait(&self) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0u8; DMX_SECTION_SIZE_MAX];
+        let size = (&self.file).read(&mut buffer)?;
+        buffer.truncate(size);
+
+        Ok(buffer)
+    }
 
-        unsafe { ioctl_call(self.as_raw_fd(), filter as *const _) }.context("DMX: set SCT filter")?;
+    /// Reads and drops data available in the receive buffer,
+    /// so the next read starts with data received after the call.
+    /// Reads no more than the buffer size, so flush of a running filter
+    /// with the incoming data rate higher than the read rate terminates
+    pub fn flush(&self) -> Result<()> {
+        let mut buffer = vec![0u8; self.buffer_size as usize];
+        let mut remain = buffer.len();
+
+        while remain > 0 {
+            match (&self.file).read(&mut buffer[..remain]) {
+                Ok(0) => break,
+                Ok(n) => remain -= n,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.raw_os_error() == Some(::nix::libc::EOVERFLOW) => {}
+                Err(e) => return Err(e).context("DMX: flush buffer"),
+            }
+        }
 
         Ok(())
     }
@@ -142,46 +439,218 @@ impl DmxDevice {
     /// Attempts to set the size of the circular buffer used for filtered data.
     /// The default size is two maximum sized sections, 
     /// i.e. if this function is not called a buffer size of 2 * 4096 bytes will be used.
-    pub fn set_buffer_size(&mut self, size: u32) -> Result<()> {
+    ///
+    /// Size is rounded up to a multiple of 4096 bytes and should not exceed
+    /// `DMX_BUFFER_SIZE_MAX`. Returns the size that was actually set.
+    pub fn set_buffer_size(&mut self, size: u32) -> Result<u32> {
+        let size = align_buffer_size(size).context("DMX: set buffer size")?;
+
         // DMX_SET_BUFFER_SIZE
-        ioctl_write_int_bad!(
-            #[inline]
-            ioctl_call,
-            request_code_none!(b'o', 45)
-        );
+        let request = request_code_none!(b'o', 45);
 
-        unsafe { ioctl_call(self.as_raw_fd(), size as _) }.context("DMX: set buffer size")?;
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, size as _) }
+            .context("DMX: set buffer size")?;
 
         self.buffer_size = size;
 
-        Ok(())
+        Ok(size)
+    }
+
+    /// Filter is started by the kernel on set if `DMX_IMMEDIATE_START` is set
+    fn set_running(&self, flags: DmxFilterFlags) {
+        let running = flags.contains(DmxFilterFlags::DMX_IMMEDIATE_START);
+        self.running.store(running, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if the filter is started with `start` or `DMX_IMMEDIATE_START`
+    /// and not stopped with `stop`. One-shot filter stays running after the first section,
+    /// use `force_start` to restart it
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
     }
 
     /// Attempts to start the actual filtering operation defined via the ioctl calls set_filter or set_pes_filter.
+    /// Does nothing if the filter is already running, for example set with `DMX_IMMEDIATE_START`,
+    /// so the receive buffer is not flushed by the kernel restart
     pub fn start(&self) -> Result<()> {
+        if self.is_running() {
+            return Ok(());
+        }
+
+        self.force_start()
+    }
+
+    /// Starts filtering regardless of the tracked state.
+    /// Kernel restarts running filter and flushes the receive buffer
+    pub fn force_start(&self) -> Result<()> {
         // DMX_START
-        ioctl_none_bad!(
-            #[inline]
-            ioctl_call,
-            request_code_none!(b'o', 41)
-        );
+        let request = request_code_none!(b'o', 41);
 
-        unsafe { ioctl_call(self.as_raw_fd()) }.context("DMX: start")?;
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, ptr::null_mut()) }
+            .context("DMX: start")?;
+
+        self.running.store(true, Ordering::Relaxed);
 
         Ok(())
     }
 
     /// Attempts to stop the actual filtering operation defined via the ioctl calls set_filter or set_pes_filter and started via start.
+    /// Does nothing if the filter is not running
     pub fn stop(&self) -> Result<()> {
+        if !self.is_running() {
+            return Ok(());
+        }
+
+        self.force_stop()
+    }
+
+    /// Stops filtering regardless of the tracked state
+    pub fn force_stop(&self) -> Result<()> {
         // DMX_STOP
-        ioctl_none_bad!(
-            #[inline]
-            ioctl_call,
-            request_code_none!(b'o', 42)
-        );
+        let request = request_code_none!(b'o', 42);
+
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, ptr::null_mut()) }
+            .context("DMX: stop")?;
 
-        unsafe { ioctl_call(self.as_raw_fd()) }.context("DMX: stop")?;
+        self.running.store(false, Ordering::Relaxed);
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{ioctl::mock::MockBackend, IoctlRequest},
+        nix::errno::Errno,
+        std::io::Seek,
+    };
+
+    const DMX_ADD_PID: IoctlRequest = request_code_write!(b'o', 51, mem::size_of::<u16>());
+    const DMX_REMOVE_PID: IoctlRequest = request_code_write!(b'o', 52, mem::size_of::<u16>());
+    const DMX_SET_FILTER: IoctlRequest =
+        request_code_write!(b'o', 43, mem::size_of::<DmxSctFilterParams>());
+    const DMX_START: IoctlRequest = request_code_none!(b'o', 41);
+    const DMX_STOP: IoctlRequest = request_code_none!(b'o', 42);
+
+    fn demux() -> DmxDevice<MockBackend> {
+        let file = File::open("/dev/null").unwrap();
+        DmxDevice::with_backend(0, 0, file, MockBackend::default())
+    }
+
+    #[test]
+    fn add_remove_pid() {
+        let dmx = demux();
+
+        dmx.add_pid(0x100).unwrap();
+        dmx.add_pid(0x101).unwrap();
+        dmx.remove_pid(0x100).unwrap();
+
+        assert_eq!(
+            dmx.backend.calls(),
+            [DMX_ADD_PID, DMX_ADD_PID, DMX_REMOVE_PID]
+        );
+        assert_eq!(*dmx.pids.lock().unwrap(), [0x101]);
+    }
+
+    #[test]
+    fn add_pid_error() {
+        let dmx = demux();
+        dmx.backend.fail(DMX_ADD_PID, Errno::EINVAL);
+
+        assert!(dmx.add_pid(0x100).is_err());
+        assert!(dmx.pids.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn add_pid_invalid() {
+        let dmx = demux();
+
+        assert!(dmx.add_pid(0x3000).is_err());
+        assert!(dmx.backend.calls().is_empty());
+    }
+
+    #[test]
+    fn start_stop_idempotent() {
+        let dmx = demux();
+
+        dmx.stop().unwrap();
+        dmx.start().unwrap();
+        dmx.start().unwrap();
+        assert!(dmx.is_running());
+        dmx.stop().unwrap();
+        dmx.stop().unwrap();
+        assert!(!dmx.is_running());
+
+        assert_eq!(dmx.backend.calls(), [DMX_START, DMX_STOP]);
+    }
+
+    #[test]
+    fn immediate_start() {
+        let dmx = demux();
+        let mut filter = DmxSctFilterParams {
+            pid: 0,
+            filter: DmxFilter::builder().table_id(0).build().unwrap(),
+            timeout: 0,
+            flags: DmxFilterFlags::DMX_IMMEDIATE_START,
+        };
+
+        dmx.set_filter(&filter).unwrap();
+        assert!(dmx.is_running());
+        dmx.start().unwrap();
+        assert_eq!(dmx.backend.calls(), [DMX_SET_FILTER]);
+
+        dmx.backend.clear();
+        dmx.replace_section_filter(&filter).unwrap();
+        assert_eq!(dmx.backend.calls(), [DMX_STOP, DMX_SET_FILTER]);
+
+        dmx.backend.clear();
+        filter.flags = DmxFilterFlags::empty();
+        dmx.replace_section_filter(&filter).unwrap();
+        assert_eq!(dmx.backend.calls(), [DMX_STOP, DMX_SET_FILTER, DMX_START]);
+        assert!(dmx.is_running());
+    }
+
+    #[test]
+    fn force_start_stop() {
+        let dmx = demux();
+
+        dmx.force_stop().unwrap();
+        dmx.force_start().unwrap();
+        dmx.force_start().unwrap();
+
+        assert_eq!(dmx.backend.calls(), [DMX_STOP, DMX_START, DMX_START]);
+    }
+
+    #[test]
+    fn start_stop_error() {
+        let dmx = demux();
+
+        // filter or feed allocation failed
+        dmx.backend.fail(DMX_START, Errno::EBUSY);
+        assert!(dmx.start().is_err());
+        assert!(!dmx.is_running());
+
+        dmx.backend.on(DMX_START, |_| Ok(0));
+        dmx.start().unwrap();
+        dmx.backend.fail(DMX_STOP, Errno::EINVAL);
+        assert!(dmx.stop().is_err());
+        assert!(dmx.is_running());
+    }
+
+    #[test]
+    fn flush_bounded() {
+        let path = std::env::temp_dir().join(format!("libdvb-flush-{}", std::process::id()));
+        std::fs::write(&path, [0x47; 3 * 2 * 4096]).unwrap();
+        let file = File::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let dmx = DmxDevice::with_backend(0, 0, file, MockBackend::default());
+        dmx.flush().unwrap();
+
+        let position = (&dmx.file).stream_position().unwrap();
+        assert_eq!(position, u64::from(dmx.buffer_size));
+    }
+}
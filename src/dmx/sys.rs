@@ -1,4 +1,6 @@
+use anyhow::Result;
 use bitflags::bitflags;
+use std::fmt;
 use strum::FromRepr;
 
 pub use {
@@ -8,6 +10,12 @@ pub use {
 };
 
 
+/// Maximum value of the PID in the transport stream
+pub const DMX_PID_MAX: u16 = 0x1FFF;
+
+/// Wildcard PID to pass all PID's through the TS filter
+pub const DMX_PID_ALL: u16 = 0x2000;
+
 /// Output for the demux
 #[repr(u32)]
 #[allow(non_camel_case_types)]
@@ -94,6 +102,18 @@ pub enum DmxTsPes {
     DMX_PES_OTHER                = 20,
 }
 
+impl DmxTsPes {
+    /// Returns true for audio, video and PCR types accepted by `DMX_OUT_DECODER`
+    pub fn is_decoder_type(self) -> bool {
+        matches!(
+            self,
+            DMX_PES_AUDIO0 | DMX_PES_AUDIO1 | DMX_PES_AUDIO2 | DMX_PES_AUDIO3
+                | DMX_PES_VIDEO0 | DMX_PES_VIDEO1 | DMX_PES_VIDEO2 | DMX_PES_VIDEO3
+                | DMX_PES_PCR0 | DMX_PES_PCR1 | DMX_PES_PCR2 | DMX_PES_PCR3
+        )
+    }
+}
+
 
 bitflags! {
     /// Flags for the demux filter
@@ -108,12 +128,44 @@ bitflags! {
     }
 }
 
+impl fmt::Display for DmxFilterFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+
+        let names = [
+            (DmxFilterFlags::DMX_CHECK_CRC, "DMX_CHECK_CRC"),
+            (DmxFilterFlags::DMX_ONESHOT, "DMX_ONESHOT"),
+            (DmxFilterFlags::DMX_IMMEDIATE_START, "DMX_IMMEDIATE_START"),
+        ];
+
+        let mut sep = "";
+        for (flag, name) in names {
+            if self.contains(flag) {
+                write!(f, "{}{}", sep, name)?;
+                sep = " | ";
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats PID in hex, `DMX_PID_ALL` as `ALL`
+fn fmt_pid(f: &mut fmt::Formatter, pid: u16) -> fmt::Result {
+    match pid {
+        DMX_PID_ALL => write!(f, "PID ALL"),
+        v => write!(f, "PID 0x{:04X}", v),
+    }
+}
+
 
 /// Specifies Packetized Elementary Stream (PES) filter parameters
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct DmxPesFilterParams {
-    /// PID to be filtered. 8192 to pass all PID's
+    /// PID to be filtered. `DMX_PID_ALL` to pass all PID's
     pub pid: u16,
     /// Demux input, as specified by `DMX_IN_*`
     pub input: DmxInput,
@@ -125,11 +177,41 @@ pub struct DmxPesFilterParams {
     pub flags: DmxFilterFlags,
 }
 
+assert_size!(DmxPesFilterParams, 20);
+
+impl fmt::Display for DmxPesFilterParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_pid(f, self.pid)?;
+        write!(
+            f,
+            " {:?} -> {:?} type: {:?} flags: {}",
+            self.input, self.output, self.pes_type, self.flags
+        )
+    }
+}
+
+impl DmxPesFilterParams {
+    /// Filter from the frontend to the hardware decoder of the full-featured card.
+    /// `pes_type` selects decoder input: `DMX_PES_AUDIO*`, `DMX_PES_VIDEO*` or `DMX_PES_PCR*`.
+    /// Decoder plays the stream directly, data is not available for reading
+    pub fn decoder(pid: u16, pes_type: DmxTsPes) -> Self {
+        DmxPesFilterParams {
+            pid,
+            input: DMX_IN_FRONTEND,
+            output: DMX_OUT_DECODER,
+            pes_type,
+            flags: DmxFilterFlags::empty(),
+        }
+    }
+}
+
 pub const DMX_FILTER_SIZE: usize = 16;
 
-/// Specifies demux section header filter parameters
+/// Specifies demux section header filter parameters.
+/// Byte 0 of the filter matches table_id, bytes from 1 match section bytes
+/// from 3, section_length is skipped
 #[repr(C)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone)]
 pub struct DmxFilter {
     /// Bit array with bits to be matched at the section header
     pub filter: [u8; DMX_FILTER_SIZE],
@@ -139,11 +221,132 @@ pub struct DmxFilter {
     pub mode: [u8; DMX_FILTER_SIZE],
 }
 
+assert_size!(DmxFilter, 48);
+
+impl DmxFilter {
+    /// Filter for sections with `table_id`
+    pub fn match_table_id(table_id: u8) -> Self {
+        let mut filter = DmxFilter::default();
+        filter.filter[0] = table_id;
+        filter.mask[0] = 0xFF;
+        filter
+    }
+
+    /// Returns builder for the multi-byte filter
+    ///
+    /// ```text
+    /// // EIT actual present/following of the service 0x1234
+    /// // with version other than 5
+    /// let filter = DmxFilter::builder()
+    ///     .table_id(0x4E)
+    ///     .table_id_extension(0x1234)
+    ///     .not_byte(3, 5 << 1, 0x3E)
+    ///     .build()?;
+    /// ```
+    #[inline]
+    pub fn builder() -> DmxFilterBuilder {
+        DmxFilterBuilder::default()
+    }
+}
+
+/// Builder of the section header filter
+///
+/// Positive match: masked bits of the section should be equal to the filter.
+/// Negative match: at least one of the masked bits should differ from the filter.
+/// Section is accepted if all positive bits match and, if negative bits are set,
+/// negative match succeeds
+#[derive(Debug, Default, Clone)]
+pub struct DmxFilterBuilder {
+    filter: DmxFilter,
+    invalid_offset: Option<usize>,
+}
+
+impl DmxFilterBuilder {
+    /// Matches table_id
+    #[inline]
+    pub fn table_id(self, table_id: u8) -> Self {
+        self.byte(0, table_id, 0xFF)
+    }
+
+    /// Matches table_id_extension, for example service_id of the PMT and EIT
+    #[inline]
+    pub fn table_id_extension(self, table_id_extension: u16) -> Self {
+        let [hi, lo] = table_id_extension.to_be_bytes();
+        self.byte(1, hi, 0xFF).byte(2, lo, 0xFF)
+    }
+
+    /// Positive match of the `mask` bits of the filter byte `offset`
+    #[inline]
+    pub fn byte(self, offset: usize, value: u8, mask: u8) -> Self {
+        self.set(offset, value, mask, false)
+    }
+
+    /// Negative match of the `mask` bits of the filter byte `offset`
+    #[inline]
+    pub fn not_byte(self, offset: usize, value: u8, mask: u8) -> Self {
+        self.set(offset, value, mask, true)
+    }
+
+    fn set(mut self, offset: usize, value: u8, mask: u8, negative: bool) -> Self {
+        if offset >= DMX_FILTER_SIZE {
+            self.invalid_offset.get_or_insert(offset);
+            return self;
+        }
+
+        let filter = &mut self.filter;
+        filter.filter[offset] = (filter.filter[offset] & !mask) | (value & mask);
+        filter.mask[offset] |= mask;
+        if negative {
+            filter.mode[offset] |= mask;
+        } else {
+            filter.mode[offset] &= !mask;
+        }
+
+        self
+    }
+
+    /// Returns filter or error if byte offset is out of the filter size
+    pub fn build(self) -> Result<DmxFilter> {
+        if let Some(offset) = self.invalid_offset {
+            bail!(
+                "DMX: filter byte offset {} is out of range 0..{}",
+                offset,
+                DMX_FILTER_SIZE
+            );
+        }
+
+        Ok(self.filter)
+    }
+}
+
+impl fmt::Display for DmxFilter {
+    /// Formats masked bytes as `filter/mask`, negative match is prefixed with `!`.
+    /// Filter without masked bytes matches any section
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let size = match self.mask.iter().rposition(|&v| v != 0) {
+            Some(v) => v + 1,
+            None => return write!(f, "any"),
+        };
+
+        for i in 0..size {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            if self.mode[i] & self.mask[i] != 0 {
+                write!(f, "!")?;
+            }
+            write!(f, "{:02X}/{:02X}", self.filter[i], self.mask[i])?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Specifies Section header (SCT) filter parameters
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct DmxSctFilterParams {
-    /// PID to be filtered. 8192 to pass all PID's
+    /// PID to be filtered. Should not be `DMX_PID_ALL`
     pub pid: u16,
     /// Section header filter, as defined by DmxFilter
     pub filter: DmxFilter,
@@ -151,4 +354,42 @@ pub struct DmxSctFilterParams {
     pub timeout: u32,
     /// Extra flags for the section filter, as specified by DmxFilterFlags
     pub flags: DmxFilterFlags
-}
\ No newline at end of file
+}
+
+assert_size!(DmxSctFilterParams, 60);
+
+impl fmt::Display for DmxSctFilterParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_pid(f, self.pid)?;
+        if self.filter.mask[0] == 0xFF && self.filter.mode[0] == 0 {
+            write!(f, " table_id: 0x{:02X}", self.filter.filter[0])?;
+        }
+        write!(
+            f,
+            " filter: {} timeout: {}ms flags: {}",
+            self.filter, self.timeout, self.flags
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::mem};
+
+    /// Sizes and alignment of the structures in linux/dvb/dmx.h
+    #[test]
+    fn kernel_abi() {
+        assert_eq!(
+            (mem::size_of::<DmxPesFilterParams>(), mem::align_of::<DmxPesFilterParams>()),
+            (20, 4)
+        );
+        assert_eq!((mem::size_of::<DmxFilter>(), mem::align_of::<DmxFilter>()), (48, 1));
+        assert_eq!(
+            (mem::size_of::<DmxSctFilterParams>(), mem::align_of::<DmxSctFilterParams>()),
+            (60, 4)
+        );
+        assert_eq!(mem::size_of::<DmxInput>(), 4);
+        assert_eq!(mem::size_of::<DmxOutput>(), 4);
+        assert_eq!(mem::size_of::<DmxTsPes>(), 4);
+    }
+}
@@ -0,0 +1,280 @@
+use {
+    anyhow::{Context, Result},
+    nix::{ioctl_write_int_bad, request_code_none, unistd::close},
+    std::{
+        fs::{File, OpenOptions},
+        io::{self, ErrorKind, Read},
+        os::unix::{
+            fs::OpenOptionsExt,
+            io::{AsRawFd, IntoRawFd, RawFd},
+        },
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+    },
+    crate::{dmx::align_buffer_size, ioctl, util, DvbDevice, DvbError},
+};
+
+pub use crate::ts::{TS_PACKET_SIZE, TS_SYNC_BYTE};
+
+/// Default path template of the DVR device
+pub const DVR_PATH_TEMPLATE: &str = "/dev/dvb/adapter{adapter}/dvr{device}";
+
+/// A reference to the logical DVR device.
+/// Receives TS packets routed from the demux with `DMX_OUT_TS_TAP`
+#[derive(Debug)]
+pub struct DvrDevice {
+    adapter: u32,
+    device: u32,
+
+    file: File,
+    overflow_count: AtomicU64,
+    /// Partial packet from the end of the last `read_ts`
+    pending: Mutex<Vec<u8>>,
+}
+
+impl AsRawFd for DvrDevice {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for DvrDevice {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
+impl DvbDevice for DvrDevice {
+    #[inline]
+    fn adapter(&self) -> u32 {
+        self.adapter
+    }
+
+    #[inline]
+    fn device(&self) -> u32 {
+        self.device
+    }
+}
+
+impl Read for DvrDevice {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.file).read(buf)
+    }
+}
+
+impl DvrDevice {
+    /// Closes device and reports error unlike drop
+    pub fn close(self) -> Result<()> {
+        close(self.into_raw_fd()).context("DVR: close")?;
+
+        Ok(())
+    }
+
+    /// Attempts to open DVR device in read-only mode
+    #[inline]
+    pub fn open_ro(adapter: u32, device: u32) -> Result<Self> {
+        Self::open_with_template(adapter, device, DVR_PATH_TEMPLATE)
+    }
+
+    /// Attempts to open DVR device in read-only mode with the path built from `template`,
+    /// for example `/dev/dvb/adapter{adapter}/dvr{device}`.
+    /// Template should contain both placeholders
+    pub fn open_with_template(adapter: u32, device: u32, template: &str) -> Result<Self> {
+        let path = util::device_path(template, adapter, device).context("DVR: open")?;
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(::nix::libc::O_NONBLOCK)
+            .open(&path)
+            .with_context(|| format!("DVR: failed to open device {}", &path))?;
+
+        Ok(DvrDevice {
+            adapter,
+            device,
+
+            file,
+            overflow_count: AtomicU64::new(0),
+            pending: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Attempts to set the size of the circular buffer used for TS data.
+    /// Size is validated like `DmxDevice::set_buffer_size`.
+    /// Returns the size that was actually set
+    pub fn set_buffer_size(&self, size: u32) -> Result<u32> {
+        let size = align_buffer_size(size).context("DVR: set buffer size")?;
+
+        // DMX_SET_BUFFER_SIZE
+        ioctl_write_int_bad!(
+            #[inline]
+            ioctl_call,
+            request_code_none!(b'o', 45)
+        );
+
+        ioctl::retry_eintr(|| unsafe { ioctl_call(self.as_raw_fd(), size as _) })
+            .context("DVR: set buffer size")?;
+
+        Ok(size)
+    }
+
+    /// Returns number of buffer overflows detected by `read_ts`
+    #[inline]
+    pub fn overflow_count(&self) -> u64 {
+        self.overflow_count.load(Ordering::Relaxed)
+    }
+
+    /// Maps result of the read call: no data is 0, overflow is counted
+    /// and returned as `DvbError::BufferOverflow`
+    fn check_read(&self, result: io::Result<usize>) -> Result<usize> {
+        match result {
+            Ok(v) => Ok(v),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(0),
+            Err(e) if e.raw_os_error() == Some(::nix::libc::EOVERFLOW) => {
+                self.overflow_count.fetch_add(1, Ordering::Relaxed);
+                Err(DvbError::BufferOverflow).context("DVR: read")
+            }
+            Err(e) => Err(e).context("DVR: read"),
+        }
+    }
+
+    /// Reads TS packets into the `buf` and returns number of bytes.
+    /// Returned data starts with sync byte and contains whole packets only,
+    /// data before the first sync byte is dropped. Sync byte is accepted
+    /// if the next packet starts with sync byte too.
+    /// Partial packet at the end is kept and completed on the next call,
+    /// so `read_ts` should not be mixed with `Read::read`.
+    ///
+    /// Returns 0 if no data available.
+    /// Returns `DvbError::BufferOverflow` if the kernel buffer was overflowed
+    /// and data has been lost. Reading could be continued.
+    pub fn read_ts(&self, buf: &mut [u8]) -> Result<usize> {
+        let len = buf.len() - buf.len() % TS_PACKET_SIZE;
+        ensure!(len != 0, "DVR: buffer is smaller than TS packet");
+
+        let mut pending = self.pending.lock().unwrap();
+        let carry = pending.len();
+        buf[..carry].copy_from_slice(&pending);
+        // partial packet is not continued after an error
+        pending.clear();
+
+        let size = carry + self.check_read((&self.file).read(&mut buf[carry..len]))?;
+        let data = &buf[..size];
+
+        let skip = match data.iter().enumerate().position(|(i, &b)| {
+            b == TS_SYNC_BYTE
+                && !matches!(data.get(i + TS_PACKET_SIZE), Some(&v) if v != TS_SYNC_BYTE)
+        }) {
+            Some(v) => v,
+            None => return Ok(0),
+        };
+
+        let size = size - skip;
+        let tail = size % TS_PACKET_SIZE;
+        let size = size - tail;
+        pending.extend_from_slice(&buf[skip + size..skip + size + tail]);
+        if skip != 0 {
+            buf.copy_within(skip..skip + size, 0);
+        }
+
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, nix::libc};
+
+    fn dvr() -> DvrDevice {
+        DvrDevice {
+            adapter: 0,
+            device: 0,
+
+            file: File::open("/dev/null").unwrap(),
+            overflow_count: AtomicU64::new(0),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// DVR device reading `data` from the temporary file
+    fn dvr_with(data: &[u8]) -> DvrDevice {
+        let path = std::env::temp_dir().join(format!("libdvb-dvr-{}", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        let file = File::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        DvrDevice {
+            file,
+            ..dvr()
+        }
+    }
+
+    fn is_overflow(e: &anyhow::Error) -> bool {
+        e.downcast_ref::<DvbError>() == Some(&DvbError::BufferOverflow)
+    }
+
+    #[test]
+    fn overflow_errno() {
+        let dvr = dvr();
+
+        let overflow = || Err(io::Error::from_raw_os_error(libc::EOVERFLOW));
+        assert!(is_overflow(&dvr.check_read(overflow()).unwrap_err()));
+        assert!(is_overflow(&dvr.check_read(overflow()).unwrap_err()));
+        assert_eq!(dvr.overflow_count(), 2);
+
+        // reading continues after overflow
+        assert_eq!(dvr.check_read(Ok(TS_PACKET_SIZE)).unwrap(), TS_PACKET_SIZE);
+        assert_eq!(dvr.overflow_count(), 2);
+    }
+
+    #[test]
+    fn other_errno() {
+        let dvr = dvr();
+
+        let would_block = Err(io::Error::from_raw_os_error(libc::EAGAIN));
+        assert_eq!(dvr.check_read(would_block).unwrap(), 0);
+
+        let error = dvr.check_read(Err(io::Error::from_raw_os_error(libc::EIO))).unwrap_err();
+        assert!(!is_overflow(&error));
+        assert_eq!(dvr.overflow_count(), 0);
+    }
+
+    #[test]
+    fn read_ts_no_data() {
+        let dvr = dvr();
+        let mut buf = [0u8; TS_PACKET_SIZE * 2];
+
+        assert_eq!(dvr.read_ts(&mut buf).unwrap(), 0);
+        assert!(dvr.read_ts(&mut buf[..TS_PACKET_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn read_ts_resync() {
+        let packets: Vec<[u8; TS_PACKET_SIZE]> = (1..=2)
+            .map(|n| {
+                let mut packet = [n; TS_PACKET_SIZE];
+                packet[0] = TS_SYNC_BYTE;
+                packet
+            })
+            .collect();
+
+        // false sync byte in the garbage before the first packet
+        let mut data = vec![TS_SYNC_BYTE, 0x12, 0x34];
+        packets.iter().for_each(|p| data.extend_from_slice(p));
+
+        let dvr = dvr_with(&data);
+        let mut buf = [0u8; TS_PACKET_SIZE * 2];
+
+        assert_eq!(dvr.read_ts(&mut buf).unwrap(), TS_PACKET_SIZE);
+        assert_eq!(buf[..TS_PACKET_SIZE], packets[0]);
+
+        // partial second packet is completed with the next read
+        assert_eq!(dvr.read_ts(&mut buf).unwrap(), TS_PACKET_SIZE);
+        assert_eq!(buf[..TS_PACKET_SIZE], packets[1]);
+
+        assert_eq!(dvr.read_ts(&mut buf).unwrap(), 0);
+    }
+}
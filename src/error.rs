@@ -0,0 +1,43 @@
+use std::fmt;
+
+/// Errors which may require special handling by the caller.
+/// Returned wrapped into `anyhow::Error` and could be checked with `downcast_ref::<DvbError>()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DvbError {
+    /// Operation was not completed in the given time
+    Timeout,
+    /// Kernel buffer was overflowed and data has been lost
+    BufferOverflow,
+    /// CA module did not respond to the transport layer request
+    CaTpduTimeout,
+    /// CA module sent SPDU with unknown tag
+    CaInvalidSpduTag(u8),
+    /// CA module sent data for the session that is not opened
+    CaSessionMismatch(u16),
+    /// CA module is not inserted
+    CaModuleNotFound,
+    /// CA module was removed from the slot. All sessions are closed
+    CaModuleRemoved,
+    /// CA module is not ready in the given time after reset
+    CaResetTimeout,
+    /// CA module repeated the PIN enquiry after the answer
+    CaPinRejected,
+}
+
+impl fmt::Display for DvbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DvbError::Timeout => write!(f, "timed out"),
+            DvbError::BufferOverflow => write!(f, "buffer overflow"),
+            DvbError::CaTpduTimeout => write!(f, "CA TPDU: no response from module"),
+            DvbError::CaInvalidSpduTag(tag) => write!(f, "CA SPDU: invalid tag 0x{:02X}", tag),
+            DvbError::CaSessionMismatch(id) => write!(f, "CA: session {} not found", id),
+            DvbError::CaModuleNotFound => write!(f, "CA: module not found"),
+            DvbError::CaModuleRemoved => write!(f, "CA: module removed"),
+            DvbError::CaResetTimeout => write!(f, "CA: module reset timed out"),
+            DvbError::CaPinRejected => write!(f, "CA: PIN rejected"),
+        }
+    }
+}
+
+impl std::error::Error for DvbError {}
@@ -0,0 +1,157 @@
+use super::sys::*;
+
+/// Frontend capabilities decoded from the `fe_caps` bit flags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Can auto-detect frequency spectral band inversion
+    pub can_inversion_auto: bool,
+    /// Can auto-detect FEC
+    pub can_fec_auto: bool,
+    /// Can auto-detect QAM modulation
+    pub can_qam_auto: bool,
+    /// Can auto-detect transmission mode
+    pub can_transmission_mode_auto: bool,
+    /// Can auto-detect bandwidth
+    pub can_bandwidth_auto: bool,
+    /// Can auto-detect guard interval
+    pub can_guard_interval_auto: bool,
+    /// Can auto-detect hierarchy
+    pub can_hierarchy_auto: bool,
+    /// Supports multistream filtering
+    pub can_multistream: bool,
+    /// Supports "turbo FEC" modulation
+    pub can_turbo_fec: bool,
+    /// Supports "2nd generation" modulation, e. g. DVB-S2, DVB-T2, DVB-C2
+    pub can_2g_modulation: bool,
+    /// Can stop spurious TS data output
+    pub can_mute_ts: bool,
+    /// Can recover from a cable unplug automatically.
+    /// Application should not retune after the lock is lost
+    pub can_recover: bool,
+    /// Supported FEC rates
+    pub fec_rates: Vec<fe_code_rate>,
+    /// Supported modulations
+    pub modulations: Vec<fe_modulation>,
+}
+
+const FEC_CAPS: &[(fe_caps, fe_code_rate)] = &[
+    (fe_caps::FE_CAN_FEC_1_2, FEC_1_2),
+    (fe_caps::FE_CAN_FEC_2_3, FEC_2_3),
+    (fe_caps::FE_CAN_FEC_3_4, FEC_3_4),
+    (fe_caps::FE_CAN_FEC_4_5, FEC_4_5),
+    (fe_caps::FE_CAN_FEC_5_6, FEC_5_6),
+    (fe_caps::FE_CAN_FEC_6_7, FEC_6_7),
+    (fe_caps::FE_CAN_FEC_7_8, FEC_7_8),
+    (fe_caps::FE_CAN_FEC_8_9, FEC_8_9),
+];
+
+const MODULATION_CAPS: &[(fe_caps, fe_modulation)] = &[
+    (fe_caps::FE_CAN_QPSK, QPSK),
+    (fe_caps::FE_CAN_QAM_16, QAM_16),
+    (fe_caps::FE_CAN_QAM_32, QAM_32),
+    (fe_caps::FE_CAN_QAM_64, QAM_64),
+    (fe_caps::FE_CAN_QAM_128, QAM_128),
+    (fe_caps::FE_CAN_QAM_256, QAM_256),
+    (fe_caps::FE_CAN_8VSB, VSB_8),
+    (fe_caps::FE_CAN_16VSB, VSB_16),
+];
+
+impl From<fe_caps> for Capabilities {
+    fn from(caps: fe_caps) -> Self {
+        Capabilities {
+            can_inversion_auto: caps.contains(fe_caps::FE_CAN_INVERSION_AUTO),
+            can_fec_auto: caps.contains(fe_caps::FE_CAN_FEC_AUTO),
+            can_qam_auto: caps.contains(fe_caps::FE_CAN_QAM_AUTO),
+            can_transmission_mode_auto: caps.contains(fe_caps::FE_CAN_TRANSMISSION_MODE_AUTO),
+            can_bandwidth_auto: caps.contains(fe_caps::FE_CAN_BANDWIDTH_AUTO),
+            can_guard_interval_auto: caps.contains(fe_caps::FE_CAN_GUARD_INTERVAL_AUTO),
+            can_hierarchy_auto: caps.contains(fe_caps::FE_CAN_HIERARCHY_AUTO),
+            can_multistream: caps.contains(fe_caps::FE_CAN_MULTISTREAM),
+            can_turbo_fec: caps.contains(fe_caps::FE_CAN_TURBO_FEC),
+            can_2g_modulation: caps.contains(fe_caps::FE_CAN_2G_MODULATION),
+            can_mute_ts: caps.contains(fe_caps::FE_CAN_MUTE_TS),
+            can_recover: caps.contains(fe_caps::FE_CAN_RECOVER),
+            fec_rates: FEC_CAPS
+                .iter()
+                .filter(|(cap, _)| caps.contains(*cap))
+                .map(|(_, fec)| *fec)
+                .collect(),
+            modulations: MODULATION_CAPS
+                .iter()
+                .filter(|(cap, _)| caps.contains(*cap))
+                .map(|(_, modulation)| *modulation)
+                .collect(),
+        }
+    }
+}
+
+/// Modulations and FEC rates valid for the delivery system
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliverySystemOptions {
+    pub delivery_system: fe_delivery_system,
+    /// Modulations defined by the standard and not excluded by the frontend capabilities
+    pub modulations: Vec<fe_modulation>,
+    /// FEC rates defined by the standard and not excluded by the frontend capabilities.
+    /// Empty if the FEC is not selectable
+    pub fec_rates: Vec<fe_code_rate>,
+}
+
+/// Returns modulations and FEC rates defined by the standard
+pub(crate) fn standard_options(
+    delivery_system: fe_delivery_system,
+) -> (&'static [fe_modulation], &'static [fe_code_rate]) {
+    match delivery_system {
+        SYS_DVBS => (&[QPSK], &[FEC_1_2, FEC_2_3, FEC_3_4, FEC_5_6, FEC_7_8]),
+        SYS_DVBS2 => (
+            &[QPSK, PSK_8, APSK_16, APSK_32],
+            &[
+                FEC_1_4, FEC_1_3, FEC_2_5, FEC_1_2, FEC_3_5, FEC_2_3, FEC_3_4, FEC_4_5, FEC_5_6,
+                FEC_8_9, FEC_9_10,
+            ],
+        ),
+        SYS_DVBT => (
+            &[QPSK, QAM_16, QAM_64],
+            &[FEC_1_2, FEC_2_3, FEC_3_4, FEC_5_6, FEC_7_8],
+        ),
+        SYS_DVBT2 => (
+            &[QPSK, QAM_16, QAM_64, QAM_256],
+            &[FEC_1_2, FEC_3_5, FEC_2_3, FEC_3_4, FEC_4_5, FEC_5_6],
+        ),
+        SYS_DVBC_ANNEX_A | SYS_DVBC_ANNEX_C => (&[QAM_16, QAM_32, QAM_64, QAM_128, QAM_256], &[]),
+        SYS_DVBC_ANNEX_B => (&[QAM_64, QAM_256], &[]),
+        SYS_ATSC => (&[VSB_8, VSB_16], &[]),
+        SYS_ISDBT => (
+            &[DQPSK, QPSK, QAM_16, QAM_64],
+            &[FEC_1_2, FEC_2_3, FEC_3_4, FEC_5_6, FEC_7_8],
+        ),
+        SYS_DTMB => (&[QAM_4_NR, QAM_16, QAM_32, QAM_64], &[FEC_2_5, FEC_3_5, FEC_4_5]),
+        _ => (&[], &[]),
+    }
+}
+
+impl DeliverySystemOptions {
+    /// Builds options for the delivery system.
+    /// Values with the capability flag are filtered with `caps`,
+    /// values without flag, for example 2nd generation FEC rates, are kept
+    pub fn new(delivery_system: fe_delivery_system, caps: fe_caps) -> Self {
+        let (modulations, fec_rates) = standard_options(delivery_system);
+
+        DeliverySystemOptions {
+            delivery_system,
+            modulations: modulations
+                .iter()
+                .filter(|m| {
+                    MODULATION_CAPS
+                        .iter()
+                        .all(|(cap, modulation)| modulation != *m || caps.contains(*cap))
+                })
+                .copied()
+                .collect(),
+            fec_rates: fec_rates
+                .iter()
+                .filter(|f| FEC_CAPS.iter().all(|(cap, fec)| fec != *f || caps.contains(*cap)))
+                .copied()
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use {
+    super::{sys::*, FeDevice},
+    crate::IoctlBackend,
+    anyhow::{Context, Result},
+    std::{thread, time::Duration},
+};
+
+/// Minimal gap between DiSEqC steps: LNB power settle,
+/// silence after command or burst, and before the continuous tone
+pub const DISEQC_DELAY: Duration = Duration::from_millis(15);
+
+/// Step of the switch control sequence
+#[derive(Debug, Clone)]
+pub enum DiseqcStep {
+    /// Sets LNB power
+    Voltage(fe_sec_voltage),
+    /// Sets continuous 22kHz tone
+    Tone(fe_sec_tone_mode),
+    /// Sends DiSEqC master command
+    Command(Vec<u8>),
+    /// Sends mini-DiSEqC tone burst
+    Burst(fe_sec_mini_cmd),
+}
+
+/// Sends steps to the frontend with `DISEQC_DELAY` after each step.
+/// Continuous tone is turned off before command or burst if it was turned on in the sequence
+///
+/// ```text
+/// diseqc::sequence(&fe, &[
+///     DiseqcStep::Voltage(SEC_VOLTAGE_18),
+///     DiseqcStep::Tone(SEC_TONE_OFF),
+///     DiseqcStep::Command(vec![0xE0, 0x10, 0x38, 0xF2]),
+///     DiseqcStep::Burst(SEC_MINI_A),
+///     DiseqcStep::Tone(SEC_TONE_ON),
+/// ])?;
+/// ```
+pub fn sequence<B: IoctlBackend>(fe: &FeDevice<B>, steps: &[DiseqcStep]) -> Result<()> {
+    let mut tone = SEC_TONE_OFF;
+
+    for step in steps {
+        if tone == SEC_TONE_ON && matches!(step, DiseqcStep::Command(..) | DiseqcStep::Burst(..)) {
+            tone = SEC_TONE_OFF;
+            fe.set_tone(SEC_TONE_OFF.into()).context("FE: diseqc sequence")?;
+            thread::sleep(DISEQC_DELAY);
+        }
+
+        match step {
+            DiseqcStep::Voltage(v) => fe.set_voltage((*v).into()),
+            DiseqcStep::Tone(v) => {
+                tone = *v;
+                fe.set_tone((*v).into())
+            }
+            DiseqcStep::Command(msg) => fe.diseqc_master_cmd(msg),
+            DiseqcStep::Burst(v) => fe.diseqc_send_burst(*v),
+        }
+        .context("FE: diseqc sequence")?;
+
+        thread::sleep(DISEQC_DELAY);
+    }
+
+    Ok(())
+}
+
+/// DiSEqC framing byte: command from master, reply required, first transmission
+const FRAMING_REPLY_REQUIRED: u8 = 0xE2;
+/// DiSEqC framing byte: reply from slave, no errors detected
+const FRAMING_REPLY_OK: u8 = 0xE4;
+
+/// DiSEqC 1.2 positioner command: read status
+const CMD_POSITIONER_STATUS: u8 = 0x64;
+
+/// Time to wait for the slave reply, in milliseconds
+const REPLY_TIMEOUT: u32 = 250;
+
+/// Status of the DiSEqC 1.2 positioner
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct PositionerStatus {
+    /// Last command completed
+    pub completed: bool,
+    /// Software limits are enabled
+    pub limits_enabled: bool,
+    /// Moving or last movement was to the west
+    pub west: bool,
+    /// Motor is running
+    pub running: bool,
+    /// Software limit reached
+    pub limit_reached: bool,
+    /// Motor power is not available
+    pub power_fault: bool,
+    /// Hardware limit switch activated
+    pub hardware_switch: bool,
+    /// Position reference data lost or corrupted
+    pub reference_lost: bool,
+}
+
+impl From<u8> for PositionerStatus {
+    fn from(value: u8) -> Self {
+        PositionerStatus {
+            completed: value & 0x80 != 0,
+            limits_enabled: value & 0x40 != 0,
+            west: value & 0x20 != 0,
+            running: value & 0x10 != 0,
+            limit_reached: value & 0x08 != 0,
+            power_fault: value & 0x04 != 0,
+            hardware_switch: value & 0x02 != 0,
+            reference_lost: value & 0x01 != 0,
+        }
+    }
+}
+
+/// DiSEqC 1.2 positioner (motorized dish)
+#[derive(Debug, Copy, Clone)]
+pub struct Positioner {
+    address: u8,
+}
+
+impl Default for Positioner {
+    fn default() -> Self {
+        Positioner::new(0x31)
+    }
+}
+
+impl Positioner {
+    /// Creates positioner with DiSEqC `address`:
+    /// 0x30 - any positioner, 0x31 - polar/azimuth positioner (default)
+    pub fn new(address: u8) -> Self {
+        Positioner { address }
+    }
+
+    /// Queries positioner status.
+    /// Requires two-way DiSEqC. Returns `None` if positioner did not reply
+    pub fn read_status<B: IoctlBackend>(&self, fe: &FeDevice<B>) -> Result<Option<PositionerStatus>> {
+        fe.diseqc_master_cmd(&[FRAMING_REPLY_REQUIRED, self.address, CMD_POSITIONER_STATUS])
+            .context("FE: positioner status")?;
+
+        let reply = fe
+            .diseqc_recv_slave_reply(REPLY_TIMEOUT)
+            .context("FE: positioner status")?;
+
+        match reply.as_slice() {
+            [] => Ok(None),
+            [FRAMING_REPLY_OK, status, ..] => Ok(Some(PositionerStatus::from(*status))),
+            [framing, ..] => Err(anyhow!(
+                "FE: positioner status: unexpected reply 0x{:02X}",
+                framing
+            )),
+        }
+    }
+}
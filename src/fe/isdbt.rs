@@ -0,0 +1,205 @@
+use {
+    super::{sys::*, Bandwidth, FeDevice, Frequency},
+    crate::{dtv_property, IoctlBackend},
+    anyhow::{Context, Result},
+};
+
+/// ISDB-T hierarchical layer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IsdbtLayer {
+    A = 0,
+    B = 1,
+    C = 2,
+}
+
+impl IsdbtLayer {
+    /// Returns bit of the layer in the DTV_ISDBT_LAYER_ENABLED bitmap
+    #[inline]
+    pub fn bit(self) -> u32 {
+        1 << (self as u32)
+    }
+}
+
+/// Parameters of the ISDB-T layer
+#[derive(Debug, Copy, Clone)]
+pub struct IsdbtLayerParams {
+    /// Inner FEC rate
+    pub fec: fe_code_rate,
+    /// Modulation: DQPSK, QPSK, QAM_16 or QAM_64
+    pub modulation: fe_modulation,
+    /// Number of segments: 0 to 13
+    pub segment_count: u32,
+    /// Time interleaving length: 0, 1, 2 or 4
+    pub time_interleaving: u32,
+}
+
+/// ISDB-T tuning parameters
+///
+/// ```text
+/// let params = IsdbtParams::new(557_142_857)
+///     .layer(IsdbtLayer::A, IsdbtLayerParams {
+///         fec: FEC_2_3,
+///         modulation: QPSK,
+///         segment_count: 1,
+///         time_interleaving: 4,
+///     })
+///     .layer(IsdbtLayer::B, IsdbtLayerParams {
+///         fec: FEC_3_4,
+///         modulation: QAM_64,
+///         segment_count: 12,
+///         time_interleaving: 2,
+///     });
+/// fe.tune_isdbt(&params)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct IsdbtParams {
+    frequency: Frequency,
+    bandwidth_hz: u32,
+    partial_reception: Option<bool>,
+    layers: [Option<IsdbtLayerParams>; 3],
+    layer_enabled: Option<u32>,
+}
+
+impl IsdbtParams {
+    /// Creates parameters for frequency in Hz with 6MHz bandwidth and no layers configured
+    #[inline]
+    pub fn new(frequency: u32) -> Self {
+        Self::with_frequency(Frequency::from_hz(u64::from(frequency)))
+    }
+
+    /// Creates parameters for frequency with 6MHz bandwidth and no layers configured
+    pub fn with_frequency(frequency: Frequency) -> Self {
+        IsdbtParams {
+            frequency,
+            bandwidth_hz: 6_000_000,
+            partial_reception: None,
+            layers: [None; 3],
+            layer_enabled: None,
+        }
+    }
+
+    /// Sets channel bandwidth in Hz
+    #[inline]
+    pub fn bandwidth_hz(mut self, bandwidth_hz: u32) -> Self {
+        self.bandwidth_hz = bandwidth_hz;
+        self
+    }
+
+    /// Sets standard channel bandwidth: 6, 7 or 8MHz
+    #[inline]
+    pub fn bandwidth(self, bandwidth: Bandwidth) -> Self {
+        self.bandwidth_hz(bandwidth.hz())
+    }
+
+    /// Sets partial reception (1-seg) flag
+    #[inline]
+    pub fn partial_reception(mut self, value: bool) -> Self {
+        self.partial_reception = Some(value);
+        self
+    }
+
+    /// Configures the layer
+    #[inline]
+    pub fn layer(mut self, layer: IsdbtLayer, params: IsdbtLayerParams) -> Self {
+        self.layers[layer as usize] = Some(params);
+        self
+    }
+
+    /// Sets bitmap of the layers to be decoded.
+    /// By default all configured layers are enabled
+    #[inline]
+    pub fn layer_enabled(mut self, bitmap: u32) -> Self {
+        self.layer_enabled = Some(bitmap);
+        self
+    }
+
+    fn get_layer_enabled(&self) -> u32 {
+        self.layers
+            .iter()
+            .zip([IsdbtLayer::A, IsdbtLayer::B, IsdbtLayer::C])
+            .filter(|(params, _)| params.is_some())
+            .fold(0, |bitmap, (_, layer)| bitmap | layer.bit())
+    }
+
+    /// Validates parameters and returns property sequence without DTV_TUNE
+    pub fn to_properties(&self) -> Result<Vec<DtvProperty>> {
+        let configured = self.get_layer_enabled();
+        let layer_enabled = self.layer_enabled.unwrap_or(configured);
+
+        ensure!(configured != 0, "ISDB-T: no layers configured");
+        ensure!(
+            layer_enabled != 0 && layer_enabled & !configured == 0,
+            "ISDB-T: enabled layers 0x{:X} are not configured (0x{:X})",
+            layer_enabled,
+            configured
+        );
+
+        ensure!(
+            matches!(Bandwidth::from_hz(self.bandwidth_hz), Some(bw) if bw.is_allowed(SYS_ISDBT)),
+            "ISDB-T: invalid bandwidth {}Hz",
+            self.bandwidth_hz
+        );
+
+        let mut segments = 0;
+        for params in self.layers.iter().flatten() {
+            ensure!(
+                matches!(params.modulation, DQPSK | QPSK | QAM_16 | QAM_64 | QAM_AUTO),
+                "ISDB-T: invalid layer modulation {:?}",
+                params.modulation
+            );
+            ensure!(
+                params.segment_count <= 13,
+                "ISDB-T: invalid segment count {}",
+                params.segment_count
+            );
+            ensure!(
+                matches!(params.time_interleaving, 0 | 1 | 2 | 4),
+                "ISDB-T: invalid time interleaving {}",
+                params.time_interleaving
+            );
+            segments += params.segment_count;
+        }
+        ensure!(segments <= 13, "ISDB-T: total segment count {} is greater than 13", segments);
+
+        let mut props = vec![
+            dtv_property!(DTV_DELIVERY_SYSTEM(SYS_ISDBT)),
+            dtv_property!(DTV_FREQUENCY(self.frequency.to_property(SYS_ISDBT)?)),
+            dtv_property!(DTV_BANDWIDTH_HZ(self.bandwidth_hz)),
+        ];
+
+        if let Some(v) = self.partial_reception {
+            props.push(dtv_property!(DTV_ISDBT_PARTIAL_RECEPTION(v as u32)));
+        }
+
+        props.push(dtv_property!(DTV_ISDBT_LAYER_ENABLED(layer_enabled)));
+
+        if let Some(p) = &self.layers[IsdbtLayer::A as usize] {
+            props.push(dtv_property!(DTV_ISDBT_LAYERA_FEC(p.fec)));
+            props.push(dtv_property!(DTV_ISDBT_LAYERA_MODULATION(p.modulation)));
+            props.push(dtv_property!(DTV_ISDBT_LAYERA_SEGMENT_COUNT(p.segment_count)));
+            props.push(dtv_property!(DTV_ISDBT_LAYERA_TIME_INTERLEAVING(p.time_interleaving)));
+        }
+        if let Some(p) = &self.layers[IsdbtLayer::B as usize] {
+            props.push(dtv_property!(DTV_ISDBT_LAYERB_FEC(p.fec)));
+            props.push(dtv_property!(DTV_ISDBT_LAYERB_MODULATION(p.modulation)));
+            props.push(dtv_property!(DTV_ISDBT_LAYERB_SEGMENT_COUNT(p.segment_count)));
+            props.push(dtv_property!(DTV_ISDBT_LAYERB_TIME_INTERLEAVING(p.time_interleaving)));
+        }
+        if let Some(p) = &self.layers[IsdbtLayer::C as usize] {
+            props.push(dtv_property!(DTV_ISDBT_LAYERC_FEC(p.fec)));
+            props.push(dtv_property!(DTV_ISDBT_LAYERC_MODULATION(p.modulation)));
+            props.push(dtv_property!(DTV_ISDBT_LAYERC_SEGMENT_COUNT(p.segment_count)));
+            props.push(dtv_property!(DTV_ISDBT_LAYERC_TIME_INTERLEAVING(p.time_interleaving)));
+        }
+
+        Ok(props)
+    }
+}
+
+impl<B: IoctlBackend> FeDevice<B> {
+    /// Tunes ISDB-T frontend with layers configuration
+    pub fn tune_isdbt(&self, params: &IsdbtParams) -> Result<()> {
+        let props = params.to_properties()?;
+        self.tune_raw(&props).context("FE: tune ISDB-T")
+    }
+}
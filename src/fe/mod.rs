@@ -1,32 +1,66 @@
 #![allow(dead_code)]
+mod caps;
+pub mod diseqc;
+mod isdbt;
+mod satellite;
+mod scan;
 mod status;
 pub mod sys;
+mod tuning;
 
 use {
     anyhow::{Context, Result},
-    nix::{ioctl_read, ioctl_write_int_bad, ioctl_write_ptr, request_code_none},
+    nix::{
+        errno::Errno, request_code_none, request_code_read, request_code_write, unistd::close,
+    },
     std::{
         ffi::CStr,
         fmt,
         fs::{File, OpenOptions},
+        mem,
         ops::Range,
         os::unix::{
             fs::{FileTypeExt, OpenOptionsExt},
-            io::{AsRawFd, RawFd},
+            io::{AsRawFd, IntoRawFd, RawFd},
         },
+        ptr,
+        sync::Mutex,
+        thread,
+        time::{Duration, Instant},
     },
     sys::*,
+    diseqc::DiseqcStep,
+    crate::{util, DvbDevice, DvbError, IoctlBackend, SysBackend},
 };
 
-pub use status::FeStatus;
+/// Default path template of the frontend device
+pub const FE_PATH_TEMPLATE: &str = "/dev/dvb/adapter{adapter}/frontend{device}";
+
+/// Time to wait for LNB power to settle
+const LNB_SETTLE_DELAY: Duration = Duration::from_millis(15);
+
+pub use {
+    caps::{Capabilities, DeliverySystemOptions},
+    isdbt::{IsdbtLayer, IsdbtLayerParams, IsdbtParams},
+    satellite::{Band, Lnb, Polarization, SatelliteTuning},
+    scan::{
+        scan_from_initial, scan_from_initial_with_progress, scan_network,
+        scan_network_with_progress, to_dvbv5_conf, DtvPropertyReader, InitialTuning,
+        ScanProgress, ScanStep, Service, SCAN_LOCK_TIMEOUT,
+    },
+    status::{FeStatus, StatPrefs},
+    tuning::{Bandwidth, Frequency, SymbolRate, TuningConfig},
+};
 
-/// A reference to the frontend device and device information
+/// A reference to the frontend device and device information.
+/// ioctl calls are performed with backend `B`
 #[derive(Debug)]
-pub struct FeDevice {
+pub struct FeDevice<B = SysBackend> {
     adapter: u32,
     device: u32,
 
     file: File,
+    backend: B,
 
     api_version: u16,
 
@@ -35,9 +69,56 @@ pub struct FeDevice {
     frequency_range: Range<u32>,
     symbolrate_range: Range<u32>,
     caps: fe_caps,
+    fe_type: fe_type,
+
+    last_tuning: Mutex<LastTuning>,
+    sec_state: Mutex<SecState>,
 }
 
-impl fmt::Display for FeDevice {
+/// Last LNB power, tone and switch commands sent to the frontend
+#[derive(Debug, Default, Copy, Clone)]
+struct SecState {
+    voltage: Option<fe_sec_voltage>,
+    tone: Option<fe_sec_tone_mode>,
+    diseqc: Option<DiseqcMasterCmd>,
+    burst: Option<fe_sec_mini_cmd>,
+}
+
+impl SecState {
+    /// Switch loses selected input without LNB power
+    fn set_voltage(&mut self, voltage: Option<fe_sec_voltage>) {
+        if voltage == Some(SEC_VOLTAGE_OFF) {
+            self.diseqc = None;
+            self.burst = None;
+        }
+        self.voltage = voltage;
+    }
+
+    /// Returns steps restoring the state: LNB power, DiSEqC command, tone burst and 22kHz tone
+    fn to_diseqc_sequence(self) -> Vec<DiseqcStep> {
+        let mut steps = Vec::new();
+        steps.extend(self.voltage.map(DiseqcStep::Voltage));
+
+        if self.diseqc.is_some() || self.burst.is_some() {
+            steps.push(DiseqcStep::Tone(SEC_TONE_OFF));
+        }
+        steps.extend(self.diseqc.map(|cmd| DiseqcStep::Command(cmd.data().to_vec())));
+        steps.extend(self.burst.map(DiseqcStep::Burst));
+        steps.extend(self.tone.map(DiseqcStep::Tone));
+
+        steps
+    }
+}
+
+/// Last successful tuning, replayed on `FE_REINIT`
+#[derive(Debug, Default, Clone)]
+struct LastTuning {
+    /// LNB state commanded before the tuning
+    sec: SecState,
+    properties: Vec<DtvProperty>,
+}
+
+impl<B> fmt::Display for FeDevice<B> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(
             f,
@@ -53,11 +134,16 @@ impl fmt::Display for FeDevice {
         }
         writeln!(f, "")?;
 
+        // legacy frontend type defines units of the frequency range
+        let frequency = |v: u32| match self.fe_type {
+            FE_QPSK => Frequency::from_khz(u64::from(v)),
+            _ => Frequency::from_hz(u64::from(v)),
+        };
         writeln!(
             f,
             "Frequency range: {} .. {}",
-            self.frequency_range.start / 1000,
-            self.frequency_range.end / 1000
+            frequency(self.frequency_range.start),
+            frequency(self.frequency_range.end)
         )?;
 
         writeln!(
@@ -73,13 +159,32 @@ impl fmt::Display for FeDevice {
     }
 }
 
-impl AsRawFd for FeDevice {
+impl<B> AsRawFd for FeDevice<B> {
     #[inline]
     fn as_raw_fd(&self) -> RawFd {
         self.file.as_raw_fd()
     }
 }
 
+impl<B> IntoRawFd for FeDevice<B> {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
+impl<B> DvbDevice for FeDevice<B> {
+    #[inline]
+    fn adapter(&self) -> u32 {
+        self.adapter
+    }
+
+    #[inline]
+    fn device(&self) -> u32 {
+        self.device
+    }
+}
+
 #[macro_export]
 macro_rules! get_dtv_properties {
     ( $device:expr, $( $property:ident ),+ ) => { (|| -> ::anyhow::Result<_> {
@@ -106,7 +211,65 @@ macro_rules! set_dtv_properties {
     };
 }
 
+/// Checks DTV_STREAM_ID value for the delivery system
+fn check_stream_id(delivery_system: fe_delivery_system, id: u32) -> Result<()> {
+    if id == NO_STREAM_ID_FILTER {
+        return Ok(());
+    }
+
+    let max = match delivery_system {
+        SYS_DVBT2 | SYS_DVBC2 => 0xFF,
+        SYS_DVBS2 => (1 << 28) - 1,
+        SYS_ISDBS => 0xFFFF,
+        v => bail!("FE: stream id is not available for {}", v),
+    };
+    ensure!(id <= max, "FE: invalid stream id {} for {}", id, delivery_system);
+
+    Ok(())
+}
+
 impl FeDevice {
+    /// Attempts to open frontend device with the path built from `template`,
+    /// for example `/dev/dvb/adapter{adapter}/frontend{device}`.
+    /// Template should contain both placeholders
+    pub fn open_with_template(
+        adapter: u32,
+        device: u32,
+        template: &str,
+        is_write: bool,
+    ) -> Result<FeDevice> {
+        let path = util::device_path(template, adapter, device).context("FE: open")?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(is_write)
+            .custom_flags(::nix::libc::O_NONBLOCK)
+            .open(&path)
+            .with_context(|| format!("FE: failed to open device {}", &path))?;
+
+        FeDevice::with_backend(adapter, device, file, SysBackend)
+    }
+
+    /// Attempts to open frontend device in read-only mode
+    #[inline]
+    pub fn open_ro(adapter: u32, device: u32) -> Result<FeDevice> {
+        Self::open_with_template(adapter, device, FE_PATH_TEMPLATE, false)
+    }
+
+    /// Attempts to open frontend device in read-write mode
+    #[inline]
+    pub fn open_rw(adapter: u32, device: u32) -> Result<FeDevice> {
+        Self::open_with_template(adapter, device, FE_PATH_TEMPLATE, true)
+    }
+}
+
+impl<B: IoctlBackend> FeDevice<B> {
+    /// Closes device and reports error unlike drop
+    pub fn close(self) -> Result<()> {
+        close(self.into_raw_fd()).context("FE: close")?;
+
+        Ok(())
+    }
+
     /// Clears frontend settings and event queue
     pub fn clear(&self) -> Result<()> {
         set_dtv_properties!(
@@ -132,14 +295,9 @@ impl FeDevice {
         let mut feinfo = FeInfo::default();
 
         // FE_GET_INFO
-        ioctl_read!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            61,
-            FeInfo
-        );
-        unsafe { ioctl_call(self.as_raw_fd(), &mut feinfo as *mut _) }.context("FE: get info")?;
+        let request = request_code_read!(b'o', 61, mem::size_of::<FeInfo>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &mut feinfo as *mut _ as _) }
+            .context("FE: get info")?;
 
         if let Some(len) = feinfo.name.iter().position(|&b| b == 0) {
             let name = unsafe { CStr::from_ptr(feinfo.name[..len + 1].as_ptr()) };
@@ -152,17 +310,31 @@ impl FeDevice {
         self.symbolrate_range = feinfo.symbol_rate_min..feinfo.symbol_rate_max;
 
         self.caps = feinfo.caps;
+        self.fe_type = feinfo.fe_type;
 
         // DVB v5 properties
-        let (api_version, enum_delsys) =
-            get_dtv_properties!(self, DTV_API_VERSION, DTV_ENUM_DELSYS)
-                .context("FE: get api version (deprecated driver)")?;
+        match get_dtv_properties!(self, DTV_API_VERSION, DTV_ENUM_DELSYS) {
+            Ok((api_version, enum_delsys)) if !enum_delsys.is_empty() => {
+                // DVB API Version
+                self.api_version = api_version as u16;
 
-        // DVB API Version
-        self.api_version = api_version as u16;
-
-        // Suppoerted delivery systems
-        self.delivery_system_list = enum_delsys;
+                // Suppoerted delivery systems
+                self.delivery_system_list = enum_delsys;
+            }
+            _ => {
+                // DTV_ENUM_DELSYS is available since DVB API 5.5
+                let (api_version,) = get_dtv_properties!(self, DTV_API_VERSION)
+                    .context("FE: get api version (deprecated driver)")?;
+                self.api_version = api_version as u16;
+
+                self.delivery_system_list = self.fe_type.delivery_systems(self.caps);
+                ensure!(
+                    !self.delivery_system_list.is_empty(),
+                    "FE: no delivery systems for legacy type {:?}",
+                    self.fe_type
+                );
+            }
+        }
 
         // dev-file metadata
 
@@ -176,20 +348,15 @@ impl FeDevice {
         Ok(())
     }
 
-    fn open(adapter: u32, device: u32, is_write: bool) -> Result<FeDevice> {
-        let path = format!("/dev/dvb/adapter{}/frontend{}", adapter, device);
-        let file = OpenOptions::new()
-            .read(true)
-            .write(is_write)
-            .custom_flags(::nix::libc::O_NONBLOCK)
-            .open(&path)
-            .with_context(|| format!("FE: failed to open device {}", &path))?;
-
+    /// Creates frontend device over the opened `file` with ioctl `backend`
+    /// and reads frontend information
+    pub fn with_backend(adapter: u32, device: u32, file: File, backend: B) -> Result<Self> {
         let mut fe = FeDevice {
             adapter,
             device,
 
             file,
+            backend,
 
             api_version: 0,
 
@@ -198,6 +365,10 @@ impl FeDevice {
             frequency_range: 0..0,
             symbolrate_range: 0..0,
             caps: fe_caps::FE_IS_STUPID,
+            fe_type: FE_QPSK,
+
+            last_tuning: Mutex::new(LastTuning::default()),
+            sec_state: Mutex::new(SecState::default()),
         };
 
         fe.get_info()?;
@@ -205,19 +376,58 @@ impl FeDevice {
         Ok(fe)
     }
 
-    /// Attempts to open frontend device in read-only mode
+    /// Re-reads frontend information: name, frequency and symbolrate ranges,
+    /// capabilities and supported delivery systems.
+    /// Multi-standard tuners may change it after delivery system switch.
+    /// Values are cached on open and not updated by tuning
     #[inline]
-    pub fn open_ro(adapter: u32, device: u32) -> Result<FeDevice> {
-        Self::open(adapter, device, false)
+    pub fn refresh_info(&mut self) -> Result<()> {
+        self.get_info()
     }
 
-    /// Attempts to open frontend device in read-write mode
-    #[inline]
-    pub fn open_rw(adapter: u32, device: u32) -> Result<FeDevice> {
-        Self::open(adapter, device, true)
+    /// Creates new handle to the same frontend with duplicated file descriptor.
+    ///
+    /// Clone is intended for the signal monitoring from another thread:
+    /// `read_status`, `read_signal_strength`, `read_snr`, `read_ber`, `read_unc`,
+    /// `get_properties` and `FeStatus::read` are safe while the original handle tunes.
+    ///
+    /// Clone shares access mode and event queue with the original handle,
+    /// `get_event` on the clone takes events from the tuning thread.
+    /// Tuning and LNB control should be done with one handle,
+    /// last tuning and commanded LNB state are not shared between handles
+    pub fn try_clone(&self) -> Result<Self>
+    where
+        B: Clone,
+    {
+        let file = self.file.try_clone().context("FE: clone")?;
+
+        Ok(FeDevice {
+            adapter: self.adapter,
+            device: self.device,
+
+            file,
+            backend: self.backend.clone(),
+
+            api_version: self.api_version,
+
+            name: self.name.clone(),
+            delivery_system_list: self.delivery_system_list.clone(),
+            frequency_range: self.frequency_range.clone(),
+            symbolrate_range: self.symbolrate_range.clone(),
+            caps: self.caps,
+            fe_type: self.fe_type,
+
+            last_tuning: Mutex::new(self.last_tuning.lock().unwrap().clone()),
+            sec_state: Mutex::new(*self.sec_state.lock().unwrap()),
+        })
     }
 
     fn check_properties(&self, cmdseq: &[DtvProperty]) -> Result<()> {
+        let delivery_system = cmdseq.iter().find_map(|p| match p {
+            DTV_DELIVERY_SYSTEM(d) => d.get().ok(),
+            _ => None,
+        });
+
         for p in cmdseq {
             match p {
                 DTV_FREQUENCY(d) => {
@@ -227,10 +437,20 @@ impl FeDevice {
                     );
                 }
                 DTV_SYMBOL_RATE(d) => {
-                    ensure!(
-                        self.symbolrate_range.contains(&d.get()?),
-                        "FE: symbolrate out of range"
-                    );
+                    let bauds = d.get()?;
+                    if !self.symbolrate_range.contains(&bauds) {
+                        let hint = match bauds.checked_mul(1_000) {
+                            Some(v) if self.symbolrate_range.contains(&v) => " (value in kSym/s?)",
+                            _ => "",
+                        };
+                        bail!(
+                            "FE: symbol rate {} out of range {} .. {}{}",
+                            SymbolRate::from_bauds(bauds),
+                            SymbolRate::from_bauds(self.symbolrate_range.start),
+                            SymbolRate::from_bauds(self.symbolrate_range.end),
+                            hint
+                        );
+                    }
                 }
                 DTV_INVERSION(d) => {
                     if d.get()? == INVERSION_AUTO {
@@ -264,11 +484,41 @@ impl FeDevice {
                         );
                     }
                 }
-                DTV_STREAM_ID(..) => {
+                DTV_INNER_FEC(d) => {
+                    ensure!(
+                        d.get()? != FEC_AUTO || self.caps.contains(fe_caps::FE_CAN_FEC_AUTO),
+                        "FE: no auto inner fec"
+                    );
+                }
+                DTV_MODULATION(d) => {
+                    ensure!(
+                        d.get()? != QAM_AUTO || self.caps.contains(fe_caps::FE_CAN_QAM_AUTO),
+                        "FE: no auto modulation"
+                    );
+                }
+                DTV_BANDWIDTH_HZ(d) => {
+                    // 0 is auto
+                    let hz = d.get()?;
+                    if let Some(sys @ (SYS_DVBT | SYS_DVBT2 | SYS_ISDBT | SYS_DTMB | SYS_DVBC2)) =
+                        delivery_system
+                    {
+                        ensure!(
+                            hz == 0
+                                || matches!(Bandwidth::from_hz(hz), Some(bw) if bw.is_allowed(sys)),
+                            "FE: invalid bandwidth {}Hz for {}",
+                            hz,
+                            sys
+                        );
+                    }
+                }
+                DTV_STREAM_ID(d) => {
                     ensure!(
                         self.caps.contains(fe_caps::FE_CAN_MULTISTREAM),
                         "FE: no multistream"
                     );
+                    if let Some(sys) = delivery_system {
+                        check_stream_id(sys, d.get()?)?;
+                    }
                 }
                 _ => {}
             }
@@ -277,8 +527,19 @@ impl FeDevice {
         Ok(())
     }
 
-    /// Sets properties on frontend device
+    /// Sets properties on frontend device.
+    /// Empty sequence is not passed to the driver
     pub fn set_properties(&self, cmdseq: &[DtvProperty]) -> Result<()> {
+        if cmdseq.is_empty() {
+            return Ok(());
+        }
+
+        ensure!(
+            cmdseq.len() <= DTV_IOCTL_MAX_MSGS,
+            "FE: too many properties ({} > DTV_IOCTL_MAX_MSGS {})",
+            cmdseq.len(),
+            DTV_IOCTL_MAX_MSGS
+        );
         self.check_properties(cmdseq)?;
 
         #[repr(C)]
@@ -293,20 +554,116 @@ impl FeDevice {
         };
 
         // FE_SET_PROPERTY
-        ioctl_write_ptr!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            82,
-            DtvProperties
-        );
-        unsafe { ioctl_call(self.as_raw_fd(), &cmd as *const _) }.context("FE: set properties")?;
+        let request = request_code_write!(b'o', 82, mem::size_of::<DtvProperties>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &cmd as *const _ as _) }
+            .context("FE: set properties")?;
+
+        let mut sec_state = self.sec_state.lock().unwrap();
+        for p in cmdseq {
+            match p {
+                DTV_VOLTAGE(d) => sec_state.set_voltage(d.get().ok()),
+                DTV_TONE(d) => sec_state.tone = d.get().ok(),
+                _ => {}
+            }
+        }
+
+        if cmdseq.iter().any(|p| matches!(p, DTV_TUNE(..))) {
+            *self.last_tuning.lock().unwrap() = LastTuning {
+                sec: *sec_state,
+                properties: cmdseq.to_vec(),
+            };
+        }
 
         Ok(())
     }
 
-    /// Gets properties from frontend device
+    /// Sets properties from iterator without intermediate heap allocation.
+    /// Properties are collected into the stack buffer of `DTV_IOCTL_MAX_MSGS` items
+    pub fn set_properties_iter<I>(&self, cmdseq: I) -> Result<()>
+    where
+        I: IntoIterator<Item = DtvProperty>,
+    {
+        let mut buffer = [DTV_CLEAR(DtvPropertyRequest::default()); DTV_IOCTL_MAX_MSGS];
+        let mut len = 0;
+
+        for property in cmdseq {
+            ensure!(
+                len < DTV_IOCTL_MAX_MSGS,
+                "FE: too many properties (> DTV_IOCTL_MAX_MSGS {})",
+                DTV_IOCTL_MAX_MSGS
+            );
+            buffer[len] = property;
+            len += 1;
+        }
+
+        self.set_properties(&buffer[..len])
+    }
+
+    /// Restores LNB power, DiSEqC switch and 22kHz tone set before the last tuning
+    /// and sends again the last successful property sequence with `DTV_TUNE`.
+    /// Should be used when frontend reports `FE_REINIT`,
+    /// because DiSEqC, tone, voltage and tuning parameters are lost.
+    pub fn reapply_last_tuning(&self) -> Result<()> {
+        let last = self.last_tuning.lock().unwrap().clone();
+        ensure!(!last.properties.is_empty(), "FE: no tuning to reapply");
+
+        diseqc::sequence(self, &last.sec.to_diseqc_sequence()).context("FE: reapply tuning")?;
+        self.set_properties(&last.properties).context("FE: reapply tuning")
+    }
+
+    /// Checks `status` for the `FE_REINIT` flag and reapplies the last tuning if it is set.
+    /// Returns `true` if tuning was reapplied
+    pub fn handle_reinit(&self, status: fe_status) -> Result<bool> {
+        if status.contains(fe_status::FE_REINIT) {
+            self.reset_sec_state();
+        }
+
+        if !status.contains(fe_status::FE_REINIT)
+            || self.last_tuning.lock().unwrap().properties.is_empty()
+        {
+            return Ok(false);
+        }
+
+        self.reapply_last_tuning()?;
+
+        Ok(true)
+    }
+
+    /// Sets properties on frontend device and starts tuning.
+    /// `DTV_TUNE` is appended to the end of the sequence automatically
+    /// and should not be included into `cmdseq`.
+    pub fn tune_raw(&self, cmdseq: &[DtvProperty]) -> Result<()> {
+        ensure!(
+            cmdseq.len() < DTV_IOCTL_MAX_MSGS,
+            "FE: too many properties to tune ({} > {})",
+            cmdseq.len() + 1,
+            DTV_IOCTL_MAX_MSGS
+        );
+        ensure!(
+            !cmdseq.iter().any(|p| matches!(p, DTV_TUNE(..))),
+            "FE: DTV_TUNE should not be in the tuning sequence"
+        );
+
+        let mut props = cmdseq.to_vec();
+        props.push(dtv_property!(DTV_TUNE(())));
+
+        self.set_properties(&props).context("FE: tune")
+    }
+
+    /// Gets properties from frontend device.
+    /// Empty sequence is not passed to the driver
     pub fn get_properties(&self, cmdseq: &mut [DtvProperty]) -> Result<()> {
+        if cmdseq.is_empty() {
+            return Ok(());
+        }
+
+        ensure!(
+            cmdseq.len() <= DTV_IOCTL_MAX_MSGS,
+            "FE: too many properties ({} > DTV_IOCTL_MAX_MSGS {})",
+            cmdseq.len(),
+            DTV_IOCTL_MAX_MSGS
+        );
+
         #[repr(C)]
         pub struct DtvProperties {
             num: u32,
@@ -319,34 +676,165 @@ impl FeDevice {
         };
 
         // FE_GET_PROPERTY
-        ioctl_read!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            83,
-            DtvProperties
-        );
-        unsafe { ioctl_call(self.as_raw_fd(), &mut cmd as *mut _) }
+        let request = request_code_read!(b'o', 83, mem::size_of::<DtvProperties>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &mut cmd as *mut _ as _) }
             .context("FE: get properties")?;
 
         Ok(())
     }
 
+    /// Returns current delivery system
+    pub fn get_delivery_system(&self) -> Result<fe_delivery_system> {
+        let (v,) =
+            get_dtv_properties!(self, DTV_DELIVERY_SYSTEM).context("FE: get delivery system")?;
+        Ok(v)
+    }
+
+    /// Returns current frequency.
+    /// Frequency is in kHz for Satellite systems and in Hz for others
+    pub fn get_frequency(&self) -> Result<u32> {
+        let (v,) = get_dtv_properties!(self, DTV_FREQUENCY).context("FE: get frequency")?;
+        Ok(v)
+    }
+
+    /// Returns current symbol rate in bauds
+    pub fn get_symbol_rate(&self) -> Result<u32> {
+        let (v,) = get_dtv_properties!(self, DTV_SYMBOL_RATE).context("FE: get symbol rate")?;
+        Ok(v)
+    }
+
+    /// Returns current modulation
+    pub fn get_modulation(&self) -> Result<fe_modulation> {
+        let (v,) = get_dtv_properties!(self, DTV_MODULATION).context("FE: get modulation")?;
+        Ok(v)
+    }
+
+    /// Returns current inner FEC rate
+    pub fn get_inner_fec(&self) -> Result<fe_code_rate> {
+        let (v,) = get_dtv_properties!(self, DTV_INNER_FEC).context("FE: get inner fec")?;
+        Ok(v)
+    }
+
     /// Returns a frontend events if available
     pub fn get_event(&self, event: &mut FeEvent) -> Result<()> {
         // FE_GET_EVENT
-        ioctl_read!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            78,
-            FeEvent
-        );
-        unsafe { ioctl_call(self.as_raw_fd(), event as *mut _) }.context("FE: get event")?;
+        let request = request_code_read!(b'o', 78, mem::size_of::<FeEvent>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, event as *mut _ as _) }
+            .context("FE: get event")?;
 
         Ok(())
     }
 
+    /// Returns a frontend event or `None` if the event queue is empty
+    pub fn try_get_event(&self) -> Result<Option<FeEvent>> {
+        let mut event = FeEvent::default();
+
+        // FE_GET_EVENT
+        let request = request_code_read!(b'o', 78, mem::size_of::<FeEvent>());
+        match unsafe { self.backend.ioctl(self.as_raw_fd(), request, &mut event as *mut _ as _) } {
+            Ok(_) => Ok(Some(event)),
+            Err(Errno::EWOULDBLOCK) => Ok(None),
+            Err(e) => Err(e).context("FE: get event"),
+        }
+    }
+
+    /// Returns all queued frontend events, no more than `FE_MAX_EVENT`.
+    /// Events lost on the queue overflow are skipped
+    pub fn drain_events(&self) -> Result<Vec<FeEvent>> {
+        let mut events = Vec::new();
+        let mut overflow = false;
+
+        while events.len() < FE_MAX_EVENT {
+            match self.try_get_event() {
+                Ok(Some(event)) => events.push(event),
+                Ok(None) => break,
+                // kernel reports overflow once and keeps the latest events
+                Err(e) if !overflow && e.downcast_ref() == Some(&Errno::EOVERFLOW) => {
+                    overflow = true;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Returns the center frequency the frontend locked on, including offset applied by the driver.
+    /// Takes frequency from the newest pending lock event if available,
+    /// otherwise reads DTV_FREQUENCY property.
+    /// Note that all pending events are removed from the event queue.
+    ///
+    /// Frequency is in kHz for Satellite systems and in Hz for others
+    pub fn locked_frequency(&self) -> Result<u32> {
+        let events = self.drain_events().unwrap_or_default();
+        let frequency = events
+            .iter()
+            .rev()
+            .filter(|e| fe_status::from_bits_truncate(e.status).contains(fe_status::FE_HAS_LOCK))
+            .map(|e| e.parameters.frequency)
+            .find(|&v| v != 0);
+
+        match frequency {
+            Some(v) => Ok(v),
+            None => self.get_frequency().context("FE: locked frequency"),
+        }
+    }
+
+    /// Waits for the frontend lock and returns status.
+    /// On timeout returns `DvbError::Timeout`
+    pub fn wait_for_lock(&self, timeout: Duration) -> Result<fe_status> {
+        let deadline = Instant::now() + timeout;
+        let mut event = FeEvent::default();
+
+        loop {
+            let status = self.read_status()?;
+            if status.contains(fe_status::FE_HAS_LOCK) {
+                return Ok(status);
+            }
+
+            let remain = deadline.saturating_duration_since(Instant::now());
+            if remain.as_millis() == 0 {
+                return Err(DvbError::Timeout).context("FE: wait for lock");
+            }
+
+            // frontend fd is ready on status change
+            let wait = remain.min(Duration::from_millis(100));
+            if util::wait_readable(self.as_raw_fd(), Some(wait)).context("FE: wait for lock")? {
+                while self.get_event(&mut event).is_ok() {}
+            }
+        }
+    }
+
+    /// Tunes frontend and waits for lock
+    ///
+    /// Sequence:
+    ///
+    /// - clears frontend settings and event queue. LNB power and tone are turned off
+    /// - for satellite: turns LNB power on with given voltage and waits 15ms to settle
+    /// - for satellite: sets 22kHz tone
+    /// - sets tuning properties with `DTV_TUNE`
+    /// - waits for lock no more than `timeout` and reads frontend status
+    pub fn zap(&self, config: &TuningConfig, timeout: Duration) -> Result<FeStatus> {
+        self.clear()?;
+
+        if let Some(voltage) = config.voltage {
+            self.set_voltage(voltage.into())?;
+            thread::sleep(LNB_SETTLE_DELAY);
+        }
+
+        if let Some(tone) = config.tone {
+            self.set_tone(tone.into())?;
+        }
+
+        self.tune_raw(&config.properties)?;
+        self.wait_for_lock(timeout)?;
+
+        let mut status = FeStatus::default();
+        status.read(self)?;
+
+        Ok(status)
+    }
+
     /// Returns frontend status
     /// - [`FE_NONE`]
     /// - [`FE_HAS_SIGNAL`]
@@ -360,17 +848,16 @@ impl FeDevice {
         let mut result: u32 = 0;
 
         // FE_READ_STATUS
-        ioctl_read!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            69,
-            u32
-        );
-        unsafe { ioctl_call(self.as_raw_fd(), &mut result as *mut _) }
+        let request = request_code_read!(b'o', 69, mem::size_of::<u32>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &mut result as *mut _ as _) }
             .context("FE: read status")?;
 
-        Ok(fe_status::from_bits(result).context("Invalid status")?)
+        let status = fe_status::from_bits(result).context("Invalid status")?;
+        if status.contains(fe_status::FE_REINIT) {
+            self.reset_sec_state();
+        }
+
+        Ok(status)
     }
 
     /// Reads and returns a signal strength relative value (DVBv3 API)
@@ -378,14 +865,8 @@ impl FeDevice {
         let mut result: u16 = 0;
 
         // FE_READ_SIGNAL_STRENGTH
-        ioctl_read!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            71,
-            u16
-        );
-        unsafe { ioctl_call(self.as_raw_fd(), &mut result as *mut _) }
+        let request = request_code_read!(b'o', 71, mem::size_of::<u16>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &mut result as *mut _ as _) }
             .context("FE: read signal strength")?;
 
         Ok(result)
@@ -396,14 +877,9 @@ impl FeDevice {
         let mut result: u16 = 0;
 
         // FE_READ_SNR
-        ioctl_read!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            72,
-            u16
-        );
-        unsafe { ioctl_call(self.as_raw_fd(), &mut result as *mut _) }.context("FE: read snr")?;
+        let request = request_code_read!(b'o', 72, mem::size_of::<u16>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &mut result as *mut _ as _) }
+            .context("FE: read snr")?;
 
         Ok(result)
     }
@@ -413,14 +889,9 @@ impl FeDevice {
         let mut result: u32 = 0;
 
         // FE_READ_BER
-        ioctl_read!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            70,
-            u32
-        );
-        unsafe { ioctl_call(self.as_raw_fd(), &mut result as *mut _) }.context("FE: read ber")?;
+        let request = request_code_read!(b'o', 70, mem::size_of::<u32>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &mut result as *mut _ as _) }
+            .context("FE: read ber")?;
 
         Ok(result as u64)
     }
@@ -430,14 +901,8 @@ impl FeDevice {
         let mut result: u32 = 0;
 
         // FE_READ_UNCORRECTED_BLOCKS
-        ioctl_read!(
-            #[inline]
-            ioctl_call,
-            b'o',
-            73,
-            u32
-        );
-        unsafe { ioctl_call(self.as_raw_fd(), &mut result as *mut _) }
+        let request = request_code_read!(b'o', 73, mem::size_of::<u32>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &mut result as *mut _ as _) }
             .context("FE: read uncorrected blocks")?;
 
         Ok(result as u64)
@@ -451,13 +916,12 @@ impl FeDevice {
     /// - SEC_TONE_OFF - turn 22kHz off
     pub fn set_tone(&self, value: u32) -> Result<()> {
         // FE_SET_TONE
-        ioctl_write_int_bad!(
-            #[inline]
-            ioctl_call,
-            request_code_none!(b'o', 66)
-        );
+        let request = request_code_none!(b'o', 66);
+
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, value as _) }
+            .context("FE: set tone")?;
 
-        unsafe { ioctl_call(self.as_raw_fd(), value as _) }.context("FE: set tone")?;
+        self.sec_state.lock().unwrap().tone = fe_sec_tone_mode::from_repr(value);
 
         Ok(())
     }
@@ -482,20 +946,98 @@ impl FeDevice {
     ///   to use same LNB with several receivers.
     pub fn set_voltage(&self, value: u32) -> Result<()> {
         // FE_SET_VOLTAGE
-        ioctl_write_int_bad!(
-            #[inline]
-            ioctl_call,
-            request_code_none!(b'o', 67)
+        let request = request_code_none!(b'o', 67);
+
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, value as _) }
+            .context("FE: set voltage")?;
+
+        self.sec_state.lock().unwrap().set_voltage(fe_sec_voltage::from_repr(value));
+
+        Ok(())
+    }
+
+    /// Returns the last LNB power set with `set_voltage` or `DTV_VOLTAGE`.
+    /// Kernel has no call to read it back, so this is the commanded state,
+    /// not the measured one. `None` if not set or lost on `FE_REINIT` or `reset_overload`
+    pub fn current_voltage(&self) -> Option<fe_sec_voltage> {
+        self.sec_state.lock().unwrap().voltage
+    }
+
+    /// Returns the last 22kHz tone set with `set_tone` or `DTV_TONE`.
+    /// Same as `current_voltage` this is the commanded state
+    pub fn current_tone(&self) -> Option<fe_sec_tone_mode> {
+        self.sec_state.lock().unwrap().tone
+    }
+
+    fn reset_sec_state(&self) {
+        *self.sec_state.lock().unwrap() = SecState::default();
+    }
+
+    /// Enables higher LNB voltage: 14V instead of 13V and 19V instead of 18V.
+    /// Compensates voltage drop on the long cable between receiver and LNB
+    ///
+    /// Not all drivers implement this call
+    pub fn set_high_lnb_voltage(&self, enable: bool) -> Result<()> {
+        // FE_ENABLE_HIGH_LNB_VOLTAGE
+        let request = request_code_none!(b'o', 68);
+
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, usize::from(enable) as _) }
+            .context("FE: enable high lnb voltage")?;
+
+        Ok(())
+    }
+
+    /// Selects stream of the multistream transponder. Applied on the next tuning
+    ///
+    /// Value depends on the current delivery system:
+    ///
+    /// - DVB-T2, DVB-C2: PLP id 0..=255
+    /// - DVB-S2: bits 0..7 is an ISI, bits 8..25 is a PLS code and
+    ///   bits 26..27 is a PLS mode (0 - root, 1 - gold)
+    /// - ISDB-S: transport stream id
+    ///
+    /// `NO_STREAM_ID_FILTER` disables filtering
+    pub fn set_stream_id(&self, id: u32) -> Result<()> {
+        ensure!(
+            self.caps.contains(fe_caps::FE_CAN_MULTISTREAM),
+            "FE: no multistream"
         );
 
-        unsafe { ioctl_call(self.as_raw_fd(), value as _) }.context("FE: set voltage")?;
+        let (delivery_system,) =
+            get_dtv_properties!(self, DTV_DELIVERY_SYSTEM).context("FE: set stream id")?;
+        check_stream_id(delivery_system, id)?;
+
+        set_dtv_properties!(self, DTV_STREAM_ID(id)).context("FE: set stream id")
+    }
+
+    /// Returns `true` if the frontend stops TS output while signal is not locked.
+    /// DVB API has no ioctl to control it, frontends with `FE_CAN_MUTE_TS` do it by themselves
+    #[inline]
+    pub fn can_mute_ts(&self) -> bool {
+        self.caps.contains(fe_caps::FE_CAN_MUTE_TS)
+    }
+
+    /// Restores LNB power after the overload
+    ///
+    /// On the short circuit in the cable driver turns LNB power off.
+    /// This call re-enables power supply. Voltage should be set again
+    /// with `set_voltage` after the reset
+    pub fn reset_overload(&self) -> Result<()> {
+        // FE_DISEQC_RESET_OVERLOAD
+        let request = request_code_none!(b'o', 62);
+
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, ptr::null_mut()) }
+            .context("FE: reset overload")?;
+
+        // power state after the reset is unknown until voltage is set again
+        self.sec_state.lock().unwrap().voltage = None;
 
         Ok(())
     }
 
     /// Sets DiSEqC master command
     ///
-    /// `msg` is a message no more 6 bytes length
+    /// `msg` is a message from 3 to 6 bytes length
     ///
     /// Example DiSEqC commited command:
     ///
@@ -512,39 +1054,129 @@ impl FeDevice {
     ///     - 000x - bit is set on SEC_TONE_ON
     ///
     pub fn diseqc_master_cmd(&self, msg: &[u8]) -> Result<()> {
-        let mut cmd = DiseqcMasterCmd::default();
-        debug_assert!(msg.len() <= cmd.msg.len());
-
-        cmd.msg[0..msg.len()].copy_from_slice(msg);
-        cmd.len = msg.len() as u8;
+        let cmd = DiseqcMasterCmd::new(msg).context("FE: diseqc master cmd")?;
 
         // FE_DISEQC_SEND_MASTER_CMD
-        ioctl_write_ptr!(ioctl_call, b'o', 63, DiseqcMasterCmd);
-        unsafe { ioctl_call(self.as_raw_fd(), &cmd as *const _) }
+        let request = request_code_write!(b'o', 63, mem::size_of::<DiseqcMasterCmd>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &cmd as *const _ as _) }
             .context("FE: diseqc master cmd")?;
 
+        self.sec_state.lock().unwrap().diseqc = Some(cmd);
+
+        Ok(())
+    }
+
+    /// Sends DiSEqC master command `times` times.
+    /// Repeat bit of the framing byte is set on the second and next transmissions
+    /// so the slave could ignore copies of the executed command.
+    /// Some switches need two transmissions to latch
+    pub fn diseqc_master_cmd_repeat(&self, msg: &[u8], times: usize) -> Result<()> {
+        ensure!(times > 0, "FE: diseqc repeat count should be greater than 0");
+        ensure!(
+            matches!(msg.first(), Some(framing) if framing & 0xF0 == 0xE0),
+            "FE: invalid diseqc framing byte"
+        );
+
+        let mut msg = msg.to_vec();
+
+        for n in 0..times {
+            if n > 0 {
+                // framing byte bit 0: repeated transmission
+                msg[0] |= 0x01;
+                thread::sleep(diseqc::DISEQC_DELAY);
+            }
+            self.diseqc_master_cmd(&msg)?;
+        }
+
         Ok(())
     }
 
+    /// Sends mini-DiSEqC tone burst to select satellite A or B
+    pub fn diseqc_send_burst(&self, value: fe_sec_mini_cmd) -> Result<()> {
+        // FE_DISEQC_SEND_BURST
+        let request = request_code_none!(b'o', 65);
+
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, value as usize as _) }
+            .context("FE: diseqc send burst")?;
+
+        self.sec_state.lock().unwrap().burst = Some(value);
+
+        Ok(())
+    }
+
+    /// Receives DiSEqC slave reply
+    ///
+    /// Waits for the reply no more than `timeout` milliseconds.
+    /// Returns framing byte and up to 3 bytes of data, or empty message if no reply received
+    pub fn diseqc_recv_slave_reply(&self, timeout: u32) -> Result<Vec<u8>> {
+        let mut reply = DiseqcSlaveReply {
+            timeout,
+            ..Default::default()
+        };
+
+        // FE_DISEQC_RECV_SLAVE_REPLY
+        let request = request_code_read!(b'o', 64, mem::size_of::<DiseqcSlaveReply>());
+        unsafe { self.backend.ioctl(self.as_raw_fd(), request, &mut reply as *mut _ as _) }
+            .context("FE: diseqc recv slave reply")?;
+
+        Ok(reply.data().to_vec())
+    }
+
     /// Returns the current API version
     /// major - first byte
     /// minor - second byte
+    ///
+    /// Value is cached on open and `refresh_info`
     #[inline]
     pub fn get_api_version(&self) -> u16 {
         self.api_version
     }
 
+    /// Reads API version from the driver with DTV_API_VERSION.
+    /// Cached value of `get_api_version` is not updated
+    pub fn read_api_version(&self) -> Result<u16> {
+        let (api_version,) =
+            get_dtv_properties!(self, DTV_API_VERSION).context("FE: read api version")?;
+
+        Ok(api_version as u16)
+    }
+
+    /// Returns `true` if the DVB API version is `major.minor` or newer
+    #[inline]
+    pub fn api_at_least(&self, major: u8, minor: u8) -> bool {
+        self.api_version >= (u16::from(major) << 8 | u16::from(minor))
+    }
+
     /// Returns the name of the device
     #[inline]
     pub fn get_name(&self) -> String {
         self.name.clone()
     }
 
+    /// Returns deprecated frontend type reported by the driver.
+    /// Only for diagnostics, use `delivery_systems` instead
+    #[inline]
+    pub fn legacy_fe_type(&self) -> fe_type {
+        self.fe_type
+    }
+
     #[inline]
     pub fn get_delivery_system_list(&self) -> &Vec<fe_delivery_system> {
         &self.delivery_system_list
     }
 
+    /// Returns a copy of the supported delivery systems list
+    #[inline]
+    pub fn delivery_systems(&self) -> Vec<fe_delivery_system> {
+        self.delivery_system_list.clone()
+    }
+
+    /// Returns true if delivery system is supported by the frontend
+    #[inline]
+    pub fn supports_delivery_system(&self, delivery_system: fe_delivery_system) -> bool {
+        self.delivery_system_list.contains(&delivery_system)
+    }
+
     #[inline]
     pub fn get_frequency_range(&self) -> Range<u32> {
         self.frequency_range.clone()
@@ -560,4 +1192,267 @@ impl FeDevice {
         self.caps
     }
 
+    /// Returns frontend capabilities as a structure
+    #[inline]
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::from(self.caps)
+    }
+
+    /// Returns valid modulations and FEC rates for each supported delivery system
+    pub fn tuning_options(&self) -> Vec<DeliverySystemOptions> {
+        self.delivery_system_list
+            .iter()
+            .map(|&v| DeliverySystemOptions::new(v, self.caps))
+            .collect()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{ioctl::mock::MockBackend, IoctlRequest},
+    };
+
+    const FE_GET_INFO: IoctlRequest = request_code_read!(b'o', 61, mem::size_of::<FeInfo>());
+    pub(super) const FE_READ_STATUS: IoctlRequest =
+        request_code_read!(b'o', 69, mem::size_of::<u32>());
+    pub(super) const FE_SET_PROPERTY: IoctlRequest =
+        request_code_write!(b'o', 82, mem::size_of::<(u32, usize)>());
+    pub(super) const FE_GET_PROPERTY: IoctlRequest =
+        request_code_read!(b'o', 83, mem::size_of::<(u32, usize)>());
+    const FE_DISEQC_SEND_MASTER_CMD: IoctlRequest =
+        request_code_write!(b'o', 63, mem::size_of::<DiseqcMasterCmd>());
+    const FE_DISEQC_RECV_SLAVE_REPLY: IoctlRequest =
+        request_code_read!(b'o', 64, mem::size_of::<DiseqcSlaveReply>());
+    const FE_GET_EVENT: IoctlRequest = request_code_read!(b'o', 78, mem::size_of::<FeEvent>());
+    const FE_DISEQC_SEND_BURST: IoctlRequest = request_code_none!(b'o', 65);
+    const FE_SET_TONE: IoctlRequest = request_code_none!(b'o', 66);
+    const FE_SET_VOLTAGE: IoctlRequest = request_code_none!(b'o', 67);
+
+    /// DVB-S frontend without DTV_ENUM_DELSYS
+    pub(super) fn frontend() -> FeDevice<MockBackend> {
+        frontend_with_caps(fe_caps::empty())
+    }
+
+    pub(super) fn frontend_with_caps(caps: fe_caps) -> FeDevice<MockBackend> {
+        let backend = MockBackend::default();
+        backend.on(FE_GET_INFO, move |arg| {
+            let info = unsafe { &mut *(arg as *mut FeInfo) };
+            info.fe_type = FE_QPSK;
+            info.frequency_min = 950_000;
+            info.frequency_max = 2_150_000;
+            info.caps = caps;
+            Ok(0)
+        });
+
+        let file = File::open("/dev/null").unwrap();
+        FeDevice::with_backend(0, 0, file, backend).unwrap()
+    }
+
+    #[test]
+    fn open_reads_info() {
+        let fe = frontend();
+
+        assert_eq!(
+            fe.backend.calls(),
+            [FE_GET_INFO, FE_GET_PROPERTY, FE_GET_PROPERTY]
+        );
+        assert_eq!(fe.get_delivery_system_list(), &[SYS_DVBS]);
+    }
+
+    #[test]
+    fn set_properties_request() {
+        let fe = frontend();
+        fe.backend.clear();
+
+        let cmdseq = [
+            dtv_property!(DTV_FREQUENCY(1_178_000u32)),
+            dtv_property!(DTV_TUNE(())),
+        ];
+        fe.set_properties(&cmdseq).unwrap();
+
+        assert_eq!(fe.backend.calls(), [FE_SET_PROPERTY]);
+        assert_eq!(fe.last_tuning.lock().unwrap().properties.len(), 2);
+    }
+
+    #[test]
+    fn set_properties_error() {
+        let fe = frontend();
+        fe.backend.fail(FE_SET_PROPERTY, Errno::EINVAL);
+
+        assert!(fe.set_properties(&[dtv_property!(DTV_TUNE(()))]).is_err());
+        assert!(fe.last_tuning.lock().unwrap().properties.is_empty());
+    }
+
+    #[test]
+    fn empty_properties() {
+        let fe = frontend();
+        fe.backend.clear();
+
+        fe.set_properties(&[]).unwrap();
+        fe.get_properties(&mut []).unwrap();
+
+        assert!(fe.backend.calls().is_empty());
+    }
+
+    #[test]
+    fn max_msgs() {
+        let fe = frontend();
+        fe.backend.clear();
+
+        let mut props = vec![dtv_property!(DTV_CLEAR(())); DTV_IOCTL_MAX_MSGS];
+        fe.set_properties(&props).unwrap();
+        fe.get_properties(&mut props).unwrap();
+        assert_eq!(fe.backend.calls(), [FE_SET_PROPERTY, FE_GET_PROPERTY]);
+
+        fe.backend.clear();
+        props.push(dtv_property!(DTV_CLEAR(())));
+        let error = fe.set_properties(&props).unwrap_err();
+        assert!(error.to_string().contains("DTV_IOCTL_MAX_MSGS"));
+        assert!(fe.get_properties(&mut props).is_err());
+        assert!(fe.set_properties_iter(props.iter().copied()).is_err());
+        assert!(fe.backend.calls().is_empty());
+    }
+
+    #[test]
+    fn diseqc_master_cmd_length() {
+        let fe = frontend();
+        fe.backend.clear();
+
+        assert!(fe.diseqc_master_cmd(&[0xE0, 0x10]).is_err());
+        assert!(fe.diseqc_master_cmd(&[0xE0; 7]).is_err());
+        assert!(fe.backend.calls().is_empty());
+
+        fe.diseqc_master_cmd(&[0xE0, 0x10, 0x38, 0xF0]).unwrap();
+        assert_eq!(fe.backend.calls(), [FE_DISEQC_SEND_MASTER_CMD]);
+    }
+
+    #[test]
+    fn diseqc_slave_reply_length() {
+        let fe = frontend();
+
+        // driver reports more bytes than the reply buffer
+        fe.backend.on(FE_DISEQC_RECV_SLAVE_REPLY, |arg| {
+            let reply = unsafe { &mut *(arg as *mut DiseqcSlaveReply) };
+            reply.msg = [0xE4, 0x01, 0x02, 0x03];
+            reply.len = 8;
+            Ok(0)
+        });
+        assert_eq!(fe.diseqc_recv_slave_reply(100).unwrap(), [0xE4, 0x01, 0x02, 0x03]);
+
+        // no reply
+        fe.backend.on(FE_DISEQC_RECV_SLAVE_REPLY, |_| Ok(0));
+        assert!(fe.diseqc_recv_slave_reply(100).unwrap().is_empty());
+    }
+
+    #[test]
+    fn auto_caps() {
+        let fec_auto = [dtv_property!(DTV_INNER_FEC(FEC_AUTO))];
+        let qam_auto = [dtv_property!(DTV_MODULATION(QAM_AUTO))];
+
+        let fe = frontend_with_caps(fe_caps::FE_CAN_INVERSION_AUTO);
+        fe.backend.clear();
+        assert!(fe.set_properties(&fec_auto).is_err());
+        assert!(fe.set_properties(&qam_auto).is_err());
+        assert!(fe.backend.calls().is_empty());
+
+        // values other than AUTO do not need caps
+        fe.set_properties(&[dtv_property!(DTV_INNER_FEC(FEC_3_4))]).unwrap();
+        fe.set_properties(&[dtv_property!(DTV_MODULATION(QPSK))]).unwrap();
+
+        let fe = frontend_with_caps(fe_caps::FE_CAN_FEC_AUTO | fe_caps::FE_CAN_QAM_AUTO);
+        fe.set_properties(&fec_auto).unwrap();
+        fe.set_properties(&qam_auto).unwrap();
+    }
+
+    #[test]
+    fn can_mute_ts() {
+        assert!(!frontend().can_mute_ts());
+        assert!(frontend_with_caps(fe_caps::FE_CAN_MUTE_TS).can_mute_ts());
+    }
+
+    #[test]
+    fn reset_overload_voltage() {
+        let fe = frontend();
+
+        fe.set_voltage(SEC_VOLTAGE_18.into()).unwrap();
+        fe.set_tone(SEC_TONE_ON.into()).unwrap();
+        assert_eq!(fe.current_voltage(), Some(SEC_VOLTAGE_18));
+
+        fe.reset_overload().unwrap();
+        assert_eq!(fe.current_voltage(), None);
+        assert_eq!(fe.current_tone(), Some(SEC_TONE_ON));
+    }
+
+    #[test]
+    fn reapply_sec_state() {
+        let fe = frontend();
+        let tune = [
+            dtv_property!(DTV_FREQUENCY(1_178_000u32)),
+            dtv_property!(DTV_TUNE(())),
+        ];
+
+        fe.set_voltage(SEC_VOLTAGE_18.into()).unwrap();
+        fe.diseqc_master_cmd(&[0xE0, 0x10, 0x38, 0xF3]).unwrap();
+        fe.diseqc_send_burst(SEC_MINI_B).unwrap();
+        fe.set_tone(SEC_TONE_ON.into()).unwrap();
+        fe.set_properties(&tune).unwrap();
+
+        fe.backend.clear();
+        assert!(fe.handle_reinit(fe_status::FE_REINIT).unwrap());
+        assert_eq!(
+            fe.backend.calls(),
+            [
+                FE_SET_VOLTAGE,
+                FE_SET_TONE,
+                FE_DISEQC_SEND_MASTER_CMD,
+                FE_DISEQC_SEND_BURST,
+                FE_SET_TONE,
+                FE_SET_PROPERTY,
+            ]
+        );
+        assert_eq!(fe.current_voltage(), Some(SEC_VOLTAGE_18));
+        assert_eq!(fe.current_tone(), Some(SEC_TONE_ON));
+
+        // switch selection is lost without LNB power
+        fe.set_voltage(SEC_VOLTAGE_OFF.into()).unwrap();
+        fe.set_properties(&tune).unwrap();
+
+        fe.backend.clear();
+        assert!(fe.handle_reinit(fe_status::FE_REINIT).unwrap());
+        assert_eq!(fe.backend.calls(), [FE_SET_VOLTAGE, FE_SET_TONE, FE_SET_PROPERTY]);
+    }
+
+    #[test]
+    fn locked_frequency() {
+        let fe = frontend();
+        let lock = fe_status::FE_HAS_SIGNAL | fe_status::FE_HAS_LOCK;
+
+        // oldest first: lock from the previous tuning, lock loss, current lock, status update
+        let mut queue = vec![
+            (lock, 1_100_000),
+            (fe_status::FE_HAS_SIGNAL, 1_100_000),
+            (lock, 1_178_000),
+            (fe_status::FE_HAS_SIGNAL, 0),
+        ]
+        .into_iter();
+        fe.backend.on(FE_GET_EVENT, move |arg| match queue.next() {
+            Some((status, frequency)) => {
+                let event = unsafe { &mut *(arg as *mut FeEvent) };
+                event.status = status.bits();
+                event.parameters.frequency = frequency;
+                Ok(0)
+            }
+            None => Err(Errno::EWOULDBLOCK),
+        });
+
+        assert_eq!(fe.locked_frequency().unwrap(), 1_178_000);
+
+        // queue is drained, frequency is read from the property
+        fe.backend.clear();
+        fe.locked_frequency().unwrap();
+        assert_eq!(fe.backend.calls(), [FE_GET_EVENT, FE_GET_PROPERTY]);
+    }
 }
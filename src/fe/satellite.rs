@@ -0,0 +1,385 @@
+use {
+    super::{
+        diseqc::{self, DiseqcStep},
+        sys::*,
+        FeDevice, Frequency, SymbolRate,
+    },
+    crate::{dtv_property, IoctlBackend},
+    anyhow::{Context, Result},
+    std::str::FromStr,
+};
+
+/// Satellite signal polarization
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Polarization {
+    Horizontal,
+    Vertical,
+    Left,
+    Right,
+}
+
+impl Polarization {
+    /// Returns true for the circular polarization
+    #[inline]
+    pub fn is_circular(self) -> bool {
+        matches!(self, Polarization::Left | Polarization::Right)
+    }
+
+    /// Returns LNB voltage selecting the polarization: 13V for vertical and right,
+    /// 18V for horizontal and left
+    pub fn to_voltage(self) -> fe_sec_voltage {
+        match self {
+            Polarization::Vertical | Polarization::Right => SEC_VOLTAGE_13,
+            Polarization::Horizontal | Polarization::Left => SEC_VOLTAGE_18,
+        }
+    }
+}
+
+impl FromStr for Polarization {
+    type Err = anyhow::Error;
+
+    /// Parses polarization of the channel list: `V`, `H`, `L`, `R` or full name
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "V" | "VERTICAL" => Ok(Polarization::Vertical),
+            "H" | "HORIZONTAL" => Ok(Polarization::Horizontal),
+            "L" | "LEFT" => Ok(Polarization::Left),
+            "R" | "RIGHT" => Ok(Polarization::Right),
+            v => bail!("FE: invalid polarization {}", v),
+        }
+    }
+}
+
+/// LNB band of the universal LNB
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Band {
+    /// Low band, local oscillator `low_lof`, 22kHz tone off
+    Low,
+    /// High band, local oscillator `high_lof`, 22kHz tone on
+    High,
+}
+
+impl Band {
+    /// Returns 22kHz tone selecting the band
+    #[inline]
+    pub fn to_tone(self) -> fe_sec_tone_mode {
+        match self {
+            Band::Low => SEC_TONE_OFF,
+            Band::High => SEC_TONE_ON,
+        }
+    }
+}
+
+/// LNB parameters
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Lnb {
+    /// Local oscillator frequency of the low band
+    pub low_lof: Frequency,
+    /// Local oscillator frequency of the high band. 0 for single band LNB
+    pub high_lof: Frequency,
+    /// Transponders from this frequency are received in the high band
+    pub switch_frequency: Frequency,
+    /// LNB receives circular polarization
+    pub circular: bool,
+}
+
+impl Lnb {
+    /// Universal Ku-band LNB: 9750/10600MHz with switch on 11700MHz
+    pub const UNIVERSAL: Lnb = Lnb {
+        low_lof: Frequency::from_mhz(9_750),
+        high_lof: Frequency::from_mhz(10_600),
+        switch_frequency: Frequency::from_mhz(11_700),
+        circular: false,
+    };
+
+    /// Single band linear LNB
+    pub fn single(lof: Frequency) -> Self {
+        Lnb {
+            low_lof: lof,
+            high_lof: Frequency::from_hz(0),
+            switch_frequency: Frequency::from_hz(0),
+            circular: false,
+        }
+    }
+
+    /// Single band circular LNB, for example 11250MHz for DBS
+    pub fn circular(lof: Frequency) -> Self {
+        Lnb {
+            circular: true,
+            ..Lnb::single(lof)
+        }
+    }
+
+    /// Returns true if transponder `frequency` is received in the high band
+    #[inline]
+    pub fn is_high_band(&self, frequency: Frequency) -> bool {
+        self.high_lof.as_hz() != 0 && frequency >= self.switch_frequency
+    }
+
+    /// Returns band of the transponder `frequency`.
+    /// Single band LNB receives everything in the low band
+    #[inline]
+    pub fn band(&self, frequency: Frequency) -> Band {
+        if self.is_high_band(frequency) {
+            Band::High
+        } else {
+            Band::Low
+        }
+    }
+
+    /// Returns local oscillator frequency of the band
+    #[inline]
+    pub fn lof(&self, band: Band) -> Frequency {
+        match band {
+            Band::Low => self.low_lof,
+            Band::High => self.high_lof,
+        }
+    }
+
+    /// Converts transponder `frequency` to the intermediate frequency.
+    /// Local oscillator above the transponder frequency (C-band) inverts spectrum
+    pub fn intermediate_frequency(&self, frequency: Frequency) -> Frequency {
+        let lof = self.lof(self.band(frequency));
+        Frequency::from_hz(frequency.as_hz().abs_diff(lof.as_hz()))
+    }
+}
+
+/// Complete satellite tuning: LNB, switch port, polarization and transponder
+///
+/// ```text
+/// let frequency = Frequency::from_mhz(11_778);
+/// let symbol_rate = SymbolRate::from_ksps(27_500);
+/// let polarization = Polarization::Vertical;
+/// let tuning = SatelliteTuning::new(Lnb::UNIVERSAL, frequency, symbol_rate, polarization)
+///     .delivery_system(SYS_DVBS2)
+///     .modulation(PSK_8)
+///     .diseqc_port(1);
+/// fe.tune_satellite_full(&tuning)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct SatelliteTuning {
+    lnb: Lnb,
+    diseqc_port: Option<u8>,
+    polarization: Polarization,
+    band: Option<Band>,
+    frequency: Frequency,
+    symbol_rate: SymbolRate,
+    delivery_system: fe_delivery_system,
+    modulation: fe_modulation,
+    fec: fe_code_rate,
+    rolloff: Option<fe_rolloff>,
+}
+
+impl SatelliteTuning {
+    /// Creates DVB-S QPSK tuning for transponder `frequency` and `symbol_rate`
+    pub fn new(
+        lnb: Lnb,
+        frequency: Frequency,
+        symbol_rate: SymbolRate,
+        polarization: Polarization,
+    ) -> Self {
+        SatelliteTuning {
+            lnb,
+            diseqc_port: None,
+            polarization,
+            band: None,
+            frequency,
+            symbol_rate,
+            delivery_system: SYS_DVBS,
+            modulation: QPSK,
+            fec: FEC_AUTO,
+            rolloff: None,
+        }
+    }
+
+    /// Sets delivery system: SYS_DVBS or SYS_DVBS2
+    #[inline]
+    pub fn delivery_system(mut self, delivery_system: fe_delivery_system) -> Self {
+        self.delivery_system = delivery_system;
+        self
+    }
+
+    /// Sets modulation. DVB-S supports QPSK only
+    #[inline]
+    pub fn modulation(mut self, modulation: fe_modulation) -> Self {
+        self.modulation = modulation;
+        self
+    }
+
+    /// Sets inner FEC rate. FEC_AUTO by default
+    #[inline]
+    pub fn fec(mut self, fec: fe_code_rate) -> Self {
+        self.fec = fec;
+        self
+    }
+
+    /// Sets roll-off factor. DVB-S2 only
+    #[inline]
+    pub fn rolloff(mut self, rolloff: fe_rolloff) -> Self {
+        self.rolloff = Some(rolloff);
+        self
+    }
+
+    /// Sets expected band. By default band is selected by the LNB switch frequency,
+    /// explicit band is checked against it
+    #[inline]
+    pub fn band(mut self, band: Band) -> Self {
+        self.band = Some(band);
+        self
+    }
+
+    /// Selects input 0..=3 of the DiSEqC 1.0 committed switch
+    #[inline]
+    pub fn diseqc_port(mut self, port: u8) -> Self {
+        self.diseqc_port = Some(port);
+        self
+    }
+
+    fn check(&self) -> Result<()> {
+        ensure!(
+            self.polarization.is_circular() == self.lnb.circular,
+            "FE: polarization {:?} is not supported by the {} LNB",
+            self.polarization,
+            if self.lnb.circular { "circular" } else { "linear" }
+        );
+
+        if let Some(band) = self.band {
+            ensure!(
+                band == self.lnb.band(self.frequency),
+                "FE: frequency {} is not in the {:?} band (LNB switch frequency {})",
+                self.frequency,
+                band,
+                self.lnb.switch_frequency
+            );
+        }
+
+        match self.delivery_system {
+            SYS_DVBS => {
+                ensure!(
+                    matches!(self.modulation, QPSK),
+                    "FE: invalid DVB-S modulation {:?}",
+                    self.modulation
+                );
+                ensure!(self.rolloff.is_none(), "FE: roll-off is not supported by DVB-S");
+            }
+            SYS_DVBS2 => ensure!(
+                matches!(self.modulation, QPSK | PSK_8 | APSK_16 | APSK_32),
+                "FE: invalid DVB-S2 modulation {:?}",
+                self.modulation
+            ),
+            v => bail!("FE: invalid satellite delivery system {:?}", v),
+        }
+
+        if let Some(port) = self.diseqc_port {
+            ensure!(port <= 3, "FE: invalid diseqc port {}", port);
+        }
+
+        Ok(())
+    }
+
+    /// Validates parameters and returns property sequence without DTV_TUNE.
+    /// Frequency is converted to the intermediate frequency of the LNB
+    pub fn to_properties(&self) -> Result<Vec<DtvProperty>> {
+        self.check()?;
+
+        let frequency = self
+            .lnb
+            .intermediate_frequency(self.frequency)
+            .to_property(self.delivery_system)?;
+
+        let mut props = vec![
+            dtv_property!(DTV_DELIVERY_SYSTEM(self.delivery_system)),
+            dtv_property!(DTV_FREQUENCY(frequency)),
+            dtv_property!(DTV_SYMBOL_RATE(self.symbol_rate.as_bauds())),
+            dtv_property!(DTV_MODULATION(self.modulation)),
+            dtv_property!(DTV_INNER_FEC(self.fec)),
+        ];
+
+        if let Some(v) = self.rolloff {
+            props.push(dtv_property!(DTV_ROLLOFF(v)));
+        }
+
+        Ok(props)
+    }
+
+    /// Returns switch control sequence: LNB power, DiSEqC command and 22kHz tone
+    pub fn to_diseqc_sequence(&self) -> Vec<DiseqcStep> {
+        let band = self.lnb.band(self.frequency);
+        let voltage = self.polarization.to_voltage();
+
+        let mut steps = vec![DiseqcStep::Voltage(voltage), DiseqcStep::Tone(SEC_TONE_OFF)];
+
+        if let Some(port) = self.diseqc_port {
+            // committed switch: option, position, polarization, band
+            let data = 0xF0
+                | (port << 2)
+                | if voltage == SEC_VOLTAGE_18 { 0x02 } else { 0 }
+                | if band == Band::High { 0x01 } else { 0 };
+            steps.push(DiseqcStep::Command(vec![0xE0, 0x10, 0x38, data]));
+        }
+
+        if band == Band::High {
+            steps.push(DiseqcStep::Tone(band.to_tone()));
+        }
+
+        steps
+    }
+}
+
+impl<B: IoctlBackend> FeDevice<B> {
+    /// Switches LNB for the tuning: sets LNB power for the polarization,
+    /// switches DiSEqC input if port is defined and sets 22kHz tone for the high band.
+    /// Frontend is not tuned
+    pub fn set_satellite_lnb(&self, tuning: &SatelliteTuning) -> Result<()> {
+        tuning.check()?;
+        diseqc::sequence(self, &tuning.to_diseqc_sequence()).context("FE: set LNB")
+    }
+
+    /// Tunes satellite frontend
+    ///
+    /// Sequence:
+    ///
+    /// - validates parameters and converts frequency to the intermediate frequency
+    /// - clears frontend settings
+    /// - sets LNB power for the polarization
+    /// - switches DiSEqC input if port is defined
+    /// - sets 22kHz tone for the high band
+    /// - sets tuning properties with `DTV_TUNE`
+    pub fn tune_satellite_full(&self, tuning: &SatelliteTuning) -> Result<()> {
+        let props = tuning.to_properties()?;
+
+        self.clear()?;
+        self.set_satellite_lnb(tuning)?;
+        self.tune_raw(&props).context("FE: tune satellite")
+    }
+
+    /// Tunes DVB-S transponder with QPSK and automatic FEC.
+    /// `frequency` is the transponder frequency, band, intermediate frequency,
+    /// LNB power and 22kHz tone are selected with the `lnb`.
+    /// Same as `tune_satellite_full` with default `SatelliteTuning`
+    ///
+    /// ```text
+    /// fe.tune_dvbs(
+    ///     Lnb::UNIVERSAL,
+    ///     Frequency::from_mhz(12_188),
+    ///     SymbolRate::from_ksps(27_500),
+    ///     Polarization::Horizontal,
+    ///     Some(0),
+    /// )?;
+    /// ```
+    pub fn tune_dvbs(
+        &self,
+        lnb: Lnb,
+        frequency: Frequency,
+        symbol_rate: SymbolRate,
+        polarization: Polarization,
+        diseqc_port: Option<u8>,
+    ) -> Result<()> {
+        let mut tuning = SatelliteTuning::new(lnb, frequency, symbol_rate, polarization);
+        if let Some(port) = diseqc_port {
+            tuning = tuning.diseqc_port(port);
+        }
+
+        self.tune_satellite_full(&tuning)
+    }
+}
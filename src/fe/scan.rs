@@ -0,0 +1,585 @@
+//! Channel scan
+//!
+//! Scan starts from the initial tuning data: list of transponders in the
+//! dvbv5 format used by the dtv-scan-tables project:
+//!
+//! ```text
+//! [CHANNEL]
+//!     DELIVERY_SYSTEM = DVBT
+//!     FREQUENCY = 474000000
+//!     BANDWIDTH_HZ = 8000000
+//! ```
+//!
+//! Satellite frequencies in the initial tuning data are defined in kHz
+//! and should be read with `InitialTuning::read_satellite` to convert
+//! them to the intermediate frequency of the LNB.
+//!
+//! Each transponder is tuned, services are discovered with the PAT
+//! and described with the SDT and the NIT. Service names are decoded from the
+//! SDT service descriptor. `scan_network` also follows transponders
+//! listed in the NIT.
+
+use {
+    super::{sys::*, FeDevice, Frequency, Lnb, Polarization, TuningConfig},
+    crate::{
+        dmx::DmxDevice,
+        dtv_property,
+        si::{Descriptor, SectionReader, NIT_PID},
+        DvbError, IoctlBackend,
+    },
+    anyhow::{Context, Result},
+    std::{
+        io::{BufRead, Lines},
+        str::FromStr,
+        time::Duration,
+    },
+};
+
+/// Time to wait for the frontend lock on each transponder
+pub const SCAN_LOCK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Transponders from the initial tuning data
+#[derive(Debug, Default, Clone)]
+pub struct InitialTuning {
+    pub transponders: Vec<TuningConfig>,
+}
+
+impl InitialTuning {
+    /// Reads initial tuning data in the dvbv5 format.
+    /// Satellite transponders are rejected, use `read_satellite` instead
+    pub fn read<R: BufRead>(reader: R) -> Result<Self> {
+        Self::read_with_lnb(reader, None)
+    }
+
+    /// Reads initial tuning data in the dvbv5 format with satellite transponders.
+    /// Transponder frequency is converted to the intermediate frequency of the `lnb`
+    /// and the 22kHz tone selects the LNB band.
+    /// `POLARIZATION` is converted to the LNB voltage
+    pub fn read_satellite<R: BufRead>(reader: R, lnb: &Lnb) -> Result<Self> {
+        Self::read_with_lnb(reader, Some(lnb))
+    }
+
+    fn read_with_lnb<R: BufRead>(reader: R, lnb: Option<&Lnb>) -> Result<Self> {
+        let mut result = InitialTuning::default();
+
+        for block in DtvPropertyReader::new(reader) {
+            let (name, properties) = block?;
+            let mut config = TuningConfig::default();
+            let mut delivery_system = None;
+
+            for property in properties {
+                match property {
+                    DTV_VOLTAGE(d) => config.voltage = Some(d.get()?),
+                    DTV_DELIVERY_SYSTEM(d) => {
+                        delivery_system = Some(d.get()?);
+                        config.properties.push(property);
+                    }
+                    _ => config.properties.push(property),
+                }
+            }
+
+            if let Some(sys) = delivery_system.filter(|v| v.is_satellite()) {
+                let lnb = lnb.with_context(|| {
+                    format!(
+                        "FE: [{}]: satellite transponder requires LNB, \
+                        use InitialTuning::read_satellite",
+                        name
+                    )
+                })?;
+                set_intermediate_frequency(&mut config, sys, lnb)
+                    .with_context(|| format!("FE: [{}]", name))?;
+            }
+
+            result.transponders.push(config);
+        }
+
+        Ok(result)
+    }
+}
+
+/// Converts transponder frequency to the intermediate frequency of the LNB
+/// and sets the 22kHz tone of the LNB band
+fn set_intermediate_frequency(
+    config: &mut TuningConfig,
+    delivery_system: fe_delivery_system,
+    lnb: &Lnb,
+) -> Result<()> {
+    for property in config.properties.iter_mut() {
+        if let DTV_FREQUENCY(d) = property {
+            let frequency = Frequency::from_property(delivery_system, d.get()?);
+            let intermediate = lnb.intermediate_frequency(frequency);
+            *property = dtv_property!(DTV_FREQUENCY(intermediate.to_property(delivery_system)?));
+            config.tone = Some(lnb.band(frequency).to_tone());
+            return Ok(());
+        }
+    }
+
+    bail!("FE: transponder frequency is not defined")
+}
+
+impl FromStr for InitialTuning {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InitialTuning::read(s.as_bytes())
+    }
+}
+
+/// Parses property line of the channel block.
+/// `POLARIZATION` is converted to the `DTV_VOLTAGE`
+fn parse_channel_line(line: &str) -> Result<DtvProperty> {
+    match line.split_once('=') {
+        Some((k, v)) if k.trim().eq_ignore_ascii_case("POLARIZATION") => {
+            let polarization = v.parse::<Polarization>()?;
+            let voltage = polarization.to_voltage();
+            Ok(dtv_property!(DTV_VOLTAGE(voltage)))
+        }
+        _ => line.parse::<DtvProperty>(),
+    }
+}
+
+/// Streaming reader of the channel blocks in the dvbv5 format.
+/// Yields channel name and properties for each block, reading
+/// the source line by line. `POLARIZATION` is converted to the `DTV_VOLTAGE`.
+/// Iteration stops after the first error
+///
+/// ```text
+/// let file = BufReader::new(File::open("channels.conf")?);
+/// for block in DtvPropertyReader::new(file) {
+///     let (name, properties) = block?;
+///     // ...
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DtvPropertyReader<R> {
+    lines: Lines<R>,
+    line_number: usize,
+    /// Name of the block being read
+    name: Option<String>,
+    done: bool,
+}
+
+impl<R: BufRead> DtvPropertyReader<R> {
+    pub fn new(reader: R) -> Self {
+        DtvPropertyReader {
+            lines: reader.lines(),
+            line_number: 0,
+            name: None,
+            done: false,
+        }
+    }
+
+    fn read_block(&mut self) -> Result<Option<(String, Vec<DtvProperty>)>> {
+        let mut properties = Vec::new();
+
+        while let Some(line) = self.lines.next() {
+            let line = line.context("FE: failed to read channel data")?;
+            self.line_number += 1;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                match self.name.replace(name.to_owned()) {
+                    Some(name) => return Ok(Some((name, properties))),
+                    None => continue,
+                }
+            }
+
+            ensure!(
+                self.name.is_some(),
+                "FE: line {}: channel section expected",
+                self.line_number
+            );
+
+            let property = parse_channel_line(line)
+                .with_context(|| format!("FE: line {}", self.line_number))?;
+            properties.push(property);
+        }
+
+        Ok(self.name.take().map(|name| (name, properties)))
+    }
+}
+
+impl<R: BufRead> Iterator for DtvPropertyReader<R> {
+    type Item = Result<(String, Vec<DtvProperty>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.read_block().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+
+        result
+    }
+}
+
+/// Formats tuning properties as a channel in the dvbv5 format.
+/// Properties without the channel file representation, for example `DTV_TUNE`,
+/// are skipped. Result could be read with `InitialTuning::read`.
+/// Channel `name` should not contain `]` and line breaks
+pub fn to_dvbv5_conf(name: &str, props: &[DtvProperty]) -> Result<String> {
+    ensure!(
+        !name.contains([']', '\n', '\r']),
+        "FE: invalid channel name {:?}",
+        name
+    );
+
+    let mut result = format!("[{}]\n", name);
+
+    for property in props.iter().filter(|p| p.is_channel_property()) {
+        result.push_str(&format!("\t{}\n", property));
+    }
+
+    Ok(result)
+}
+
+/// Service found by the scan
+#[derive(Debug, Clone)]
+pub struct Service {
+    /// Tuning configuration of the transponder
+    pub tuning: TuningConfig,
+    pub transport_stream_id: u16,
+    /// Defined if the service is described in the SDT
+    pub original_network_id: Option<u16>,
+    pub service_id: u16,
+    pub pmt_pid: u16,
+    /// Service is scrambled. Defined if the service is described in the SDT
+    pub free_ca_mode: Option<bool>,
+    /// Service name from the SDT service descriptor
+    pub name: Option<String>,
+    /// Provider name from the SDT service descriptor
+    pub provider: Option<String>,
+    /// Service descriptors from the SDT
+    pub descriptors: Vec<Descriptor>,
+    /// Network delivering the transport stream.
+    /// Defined if the transponder has the NIT
+    pub network_id: Option<u16>,
+}
+
+/// Result of the scan step
+#[derive(Debug, Clone)]
+pub enum ScanStep {
+    /// Transponder is tuning
+    Tuning,
+    /// Frontend is not locked
+    NoLock,
+    /// Transponder is skipped with error
+    Failed(String),
+    /// Transponder is scanned with number of the new services
+    Found(usize),
+}
+
+/// Scan progress reported for each transponder
+#[derive(Debug, Clone)]
+pub struct ScanProgress {
+    /// Index of the transponder in the initial tuning data
+    pub transponder: usize,
+    /// Total number of transponders
+    pub total: usize,
+    pub step: ScanStep,
+}
+
+/// Scans all transponders from the initial tuning data and returns found services
+pub fn scan_from_initial<B: IoctlBackend>(
+    fe: &FeDevice<B>,
+    dmx: &DmxDevice<B>,
+    initial: &InitialTuning,
+) -> Result<Vec<Service>> {
+    scan_from_initial_with_progress(fe, dmx, initial, |_| {})
+}
+
+/// Scans all transponders from the initial tuning data and returns found services.
+/// `progress` is called before tuning and after each transponder.
+/// Transponders without lock or with errors are skipped
+pub fn scan_from_initial_with_progress<B, F>(
+    fe: &FeDevice<B>,
+    dmx: &DmxDevice<B>,
+    initial: &InitialTuning,
+    progress: F,
+) -> Result<Vec<Service>>
+where
+    B: IoctlBackend,
+    F: FnMut(&ScanProgress),
+{
+    scan(fe, dmx, initial, false, progress)
+}
+
+/// Scans transponders from the initial tuning data and transponders
+/// discovered with the NIT of the actual network
+pub fn scan_network<B: IoctlBackend>(
+    fe: &FeDevice<B>,
+    dmx: &DmxDevice<B>,
+    initial: &InitialTuning,
+) -> Result<Vec<Service>> {
+    scan_network_with_progress(fe, dmx, initial, |_| {})
+}
+
+/// Scans transponders from the initial tuning data and transponders
+/// discovered with the NIT of the actual network. Total number of transponders
+/// in the `progress` grows with discovered transponders.
+/// Satellite transponders are not followed: NIT defines the transponder frequency
+/// but the frontend should be tuned to the intermediate frequency of the LNB
+pub fn scan_network_with_progress<B, F>(
+    fe: &FeDevice<B>,
+    dmx: &DmxDevice<B>,
+    initial: &InitialTuning,
+    progress: F,
+) -> Result<Vec<Service>>
+where
+    B: IoctlBackend,
+    F: FnMut(&ScanProgress),
+{
+    scan(fe, dmx, initial, true, progress)
+}
+
+/// Returns delivery system and frequency of the tuning configuration
+fn transponder_frequency(tuning: &TuningConfig) -> Option<(fe_delivery_system, Frequency)> {
+    let mut delivery_system = None;
+    let mut frequency = None;
+
+    for property in &tuning.properties {
+        match property {
+            DTV_DELIVERY_SYSTEM(d) => delivery_system = d.get().ok(),
+            DTV_FREQUENCY(d) => frequency = d.get().ok(),
+            _ => {}
+        }
+    }
+
+    let delivery_system = delivery_system?;
+    Some((delivery_system, Frequency::from_property(delivery_system, frequency?)))
+}
+
+/// Returns true if tuning configurations point to the same transponder
+fn is_same_transponder(a: &TuningConfig, b: &TuningConfig) -> bool {
+    match (transponder_frequency(a), transponder_frequency(b)) {
+        // tolerance for the frequency offsets of the same transponder
+        (Some((_, fa)), Some((_, fb))) => {
+            fa.as_hz().abs_diff(fb.as_hz()) < 1_000_000
+                && a.voltage == b.voltage
+                && a.tone == b.tone
+        }
+        _ => false,
+    }
+}
+
+/// Returns false for the ATSC streams: services are described with PSIP
+/// instead of the DVB SDT and NIT
+fn has_dvb_si(tuning: &TuningConfig) -> bool {
+    !matches!(
+        transponder_frequency(tuning),
+        Some((SYS_ATSC | SYS_ATSCMH | SYS_DVBC_ANNEX_B, _))
+    )
+}
+
+fn scan<B, F>(
+    fe: &FeDevice<B>,
+    dmx: &DmxDevice<B>,
+    initial: &InitialTuning,
+    follow_nit: bool,
+    mut progress: F,
+) -> Result<Vec<Service>>
+where
+    B: IoctlBackend,
+    F: FnMut(&ScanProgress),
+{
+    let mut transponders = initial.transponders.clone();
+    let mut services: Vec<Service> = Vec::new();
+    let mut transponder = 0;
+
+    while transponder < transponders.len() {
+        let tuning = transponders[transponder].clone();
+
+        progress(&ScanProgress {
+            transponder,
+            total: transponders.len(),
+            step: ScanStep::Tuning,
+        });
+
+        let step = match scan_transponder(fe, dmx, &tuning, follow_nit) {
+            Ok(Some((found, discovered))) => {
+                let count = services.len();
+                for service in found {
+                    // same transport stream could be listed on several frequencies
+                    let exists = services.iter().any(|s| {
+                        s.transport_stream_id == service.transport_stream_id
+                            && s.original_network_id == service.original_network_id
+                            && s.service_id == service.service_id
+                    });
+                    if !exists {
+                        services.push(service);
+                    }
+                }
+                for tuning in discovered {
+                    if !transponders.iter().any(|t| is_same_transponder(t, &tuning)) {
+                        transponders.push(tuning);
+                    }
+                }
+                ScanStep::Found(services.len() - count)
+            }
+            Ok(None) => ScanStep::NoLock,
+            Err(e) => ScanStep::Failed(format!("{:#}", e)),
+        };
+
+        progress(&ScanProgress {
+            transponder,
+            total: transponders.len(),
+            step,
+        });
+
+        transponder += 1;
+    }
+
+    Ok(services)
+}
+
+/// Services of the transponder and transponders discovered with the NIT
+type TransponderScan = (Vec<Service>, Vec<TuningConfig>);
+
+/// Returns services of the transponder and transponders discovered with the NIT.
+/// Returns `None` if the frontend is not locked.
+/// Timeout on the table reading is an error
+fn scan_transponder<B: IoctlBackend>(
+    fe: &FeDevice<B>,
+    dmx: &DmxDevice<B>,
+    tuning: &TuningConfig,
+    follow_nit: bool,
+) -> Result<Option<TransponderScan>> {
+    match fe.zap(tuning, SCAN_LOCK_TIMEOUT) {
+        Ok(_) => {}
+        Err(e) if matches!(e.downcast_ref(), Some(DvbError::Timeout)) => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let reader = SectionReader::new(dmx);
+    let pat = reader.read_pat()?;
+
+    let (sdt, nit) = if has_dvb_si(tuning) {
+        let sdt = reader.read_sdt()?;
+        let nit = reader.read_nit_with_pid(pat.network_pid.unwrap_or(NIT_PID))?;
+        (Some(sdt), Some(nit))
+    } else {
+        (None, None)
+    };
+
+    let services = pat
+        .programs
+        .iter()
+        .map(|program| {
+            let sdt_service = sdt.as_ref().and_then(|sdt| {
+                sdt.services
+                    .iter()
+                    .find(|s| s.service_id == program.program_number)
+                    .map(|s| (sdt.original_network_id, s))
+            });
+
+            Service {
+                tuning: tuning.clone(),
+                transport_stream_id: pat.transport_stream_id,
+                original_network_id: sdt_service.map(|(onid, _)| onid),
+                service_id: program.program_number,
+                pmt_pid: program.pid,
+                free_ca_mode: sdt_service.map(|(_, s)| s.free_ca_mode),
+                name: sdt_service.and_then(|(_, s)| s.service_name.clone()),
+                provider: sdt_service.and_then(|(_, s)| s.provider_name.clone()),
+                descriptors: sdt_service
+                    .map(|(_, s)| s.descriptors.clone())
+                    .unwrap_or_default(),
+                network_id: nit.as_ref().map(|nit| nit.network_id),
+            }
+        })
+        .collect();
+
+    let discovered = match nit {
+        Some(nit) if follow_nit => nit
+            .transport_streams
+            .into_iter()
+            .filter_map(|ts| ts.tuning)
+            .filter(|t| matches!(transponder_frequency(t), Some((sys, _)) if !sys.is_satellite()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Ok(Some((services, discovered)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dvbv5_conf_round_trip() {
+        let props = [
+            dtv_property!(DTV_DELIVERY_SYSTEM(SYS_DVBS2)),
+            dtv_property!(DTV_FREQUENCY(11_778_000u32)),
+            dtv_property!(DTV_SYMBOL_RATE(27_500_000u32)),
+            dtv_property!(DTV_INNER_FEC(FEC_3_4)),
+            dtv_property!(DTV_MODULATION(PSK_8)),
+            dtv_property!(DTV_VOLTAGE(SEC_VOLTAGE_13)),
+            dtv_property!(DTV_STREAM_ID(5u32)),
+            dtv_property!(DTV_TUNE(())),
+        ];
+
+        let conf = to_dvbv5_conf("Transponder 1", &props).unwrap();
+        let channels = DtvPropertyReader::new(conf.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(channels.len(), 1);
+        let (name, parsed) = &channels[0];
+        assert_eq!(name, "Transponder 1");
+
+        // DTV_TUNE has no channel file representation
+        let expected = &props[..props.len() - 1];
+        assert_eq!(
+            parsed.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+            expected.iter().map(|p| p.to_string()).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn dvbv5_conf_invalid_name() {
+        let props = [dtv_property!(DTV_DELIVERY_SYSTEM(SYS_DVBT))];
+        assert!(to_dvbv5_conf("A]", &props).is_err());
+        assert!(to_dvbv5_conf("A\nFREQUENCY = 1", &props).is_err());
+        assert!(to_dvbv5_conf("[A]", &props).is_err());
+        assert!(to_dvbv5_conf("[A", &props).is_ok());
+    }
+
+    const SATELLITE: &str = "\
+[11778 V]
+\tDELIVERY_SYSTEM = DVBS2
+\tFREQUENCY = 11778000
+\tPOLARIZATION = VERTICAL
+\tSYMBOL_RATE = 27500000
+";
+
+    #[test]
+    fn read_satellite() {
+        let initial = InitialTuning::read_satellite(SATELLITE.as_bytes(), &Lnb::UNIVERSAL).unwrap();
+        assert_eq!(initial.transponders.len(), 1);
+
+        let tuning = &initial.transponders[0];
+        assert_eq!(tuning.voltage, Some(SEC_VOLTAGE_13));
+        assert_eq!(tuning.tone, Some(SEC_TONE_ON));
+        // 11778MHz in the high band of the universal LNB
+        let (_, frequency) = transponder_frequency(tuning).unwrap();
+        assert_eq!(frequency, Frequency::from_mhz(1_178));
+    }
+
+    #[test]
+    fn read_rejects_satellite() {
+        let e = InitialTuning::read(SATELLITE.as_bytes()).unwrap_err();
+        assert!(format!("{:#}", e).contains("read_satellite"));
+
+        let terrestrial = "[CHANNEL]\n\tDELIVERY_SYSTEM = DVBT\n\tFREQUENCY = 474000000\n";
+        let initial = InitialTuning::read(terrestrial.as_bytes()).unwrap();
+        assert_eq!(initial.transponders[0].tone, None);
+    }
+}
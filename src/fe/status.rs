@@ -2,10 +2,34 @@ use crate::get_dtv_properties;
 
 use {
     super::{sys::*, FeDevice},
+    crate::IoctlBackend,
     anyhow::{Result},
-    std::fmt,
+    std::{fmt, ops::Range},
 };
 
+/// Scale priority for the signal strength and SNR percentage.
+/// Decibel values are converted to percentage with the delivery system range
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum StatPrefs {
+    /// Relative value reported by the driver, decibel value if relative is missing
+    #[default]
+    RelativeFirst,
+    /// Decibel value, relative value if decibel is missing
+    DecibelFirst,
+    /// Decibel value only
+    DecibelOnly,
+}
+
+impl StatPrefs {
+    fn select(self, relative: Option<u8>, decibel: Option<u8>) -> Option<u8> {
+        match self {
+            StatPrefs::RelativeFirst => relative.or(decibel),
+            StatPrefs::DecibelFirst => decibel.or(relative),
+            StatPrefs::DecibelOnly => decibel,
+        }
+    }
+}
+
 /// Frontend status
 #[derive(Debug)]
 pub struct FeStatus {
@@ -16,8 +40,10 @@ pub struct FeStatus {
     modulation: Option<fe_modulation>,
     signal_strength_decibel: Option<f64>,
     signal_strength_percentage: Option<u8>,
+    signal_strength_availability: StatAvailability,
     snr_decibel: Option<f64>,
     snr_percentage: Option<u8>,
+    snr_availability: StatAvailability,
     // ber - number of bit errors
     ber: Option<u64>,
     // unc - number of block errors
@@ -32,14 +58,34 @@ impl Default for FeStatus {
             modulation: None,
             signal_strength_decibel: None,
             signal_strength_percentage: None,
+            signal_strength_availability: StatAvailability::Unsupported,
             snr_decibel: None,
             snr_percentage: None,
+            snr_availability: StatAvailability::Unsupported,
             ber: None,
             unc: None,
         }
     }
 }
 
+/// Writes `-38.56dBm (59%)` with `-` for missing values
+fn write_level(
+    f: &mut fmt::Formatter,
+    decibel: Option<f64>,
+    unit: &str,
+    percentage: Option<u8>,
+) -> fmt::Result {
+    match decibel {
+        Some(v) => write!(f, "{:.02}{}", v, unit)?,
+        None => write!(f, "-")?,
+    }
+
+    match percentage {
+        Some(v) => write!(f, " ({}%)", v),
+        None => write!(f, " (-)"),
+    }
+}
+
 /// Returns an object that implements `Display` for different verbosity levels
 ///
 /// Tuner is turned off:
@@ -69,6 +115,8 @@ impl Default for FeStatus {
 /// ```text
 /// LOCK dvb-s2 | Signal -38.56dBm (59%) | Quality 14.57dB (70%) | BER:0 | UNC:0
 /// ```
+///
+/// Values not reported by the driver are shown as `-`
 impl fmt::Display for FeStatus {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.status == fe_status::FE_NONE {
@@ -92,23 +140,15 @@ impl fmt::Display for FeStatus {
             return Ok(());
         }
 
-        write!(
-            f,
-            " | Signal {:.02}dBm ({}%)",
-            self.get_signal_strength_decibel().unwrap_or(0.0),
-            self.get_signal_strength().unwrap_or(0)
-        )?;
+        write!(f, " | Signal ")?;
+        write_level(f, self.signal_strength_decibel, "dBm", self.signal_strength_percentage)?;
 
         if !self.status.contains(fe_status::FE_HAS_CARRIER) {
             return Ok(());
         }
 
-        write!(
-            f,
-            " | Quality {:.02}dB ({}%)",
-            self.get_snr_decibel().unwrap_or(0.0),
-            self.get_snr().unwrap_or(0)
-        )?;
+        write!(f, " | Quality ")?;
+        write_level(f, self.snr_decibel, "dB", self.snr_percentage)?;
 
         if !self.status.contains(fe_status::FE_HAS_LOCK) {
             return Ok(());
@@ -155,6 +195,11 @@ impl FeStatus {
         &self.signal_strength_percentage
     }
 
+    /// Returns why Signal Strength is missing or `Available`
+    pub fn get_signal_strength_availability(&self) -> StatAvailability {
+        self.signal_strength_availability
+    }
+
     /// Returns Signal to noise ratio in dB
     pub fn get_snr_decibel(&self) -> &Option<f64> {
         &self.snr_decibel
@@ -165,6 +210,11 @@ impl FeStatus {
         &self.snr_percentage
     }
 
+    /// Returns why Signal to noise ratio is missing or `Available`
+    pub fn get_snr_availability(&self) -> StatAvailability {
+        self.snr_availability
+    }
+
     /// Returns BER value if available
     pub fn get_ber(&self) -> &Option<u64> {
         &self.ber
@@ -175,34 +225,99 @@ impl FeStatus {
         &self.unc
     }
 
-    fn normalize_signal_strength(&mut self, stats: DtvFrontendStats) {
-        self.signal_strength_decibel = stats.get_decibel_float();
-        self.signal_strength_percentage = match (stats.get_relative(), stats.get_decibel()) {
-            (Some(v), _) => Some(((v as u32) * 100 / 65535) as u8),
-            (None, Some(decibel)) if self.status.contains(fe_status::FE_HAS_SIGNAL) => {
-                // TODO: check delivery_system
-                // TODO: this logic looks very sus
-                let lo: i64 = -85000;
-                let hi: i64 = -6000;
+    /// Returns composite signal quality in percentage or `None` if tuner is turned off.
+    /// See `quality_score` for weighting
+    pub fn quality(&self) -> Option<u8> {
+        if self.status == fe_status::FE_NONE {
+            return None;
+        }
+
+        Some(Self::quality_score(
+            self.status.contains(fe_status::FE_HAS_LOCK),
+            self.snr_percentage,
+            self.ber,
+        ))
+    }
+
+    /// Combines lock state, SNR percentage and BER into 0-100 score:
+    ///
+    /// - without lock: up to 30 points by SNR
+    /// - with lock: 30 points for lock, up to 50 points by SNR and up to 20 points by BER
+    ///
+    /// BER points: 0 errors - 20, up to 10 - 15, up to 100 - 10, up to 1000 - 5, more - 0.
+    /// Unknown SNR gives no points, unknown BER gives full points
+    pub fn quality_score(has_lock: bool, snr: Option<u8>, ber: Option<u64>) -> u8 {
+        let snr = u32::from(snr.unwrap_or(0).min(100));
+
+        if !has_lock {
+            return (snr * 30 / 100) as u8;
+        }
+
+        let ber = match ber {
+            None | Some(0) => 20,
+            Some(1..=10) => 15,
+            Some(11..=100) => 10,
+            Some(101..=1000) => 5,
+            Some(_) => 0,
+        };
+
+        (30 + snr * 50 / 100 + ber) as u8
+    }
+
+    /// Returns signal strength range in 0.001 dBm steps used to convert decibel value into percentage.
+    /// Values below range are 0%, values above range are 100%
+    pub fn signal_strength_range(delivery_system: Option<fe_delivery_system>) -> Range<i64> {
+        match delivery_system {
+            Some(SYS_DVBS | SYS_DVBS2 | SYS_TURBO | SYS_DSS | SYS_ISDBS) => -75000..-25000,
+
+            Some(SYS_DVBC_ANNEX_A | SYS_DVBC_ANNEX_B | SYS_DVBC_ANNEX_C | SYS_DVBC2 | SYS_ISDBC) => {
+                -75000..-15000
+            }
+
+            Some(SYS_DVBT | SYS_DVBT2 | SYS_DVBH | SYS_ISDBT | SYS_ATSC | SYS_ATSCMH | SYS_DTMB) => {
+                -90000..-20000
+            }
+
+            _ => -85000..-6000,
+        }
+    }
+
+    fn normalize_signal_strength(&mut self, stats: DtvFrontendStats, prefs: StatPrefs) {
+        self.signal_strength_availability = stats.availability();
+        let decibel = stats.get_signal_decibel();
+        self.signal_strength_decibel = decibel.map(|v| v as f64 / 1000.0);
+        let from_decibel = match decibel {
+            Some(decibel) if self.status.contains(fe_status::FE_HAS_SIGNAL) => {
+                let range = Self::signal_strength_range(self.delivery_system);
                 Some({
-                    if decibel > hi {
+                    if decibel >= range.end {
                         100
-                    } else if decibel < lo {
+                    } else if decibel <= range.start {
                         0
                     } else {
-                        (((lo - decibel) * 100) / (lo - hi)) as u8
+                        (((decibel - range.start) * 100) / (range.end - range.start)) as u8
                     }
                 })
             }
             _ => None,
         };
+        self.signal_strength_percentage =
+            prefs.select(stats.get_relative_percentage(), from_decibel);
+
+        // only invalid value was reported
+        if self.signal_strength_decibel.is_none() && self.signal_strength_percentage.is_none() {
+            self.signal_strength_availability = match self.signal_strength_availability {
+                StatAvailability::Available => StatAvailability::NotAvailable,
+                v => v,
+            };
+        }
     }
 
-    fn normalize_snr(&mut self, stats: DtvFrontendStats) {
+    fn normalize_snr(&mut self, stats: DtvFrontendStats, prefs: StatPrefs) {
+        self.snr_availability = stats.availability();
         self.snr_decibel = stats.get_decibel_float();
-        self.snr_percentage = match (stats.get_relative(), stats.get_decibel()) {
-            (Some(v), _) => Some(((v as u32) * 100 / 65535) as u8),
-            (None, Some(decibel)) if self.status.contains(fe_status::FE_HAS_CARRIER) => {
+        let from_decibel = match stats.get_decibel() {
+            Some(decibel) if self.status.contains(fe_status::FE_HAS_CARRIER) => {
                 match match self.delivery_system {
                     Some(SYS_DVBS) | Some(SYS_DVBS2) => Some(15000),
 
@@ -227,16 +342,93 @@ impl FeStatus {
             }
             _ => None,
         };
+        self.snr_percentage = prefs.select(stats.get_relative_percentage(), from_decibel);
+    }
+
+    /// Value is unsupported if the selected scale is not provided by DVBv3 API
+    #[inline]
+    fn v3_availability(percentage: Option<u8>) -> StatAvailability {
+        match percentage {
+            Some(_) => StatAvailability::Available,
+            None => StatAvailability::Unsupported,
+        }
     }
 
-    /// Reads frontend status with fallback to DVBv3 API
-    pub fn read(&mut self, fe: &FeDevice) -> Result<()> {
+    /// Reads relative values with DVBv3 API
+    fn read_v3<B: IoctlBackend>(&mut self, fe: &FeDevice<B>, prefs: StatPrefs) -> Result<()> {
+        let (delivery_system, modulation) =
+            get_dtv_properties!(fe, DTV_DELIVERY_SYSTEM, DTV_MODULATION)?;
+        self.delivery_system = Some(delivery_system);
+        self.modulation = Some(modulation);
+
+        // DVBv3 API reports relative values only
+        self.signal_strength_decibel = None;
+        let relative = match fe.read_signal_strength() {
+            Ok(v) if self.status.contains(fe_status::FE_HAS_SIGNAL) => {
+                Some(((v as u32) * 100 / 65535) as u8)
+            }
+            _ => None,
+        };
+        self.signal_strength_percentage = prefs.select(relative, None);
+        self.signal_strength_availability = Self::v3_availability(self.signal_strength_percentage);
+
+        self.snr_decibel = None;
+        let relative = match fe.read_snr() {
+            Ok(v) if self.status.contains(fe_status::FE_HAS_CARRIER) => {
+                Some(((v as u32) * 100 / 65535) as u8)
+            }
+            _ => None,
+        };
+        self.snr_percentage = prefs.select(relative, None);
+        self.snr_availability = Self::v3_availability(self.snr_percentage);
+
+        if self.status.contains(fe_status::FE_HAS_LOCK) {
+            self.ber = fe.read_ber().ok();
+            self.unc = fe.read_unc().ok();
+        } else {
+            self.ber = None;
+            self.unc = None;
+        }
+
+        Ok(())
+    }
+
+    /// Reads frontend status with fallback to DVBv3 API.
+    /// Values are updated only if all of them were read successfully
+    #[inline]
+    pub fn read<B: IoctlBackend>(&mut self, fe: &FeDevice<B>) -> Result<()> {
+        self.read_with(fe, StatPrefs::default())
+    }
+
+    /// Same as `read` with scale priority for the percentage values
+    pub fn read_with<B: IoctlBackend>(&mut self, fe: &FeDevice<B>, prefs: StatPrefs) -> Result<()> {
+        let mut status = FeStatus::default();
+        status.read_all(fe, prefs)?;
+        *self = status;
+
+        Ok(())
+    }
+
+    /// Reads frontend status into the new object
+    pub fn from_device<B: IoctlBackend>(fe: &FeDevice<B>) -> Result<FeStatus> {
+        let mut status = FeStatus::default();
+        status.read_all(fe, StatPrefs::default())?;
+
+        Ok(status)
+    }
+
+    fn read_all<B: IoctlBackend>(&mut self, fe: &FeDevice<B>, prefs: StatPrefs) -> Result<()> {
         self.status = fe.read_status()?;
 
         if self.status == fe_status::FE_NONE {
             return Ok(());
         }
 
+        // DTV_STAT_* properties are available since DVB API 5.10
+        if !fe.api_at_least(5, 10) {
+            return self.read_v3(fe, prefs);
+        }
+
         let (delivery_system, modulation, signal_strength, snr, ber, unc) = get_dtv_properties!(
             fe,
             DTV_DELIVERY_SYSTEM,
@@ -248,8 +440,8 @@ impl FeStatus {
         )?;
         self.delivery_system = Some(delivery_system);
         self.modulation = Some(modulation);
-        self.normalize_signal_strength(signal_strength);
-        self.normalize_snr(snr);
+        self.normalize_signal_strength(signal_strength, prefs);
+        self.normalize_snr(snr, prefs);
         self.ber = match ber.get_counter() {
             Some(v) => Some(v),
             None if self.status.contains(fe_status::FE_HAS_LOCK) => fe.read_ber().ok(),
@@ -264,3 +456,76 @@ impl FeStatus {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            fe::tests::{frontend, FE_GET_PROPERTY, FE_READ_STATUS},
+            ioctl::mock::MockBackend,
+        },
+        nix::errno::Errno,
+    };
+
+    fn locked_frontend(api_version: u16) -> FeDevice<MockBackend> {
+        let mut fe = frontend();
+        fe.api_version = api_version;
+        fe.backend.on(FE_READ_STATUS, |arg| {
+            let status =
+                fe_status::FE_HAS_SIGNAL | fe_status::FE_HAS_CARRIER | fe_status::FE_HAS_LOCK;
+            unsafe { *(arg as *mut u32) = status.bits() };
+            Ok(0)
+        });
+        fe
+    }
+
+    fn check_failed_read(api_version: u16) {
+        let fe = locked_frontend(api_version);
+        let mut status = FeStatus::default();
+        status.read(&fe).unwrap();
+        assert!(status.status.contains(fe_status::FE_HAS_LOCK));
+        let before = format!("{:?}", status);
+
+        // new status is read, properties are not
+        fe.backend.on(FE_READ_STATUS, |arg| {
+            unsafe { *(arg as *mut u32) = fe_status::FE_HAS_SIGNAL.bits() };
+            Ok(0)
+        });
+        fe.backend.fail(FE_GET_PROPERTY, Errno::EIO);
+        assert!(status.read(&fe).is_err());
+        assert_eq!(format!("{:?}", status), before);
+
+        fe.backend.fail(FE_READ_STATUS, Errno::EIO);
+        assert!(status.read(&fe).is_err());
+        assert_eq!(format!("{:?}", status), before);
+    }
+
+    #[test]
+    fn failed_read_v3() {
+        check_failed_read(0x0500);
+    }
+
+    #[test]
+    fn failed_read_v5() {
+        check_failed_read(0x050B);
+    }
+
+    #[test]
+    fn v3_decibel_only() {
+        let fe = locked_frontend(0x0500);
+        let mut status = FeStatus::default();
+
+        status.read(&fe).unwrap();
+        assert_eq!(status.get_signal_strength_availability(), StatAvailability::Available);
+        assert_eq!(status.get_snr_availability(), StatAvailability::Available);
+        assert!(status.signal_strength_percentage.is_some());
+        assert!(status.snr_percentage.is_some());
+
+        status.read_with(&fe, StatPrefs::DecibelOnly).unwrap();
+        assert_eq!(status.get_signal_strength_availability(), StatAvailability::Unsupported);
+        assert_eq!(status.get_snr_availability(), StatAvailability::Unsupported);
+        assert_eq!(status.signal_strength_percentage, None);
+        assert_eq!(status.snr_percentage, None);
+    }
+}
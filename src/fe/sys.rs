@@ -2,6 +2,7 @@ use anyhow::Context;
 use std::convert::TryFrom;
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 use std::{fmt, mem};
 
@@ -87,7 +88,7 @@ bitflags! {
 /// DEPRECATED: Should be kept just due to backward compatibility
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(Debug, PartialEq, Eq, FromRepr)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, FromRepr)]
 pub enum fe_type {
     FE_QPSK = 0,
     FE_QAM = 1,
@@ -95,6 +96,40 @@ pub enum fe_type {
     FE_ATSC = 3,
 }
 
+impl fe_type {
+    /// Returns delivery systems for the legacy frontend type.
+    /// Used for drivers without DTV_ENUM_DELSYS
+    pub fn delivery_systems(self, caps: fe_caps) -> Vec<fe_delivery_system> {
+        let mut result = Vec::new();
+
+        match self {
+            FE_QPSK => {
+                result.push(SYS_DVBS);
+                if caps.contains(fe_caps::FE_CAN_2G_MODULATION) {
+                    result.push(SYS_DVBS2);
+                }
+            }
+            FE_QAM => result.push(SYS_DVBC_ANNEX_A),
+            FE_OFDM => {
+                result.push(SYS_DVBT);
+                if caps.contains(fe_caps::FE_CAN_2G_MODULATION) {
+                    result.push(SYS_DVBT2);
+                }
+            }
+            FE_ATSC => {
+                if caps.intersects(fe_caps::FE_CAN_8VSB | fe_caps::FE_CAN_16VSB) {
+                    result.push(SYS_ATSC);
+                }
+                if caps.intersects(fe_caps::FE_CAN_QAM_64 | fe_caps::FE_CAN_QAM_256) {
+                    result.push(SYS_DVBC_ANNEX_B);
+                }
+            }
+        }
+
+        result
+    }
+}
+
 /// Frontend properties and capabilities
 /// The frequencies are specified in Hz for Terrestrial and Cable systems.
 /// The frequencies are specified in kHz for Satellite systems.
@@ -125,14 +160,24 @@ pub struct FeInfo {
     pub caps: fe_caps,
 }
 
+assert_size!(FeInfo, 168);
+
 impl Default for FeInfo {
     #[inline]
     fn default() -> Self {
-        unsafe { mem::zeroed::<Self>() }
+        Self::zeroed()
     }
 }
 
 impl FeInfo {
+    /// Creates zero-initialized structure for the FE_GET_INFO ioctl.
+    /// All fields are integers, arrays of integers, bitflags or fe_type
+    /// with valid zero value (FE_QPSK)
+    #[inline]
+    pub fn zeroed() -> Self {
+        unsafe { mem::zeroed::<Self>() }
+    }
+
     #[inline]
     pub fn as_mut_ptr(&mut self) -> *mut FeInfo {
         self as *mut _
@@ -143,7 +188,7 @@ impl FeInfo {
 /// Check out the DiSEqC bus spec available on http://www.eutelsat.org/ for
 /// the possible messages that can be used.
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct DiseqcMasterCmd {
     /// DiSEqC message to be sent. It contains a 3 bytes header with:
     /// framing + address + command, and an optional argument
@@ -153,11 +198,44 @@ pub struct DiseqcMasterCmd {
     pub len: u8,
 }
 
+assert_size!(DiseqcMasterCmd, 7);
+
 impl Default for DiseqcMasterCmd {
     #[inline]
     fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+impl DiseqcMasterCmd {
+    /// Creates zero-initialized structure for the ioctl call.
+    /// All fields are integers or arrays of integers, so zero is valid value
+    #[inline]
+    pub fn zeroed() -> Self {
         unsafe { mem::zeroed::<Self>() }
     }
+
+    /// Creates command from the message. Message length should be 3 to 6 bytes
+    pub fn new(msg: &[u8]) -> anyhow::Result<Self> {
+        let mut cmd = Self::default();
+        ensure!(
+            (3..=cmd.msg.len()).contains(&msg.len()),
+            "DiSEqC: invalid message length {}",
+            msg.len()
+        );
+
+        cmd.msg[0..msg.len()].copy_from_slice(msg);
+        cmd.len = msg.len() as u8;
+
+        Ok(cmd)
+    }
+
+    /// Returns valid bytes of the message
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        let len = ::std::cmp::min(self.len as usize, self.msg.len());
+        &self.msg[0..len]
+    }
 }
 
 /// DiSEqC received data
@@ -176,23 +254,46 @@ pub struct DiseqcSlaveReply {
     pub timeout: u32,
 }
 
+assert_size!(DiseqcSlaveReply, 12);
+
 impl Default for DiseqcSlaveReply {
     #[inline]
     fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+impl DiseqcSlaveReply {
+    /// Creates zero-initialized structure for the ioctl call.
+    /// All fields are integers or arrays of integers, so zero is valid value
+    #[inline]
+    pub fn zeroed() -> Self {
         unsafe { mem::zeroed::<Self>() }
     }
+
+    /// Returns valid bytes of the received message.
+    /// Length reported by the driver is limited to the message buffer size
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        let len = ::std::cmp::min(self.len as usize, self.msg.len());
+        &self.msg[0..len]
+    }
 }
 
 /// DC Voltage used to feed the LNBf
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, Copy, Clone, PartialEq, Eq, FromRepr)]
+#[derive(EnumString, Display, Debug, Copy, Clone, PartialEq, Eq, FromRepr)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_sec_voltage {
     /// Output 13V to the LNB. Vertical linear. Right circular.
+    #[strum(to_string = "13", serialize = "SEC_VOLTAGE_13")]
     SEC_VOLTAGE_13 = 0,
     /// Output 18V to the LNB. Horizontal linear. Left circular.
+    #[strum(to_string = "18", serialize = "SEC_VOLTAGE_18")]
     SEC_VOLTAGE_18 = 1,
     /// Don't feed the LNB with a DC voltage
+    #[strum(to_string = "OFF", serialize = "SEC_VOLTAGE_OFF")]
     SEC_VOLTAGE_OFF = 2,
 }
 
@@ -204,11 +305,14 @@ impl Into<u32> for fe_sec_voltage {
 
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, Copy, Clone, PartialEq, Eq, FromRepr)]
+#[derive(EnumString, Display, Debug, Copy, Clone, PartialEq, Eq, FromRepr)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_sec_tone_mode {
     /// Sends a 22kHz tone burst to the antenna
+    #[strum(to_string = "ON", serialize = "SEC_TONE_ON")]
     SEC_TONE_ON = 0,
     /// Don't send a 22kHz tone to the antenna (except if the FE_DISEQC_* ioctl are called)
+    #[strum(to_string = "OFF", serialize = "SEC_TONE_OFF")]
     SEC_TONE_OFF = 1,
 }
 
@@ -259,10 +363,25 @@ bitflags! {
     }
 }
 
+impl fe_status {
+    /// Returns `true` if frontend got the lock between two successive reads
+    #[inline]
+    pub fn just_locked(prev: fe_status, now: fe_status) -> bool {
+        !prev.contains(fe_status::FE_HAS_LOCK) && now.contains(fe_status::FE_HAS_LOCK)
+    }
+
+    /// Returns `true` if frontend lost the lock between two successive reads
+    #[inline]
+    pub fn signal_lost(prev: fe_status, now: fe_status) -> bool {
+        prev.contains(fe_status::FE_HAS_LOCK) && !now.contains(fe_status::FE_HAS_LOCK)
+    }
+}
+
 /// Spectral band inversion
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Clone, Copy)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Clone, Copy)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_spectral_inversion {
     #[strum(serialize = "OFF")]
     INVERSION_OFF = 0,
@@ -280,7 +399,7 @@ impl Into<u32> for fe_spectral_inversion {
 
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Clone, Copy)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Clone, Copy)]
 #[strum(ascii_case_insensitive)]
 pub enum fe_code_rate {
     #[strum(serialize = "NONE")]
@@ -323,7 +442,8 @@ impl Into<u32> for fe_code_rate {
 /// Type of modulation/constellation
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_modulation {
     QPSK = 0,
     #[strum(serialize = "QAM/16")]
@@ -367,7 +487,8 @@ impl Into<u32> for fe_modulation {
 }
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_transmit_mode {
     #[strum(serialize = "2K")]
     TRANSMISSION_MODE_2K = 0,
@@ -396,7 +517,8 @@ impl Into<u32> for fe_transmit_mode {
 }
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_guard_interval {
     #[strum(serialize = "1/32")]
     GUARD_INTERVAL_1_32 = 0,
@@ -430,7 +552,8 @@ impl Into<u32> for fe_guard_interval {
 
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_hierarchy {
     #[strum(serialize = "NONE")]
     HIERARCHY_NONE = 0,
@@ -452,7 +575,8 @@ impl Into<u32> for fe_hierarchy {
 
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_interleaving {
     #[strum(serialize = "NONE")]
     INTERLEAVING_NONE = 0,
@@ -472,10 +596,14 @@ impl Into<u32> for fe_interleaving {
 
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_pilot {
+    #[strum(to_string = "ON", serialize = "PILOT_ON")]
     PILOT_ON = 0,
+    #[strum(to_string = "OFF", serialize = "PILOT_OFF")]
     PILOT_OFF = 1,
+    #[strum(to_string = "AUTO", serialize = "PILOT_AUTO")]
     PILOT_AUTO = 2,
 }
 
@@ -486,14 +614,22 @@ impl Into<u32> for fe_pilot {
 }
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_rolloff {
+    #[strum(to_string = "35", serialize = "ROLLOFF_35")]
     ROLLOFF_35 = 0,
+    #[strum(to_string = "20", serialize = "ROLLOFF_20")]
     ROLLOFF_20 = 1,
+    #[strum(to_string = "25", serialize = "ROLLOFF_25")]
     ROLLOFF_25 = 2,
+    #[strum(to_string = "AUTO", serialize = "ROLLOFF_AUTO")]
     ROLLOFF_AUTO = 3,
+    #[strum(to_string = "15", serialize = "ROLLOFF_15")]
     ROLLOFF_15 = 4,
+    #[strum(to_string = "10", serialize = "ROLLOFF_10")]
     ROLLOFF_10 = 5,
+    #[strum(to_string = "5", serialize = "ROLLOFF_5")]
     ROLLOFF_5 = 6,
 }
 
@@ -503,7 +639,7 @@ impl Into<u32> for fe_rolloff {
     }
 }
 
-#[derive(EnumString, Display, FromRepr, Debug, Copy, Clone)]
+#[derive(EnumString, Display, FromRepr, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u32)]
 #[allow(non_camel_case_types)]
 #[strum(ascii_case_insensitive)]
@@ -556,12 +692,24 @@ impl Into<u32> for fe_delivery_system {
     }
 }
 
+impl fe_delivery_system {
+    /// Returns true for satellite delivery systems.
+    /// Frequency of these systems is in kHz, for others in Hz
+    pub fn is_satellite(self) -> bool {
+        matches!(self, SYS_DSS | SYS_DVBS | SYS_DVBS2 | SYS_ISDBS | SYS_TURBO)
+    }
+}
+
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[derive(EnumString, Display, Debug, PartialEq, Eq, FromRepr, Copy, Clone)]
+#[strum(ascii_case_insensitive)]
 pub enum fe_lna {
+    #[strum(to_string = "OFF", serialize = "LNA_OFF")]
     LNA_OFF = 0,
+    #[strum(to_string = "ON", serialize = "LNA_ON")]
     LNA_ON = 1,
+    #[strum(to_string = "AUTO", serialize = "LNA_AUTO")]
     LNA_AUTO = 0xFFFFFFFF,
 }
 
@@ -580,6 +728,9 @@ pub trait WrappedResult<T> {
     fn get(&self) -> anyhow::Result<T>;
 }
 
+/// Range of the plausible signal strength in 0.001 dBm.
+/// Some drivers report 0 or extreme values when the level is unknown
+pub const SIGNAL_DECIBEL_RANGE: RangeInclusive<i64> = -120_000..=20_000;
 
 pub trait DtvStatType {
     fn get_decibel(&self) -> Option<i64>;
@@ -588,6 +739,12 @@ pub trait DtvStatType {
     fn get_decibel_float(&self) -> Option<f64> {
         Some((self.get_decibel()? as f64) / 1000.0)
     }
+    /// Returns signal strength in 0.001 dBm. Value 0 and values out of
+    /// the `SIGNAL_DECIBEL_RANGE` are treated as invalid
+    fn get_signal_decibel(&self) -> Option<i64> {
+        self.get_decibel()
+            .filter(|v| *v != 0 && SIGNAL_DECIBEL_RANGE.contains(v))
+    }
     fn get_relative_percentage(&self) -> Option<u8> {
         Some((((self.get_relative()? as u32) * 100) / 65535) as u8)
     }
@@ -636,6 +793,27 @@ pub enum DtvStat {
     FE_SCALE_COUNTER(ScaleCounter),
 }
 
+/// Reason of the missing statistics value
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StatAvailability {
+    /// Driver does not provide this statistics
+    Unsupported,
+    /// Value is temporary not available, for example without lock
+    NotAvailable,
+    /// Value is present
+    Available,
+}
+
+impl DtvStat {
+    /// Returns `NotAvailable` for the `FE_SCALE_NOT_AVAILABLE` and `Available` otherwise
+    pub fn availability(&self) -> StatAvailability {
+        match self {
+            FE_SCALE_NOT_AVAILABLE(..) => StatAvailability::NotAvailable,
+            _ => StatAvailability::Available,
+        }
+    }
+}
+
 impl DtvStatType for DtvStat {
     fn get_decibel(&self) -> Option<i64> {
         match self {
@@ -680,6 +858,22 @@ impl Debug for DtvFrontendStats {
     }
 }
 
+impl DtvFrontendStats {
+    /// Returns `Unsupported` if driver returns no statistics,
+    /// `Available` if any value is present and `NotAvailable` otherwise
+    pub fn availability(&self) -> StatAvailability {
+        let stats = self.slice();
+
+        if stats.is_empty() {
+            StatAvailability::Unsupported
+        } else if stats.iter().any(|s| s.availability() == StatAvailability::Available) {
+            StatAvailability::Available
+        } else {
+            StatAvailability::NotAvailable
+        }
+    }
+}
+
 impl DtvStatType for DtvFrontendStats {
     fn get_decibel(&self) -> Option<i64> {
         for stat in self.slice() {
@@ -751,7 +945,18 @@ impl<T, TFake, const N: usize> DtvPropertyRequest<T, TFake, N> {
     }
 }
 
+impl<T: Copy, TFake, const N: usize> Clone for DtvPropertyRequest<T, TFake, N> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Copy, TFake, const N: usize> Copy for DtvPropertyRequest<T, TFake, N> {}
+
 impl<T, TFake, const N: usize> Default for DtvPropertyRequest<T, TFake, N> {
+    /// Zero-initialized request for the FE_GET_PROPERTY ioctl.
+    /// `T` is `()`, `u32` or a structure of integers, so zero is valid value
     #[inline]
     fn default() -> Self {
         unsafe { mem::zeroed::<Self>() }
@@ -775,6 +980,12 @@ impl Debug for DtvPropertyRequestVoid {
 
 pub type DtvPropertyRequestInt<T> = DtvPropertyRequest<u32, T, { DATA_SIZE - 4 }>;
 
+// sizes of the packed struct dtv_property without cmd: reserved, union u and result
+#[cfg(target_pointer_width = "64")]
+assert_size!(DtvPropertyRequestVoid, 72);
+#[cfg(target_pointer_width = "64")]
+assert_size!(DtvPropertyRequestInt<u32>, 72);
+
 impl<Err: 'static + std::error::Error + Send + Sync, T: Copy + Debug + TryFrom<u32, Error = Err>> WrappedResult<T> for DtvPropertyRequestInt<T> {
     #[inline]
     fn get(&self) -> anyhow::Result<T> {
@@ -782,6 +993,47 @@ impl<Err: 'static + std::error::Error + Send + Sync, T: Copy + Debug + TryFrom<u
     }
 }
 
+/// Value which could be stored into the integer property
+pub trait DtvPropertyValue: Copy {
+    fn into_raw(self) -> u32;
+}
+
+macro_rules! impl_dtv_property_value {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl DtvPropertyValue for $t {
+                #[inline]
+                fn into_raw(self) -> u32 {
+                    self as u32
+                }
+            }
+        )*
+    };
+}
+
+impl_dtv_property_value!(
+    u32, i32, fe_modulation, fe_spectral_inversion, fe_code_rate, fe_sec_voltage,
+    fe_sec_tone_mode, fe_pilot, fe_rolloff, fe_delivery_system, fe_guard_interval,
+    fe_transmit_mode, fe_hierarchy, fe_interleaving, fe_lna
+);
+
+impl<Err: 'static + std::error::Error + Send + Sync, T: DtvPropertyValue + FromStr<Err = Err>> FromStr for DtvPropertyRequestInt<T> {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s.parse::<T>()?.into_raw()))
+    }
+}
+
+impl<Err: 'static + std::error::Error + Send + Sync, T: Copy + fmt::Display + TryFrom<u32, Error = Err>> fmt::Display for DtvPropertyRequestInt<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let data = self.data;
+        match T::try_from(data) {
+            Ok(v) => write!(f, "{}", v),
+            Err(_) => write!(f, "{}", data as i32),
+        }
+    }
+}
+
 impl<Err: 'static + std::error::Error + Send + Sync, T: Copy + Debug + TryFrom<u32, Error = Err>> Debug for DtvPropertyRequestInt<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.get().unwrap().fmt(f)
@@ -790,6 +1042,10 @@ impl<Err: 'static + std::error::Error + Send + Sync, T: Copy + Debug + TryFrom<u
 
 pub type DtvPropertyRequestFrontendStats = DtvPropertyRequest<DtvFrontendStats, DtvFrontendStats, { DATA_SIZE - 37 }>;
 
+assert_size!(DtvFrontendStats, 37);
+#[cfg(target_pointer_width = "64")]
+assert_size!(DtvPropertyRequestFrontendStats, 72);
+
 impl WrappedResult<DtvFrontendStats> for DtvPropertyRequestFrontendStats {
     #[inline]
     fn get(&self) -> anyhow::Result<DtvFrontendStats> {
@@ -806,14 +1062,20 @@ impl Debug for DtvPropertyRequestFrontendStats {
 pub type DtvPropertyRequestDeliverySystems =
     DtvPropertyRequest<DtvPropertyBuffer, Vec<fe_delivery_system>, { DATA_SIZE - 4 - 32 }>;
 
+#[cfg(target_pointer_width = "64")]
+assert_size!(DtvPropertyRequestDeliverySystems, 72);
+
 impl WrappedResult<Vec<fe_delivery_system>> for DtvPropertyRequestDeliverySystems {
+    /// Returns known delivery systems. Values added in the newer kernels
+    /// are skipped, count is limited by the buffer size
     #[inline]
     fn get(&self) -> Result<Vec<fe_delivery_system>, anyhow::Error> {
-        self.data
+        Ok(self
+            .data
             .slice()
-            .into_iter()
-            .map(|&x| fe_delivery_system::from_repr(x as u32).context("Invalid delivery system"))
-            .collect()
+            .iter()
+            .filter_map(|&x| fe_delivery_system::from_repr(x as u32))
+            .collect())
     }
 }
 
@@ -824,6 +1086,7 @@ impl Debug for DtvPropertyRequestDeliverySystems {
 }
 
 #[repr(C, packed)]
+#[derive(Copy, Clone)]
 pub struct DtvPropertyNotImplementedLinux {
     __reserved: [u8; DATA_SIZE],
 }
@@ -845,7 +1108,7 @@ type DtvPropertyDeprecated = DtvPropertyNotImplementedLinux;
 #[repr(u32, C)]
 #[allow(non_camel_case_types)]
 #[allow(deprecated)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub enum DtvProperty {
     DTV_UNDEFINED(DtvPropertyNotImplementedLinux),
     DTV_TUNE(DtvPropertyRequestVoid),
@@ -943,6 +1206,10 @@ pub enum DtvProperty {
     DTV_SCRAMBLING_SEQUENCE_INDEX(DtvPropertyRequestInt<u32>),
 }
 
+// size of the packed struct dtv_property on 64-bit systems
+#[cfg(target_pointer_width = "64")]
+assert_size!(DtvProperty, 76);
+
 #[macro_export]
 macro_rules! dtv_property {
     ( $property:ident ) => {
@@ -959,7 +1226,7 @@ macro_rules! dtv_property {
 #[macro_export]
 macro_rules! dtv_property_parse {
     ( $property:ident($data:expr)) => {
-        $property($crate::fe::sys::DtvPropertyRequest::new($data.parse().with_context(||format!("Invalid {}: {}", stringify!($property), $data))?))
+        $property($data.parse().with_context(||format!("Invalid {}: {}", stringify!($property), $data))?)
     };
 }
 
@@ -971,7 +1238,7 @@ impl FromStr for DtvProperty {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (k, v) = s.split_once('=').context("Invalid line")?;
         let v = v.trim();
-        Ok(match k.trim() {
+        Ok(match k.trim().to_ascii_uppercase().as_str() {
             "FREQUENCY" => dtv_property_parse!(DTV_FREQUENCY(v)),
             "MODULATION" => dtv_property_parse!(DTV_MODULATION(v)),
             "BANDWIDTH_HZ" => dtv_property_parse!(DTV_BANDWIDTH_HZ(v)),
@@ -1041,9 +1308,154 @@ impl FromStr for DtvProperty {
     }
 }
 
+impl DtvProperty {
+    /// Parses line of the channel file like `from_str` and checks that
+    /// modulation and FEC rate are defined for the `delivery_system`.
+    /// AUTO values are always accepted, unknown delivery systems are not checked
+    pub fn from_str_for(delivery_system: fe_delivery_system, s: &str) -> anyhow::Result<Self> {
+        let property: DtvProperty = s.parse()?;
+        let (modulations, fec_rates) = super::caps::standard_options(delivery_system);
+        if modulations.is_empty() {
+            return Ok(property);
+        }
+
+        let valid = match &property {
+            DTV_MODULATION(d)
+            | DTV_ISDBT_LAYERA_MODULATION(d)
+            | DTV_ISDBT_LAYERB_MODULATION(d)
+            | DTV_ISDBT_LAYERC_MODULATION(d) => {
+                let v = d.get()?;
+                v == QAM_AUTO || modulations.contains(&v)
+            }
+            DTV_INNER_FEC(d)
+            | DTV_CODE_RATE_HP(d)
+            | DTV_CODE_RATE_LP(d)
+            | DTV_ISDBT_LAYERA_FEC(d)
+            | DTV_ISDBT_LAYERB_FEC(d)
+            | DTV_ISDBT_LAYERC_FEC(d) => {
+                let v = d.get()?;
+                matches!(v, FEC_AUTO | FEC_NONE) || fec_rates.contains(&v)
+            }
+            _ => true,
+        };
+
+        ensure!(valid, "Invalid {} for {}", property, delivery_system);
+
+        Ok(property)
+    }
+
+    /// Returns true if property has the dvbv5 channel file representation:
+    /// could be formatted with `Display` and parsed with `from_str`
+    pub fn is_channel_property(&self) -> bool {
+        matches!(
+            self,
+            DTV_FREQUENCY(_)
+                | DTV_MODULATION(_)
+                | DTV_BANDWIDTH_HZ(_)
+                | DTV_INVERSION(_)
+                | DTV_SYMBOL_RATE(_)
+                | DTV_INNER_FEC(_)
+                | DTV_VOLTAGE(_)
+                | DTV_TONE(_)
+                | DTV_PILOT(_)
+                | DTV_ROLLOFF(_)
+                | DTV_DELIVERY_SYSTEM(_)
+                | DTV_ISDBT_PARTIAL_RECEPTION(_)
+                | DTV_ISDBT_SOUND_BROADCASTING(_)
+                | DTV_ISDBT_SB_SUBCHANNEL_ID(_)
+                | DTV_ISDBT_SB_SEGMENT_IDX(_)
+                | DTV_ISDBT_SB_SEGMENT_COUNT(_)
+                | DTV_ISDBT_LAYERA_FEC(_)
+                | DTV_ISDBT_LAYERA_MODULATION(_)
+                | DTV_ISDBT_LAYERA_SEGMENT_COUNT(_)
+                | DTV_ISDBT_LAYERA_TIME_INTERLEAVING(_)
+                | DTV_ISDBT_LAYERB_FEC(_)
+                | DTV_ISDBT_LAYERB_MODULATION(_)
+                | DTV_ISDBT_LAYERB_SEGMENT_COUNT(_)
+                | DTV_ISDBT_LAYERB_TIME_INTERLEAVING(_)
+                | DTV_ISDBT_LAYERC_FEC(_)
+                | DTV_ISDBT_LAYERC_MODULATION(_)
+                | DTV_ISDBT_LAYERC_SEGMENT_COUNT(_)
+                | DTV_ISDBT_LAYERC_TIME_INTERLEAVING(_)
+                | DTV_CODE_RATE_HP(_)
+                | DTV_CODE_RATE_LP(_)
+                | DTV_GUARD_INTERVAL(_)
+                | DTV_TRANSMISSION_MODE(_)
+                | DTV_HIERARCHY(_)
+                | DTV_ISDBT_LAYER_ENABLED(_)
+                | DTV_STREAM_ID(_)
+                | DTV_ATSCMH_FIC_VER(_)
+                | DTV_ATSCMH_PARADE_ID(_)
+                | DTV_ATSCMH_NOG(_)
+                | DTV_ATSCMH_TNOG(_)
+                | DTV_ATSCMH_SGN(_)
+                | DTV_ATSCMH_PRC(_)
+                | DTV_INTERLEAVING(_)
+                | DTV_LNA(_)
+        )
+    }
+}
+
+/// Formats property as a line of the dvbv5 channel file: `KEY = value`.
+/// Properties which could not be stored in the channel file are formatted with `Debug`,
+/// see `is_channel_property`
+impl fmt::Display for DtvProperty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DTV_FREQUENCY(d) => write!(f, "FREQUENCY = {}", d),
+            DTV_MODULATION(d) => write!(f, "MODULATION = {}", d),
+            DTV_BANDWIDTH_HZ(d) => write!(f, "BANDWIDTH_HZ = {}", d),
+            DTV_INVERSION(d) => write!(f, "INVERSION = {}", d),
+            DTV_SYMBOL_RATE(d) => write!(f, "SYMBOL_RATE = {}", d),
+            DTV_INNER_FEC(d) => write!(f, "INNER_FEC = {}", d),
+            DTV_VOLTAGE(d) => write!(f, "VOLTAGE = {}", d),
+            DTV_TONE(d) => write!(f, "TONE = {}", d),
+            DTV_PILOT(d) => write!(f, "PILOT = {}", d),
+            DTV_ROLLOFF(d) => write!(f, "ROLLOFF = {}", d),
+            DTV_DELIVERY_SYSTEM(d) => write!(f, "DELIVERY_SYSTEM = {}", d),
+            DTV_ISDBT_PARTIAL_RECEPTION(d) => write!(f, "ISDBT_PARTIAL_RECEPTION = {}", d),
+            DTV_ISDBT_SOUND_BROADCASTING(d) => write!(f, "ISDBT_SOUND_BROADCASTING = {}", d),
+            DTV_ISDBT_SB_SUBCHANNEL_ID(d) => write!(f, "ISDBT_SB_SUBCHANNEL_ID = {}", d),
+            DTV_ISDBT_SB_SEGMENT_IDX(d) => write!(f, "ISDBT_SB_SEGMENT_IDX = {}", d),
+            DTV_ISDBT_SB_SEGMENT_COUNT(d) => write!(f, "ISDBT_SB_SEGMENT_COUNT = {}", d),
+            DTV_ISDBT_LAYERA_FEC(d) => write!(f, "ISDBT_LAYERA_FEC = {}", d),
+            DTV_ISDBT_LAYERA_MODULATION(d) => write!(f, "ISDBT_LAYERA_MODULATION = {}", d),
+            DTV_ISDBT_LAYERA_SEGMENT_COUNT(d) => write!(f, "ISDBT_LAYERA_SEGMENT_COUNT = {}", d),
+            DTV_ISDBT_LAYERA_TIME_INTERLEAVING(d) => write!(f, "ISDBT_LAYERA_TIME_INTERLEAVING = {}", d),
+            DTV_ISDBT_LAYERB_FEC(d) => write!(f, "ISDBT_LAYERB_FEC = {}", d),
+            DTV_ISDBT_LAYERB_MODULATION(d) => write!(f, "ISDBT_LAYERB_MODULATION = {}", d),
+            DTV_ISDBT_LAYERB_SEGMENT_COUNT(d) => write!(f, "ISDBT_LAYERB_SEGMENT_COUNT = {}", d),
+            DTV_ISDBT_LAYERB_TIME_INTERLEAVING(d) => write!(f, "ISDBT_LAYERB_TIME_INTERLEAVING = {}", d),
+            DTV_ISDBT_LAYERC_FEC(d) => write!(f, "ISDBT_LAYERC_FEC = {}", d),
+            DTV_ISDBT_LAYERC_MODULATION(d) => write!(f, "ISDBT_LAYERC_MODULATION = {}", d),
+            DTV_ISDBT_LAYERC_SEGMENT_COUNT(d) => write!(f, "ISDBT_LAYERC_SEGMENT_COUNT = {}", d),
+            DTV_ISDBT_LAYERC_TIME_INTERLEAVING(d) => write!(f, "ISDBT_LAYERC_TIME_INTERLEAVING = {}", d),
+            DTV_CODE_RATE_HP(d) => write!(f, "CODE_RATE_HP = {}", d),
+            DTV_CODE_RATE_LP(d) => write!(f, "CODE_RATE_LP = {}", d),
+            DTV_GUARD_INTERVAL(d) => write!(f, "GUARD_INTERVAL = {}", d),
+            DTV_TRANSMISSION_MODE(d) => write!(f, "TRANSMISSION_MODE = {}", d),
+            DTV_HIERARCHY(d) => write!(f, "HIERARCHY = {}", d),
+            DTV_ISDBT_LAYER_ENABLED(d) => write!(f, "ISDBT_LAYER_ENABLED = {}", d),
+            DTV_STREAM_ID(d) => write!(f, "STREAM_ID = {}", d),
+            DTV_ATSCMH_FIC_VER(d) => write!(f, "ATSCMH_FIC_VER = {}", d),
+            DTV_ATSCMH_PARADE_ID(d) => write!(f, "ATSCMH_PARADE_ID = {}", d),
+            DTV_ATSCMH_NOG(d) => write!(f, "ATSCMH_NOG = {}", d),
+            DTV_ATSCMH_TNOG(d) => write!(f, "ATSCMH_TNOG = {}", d),
+            DTV_ATSCMH_SGN(d) => write!(f, "ATSCMH_SGN = {}", d),
+            DTV_ATSCMH_PRC(d) => write!(f, "ATSCMH_PRC = {}", d),
+            DTV_INTERLEAVING(d) => write!(f, "INTERLEAVING = {}", d),
+            DTV_LNA(d) => write!(f, "LNA = {}", d),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
 /// num of properties cannot exceed DTV_IOCTL_MAX_MSGS per ioctl
 pub const DTV_IOCTL_MAX_MSGS: usize = 64;
 
+/// DTV_STREAM_ID value to disable stream filtering
+pub const NO_STREAM_ID_FILTER: u32 = !0;
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct FeParameters {
@@ -1064,16 +1476,184 @@ pub struct FeEvent {
     pub parameters: FeParameters,
 }
 
+assert_size!(FeEvent, 40);
+
 impl Default for FeEvent {
     #[inline]
     fn default() -> Self {
-        unsafe { mem::zeroed::<Self>() }
+        Self::zeroed()
     }
 }
 
 impl FeEvent {
+    /// Creates zero-initialized structure for the ioctl call.
+    /// All fields are integers or arrays of integers, so zero is valid value
+    #[inline]
+    pub fn zeroed() -> Self {
+        unsafe { mem::zeroed::<Self>() }
+    }
+
     #[inline]
     pub fn as_mut_ptr(&mut self) -> *mut FeEvent {
         self as *mut _
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::tests::is_zeroed};
+
+    #[test]
+    fn zeroed() {
+        let info = FeInfo::default();
+        assert!(is_zeroed(&info));
+        assert_eq!(info.fe_type, FE_QPSK);
+        assert!(info.caps.is_empty());
+
+        let cmd = DiseqcMasterCmd::default();
+        assert!(is_zeroed(&cmd));
+        assert!(cmd.data().is_empty());
+
+        assert!(is_zeroed(&DiseqcSlaveReply::default()));
+        assert!(is_zeroed(&FeEvent::default()));
+        assert!(is_zeroed(&DtvPropertyRequestInt::<u32>::default()));
+    }
+
+    #[test]
+    fn diseqc_master_cmd_length() {
+        assert!(DiseqcMasterCmd::new(&[0xE0, 0x10]).is_err());
+        assert!(DiseqcMasterCmd::new(&[0xE0, 0x10, 0x38, 0xF0, 0x00, 0x00, 0x00]).is_err());
+
+        let cmd = DiseqcMasterCmd::new(&[0xE0, 0x10, 0x00]).unwrap();
+        assert_eq!(cmd.data(), &[0xE0, 0x10, 0x00]);
+
+        let msg = [0xE0, 0x31, 0x6B, 0x12, 0x34, 0x56];
+        let mut cmd = DiseqcMasterCmd::new(&msg).unwrap();
+        assert_eq!(cmd.data(), &msg);
+
+        cmd.len = 200;
+        assert_eq!(cmd.data(), &msg);
+    }
+
+    #[test]
+    fn diseqc_slave_reply_length() {
+        let mut reply = DiseqcSlaveReply {
+            msg: [0xE4, 0x01, 0x02, 0x03],
+            ..Default::default()
+        };
+        assert!(reply.data().is_empty());
+
+        reply.len = 2;
+        assert_eq!(reply.data(), &[0xE4, 0x01]);
+
+        reply.len = 4;
+        assert_eq!(reply.data(), &[0xE4, 0x01, 0x02, 0x03]);
+
+        reply.len = 5;
+        assert_eq!(reply.data(), &[0xE4, 0x01, 0x02, 0x03]);
+    }
+
+    /// One line for each key supported by `DtvProperty::from_str`
+    const CHANNEL_LINES: &[&str] = &[
+        "FREQUENCY = 474000000",
+        "MODULATION = QAM/64",
+        "BANDWIDTH_HZ = 8000000",
+        "INVERSION = OFF",
+        "SYMBOL_RATE = 27500000",
+        "INNER_FEC = 3/4",
+        "VOLTAGE = 18",
+        "TONE = ON",
+        "PILOT = AUTO",
+        "ROLLOFF = 35",
+        "DELIVERY_SYSTEM = dvbs2",
+        "ISDBT_PARTIAL_RECEPTION = 1",
+        "ISDBT_SOUND_BROADCASTING = 0",
+        "ISDBT_SB_SUBCHANNEL_ID = 22",
+        "ISDBT_SB_SEGMENT_IDX = 3",
+        "ISDBT_SB_SEGMENT_COUNT = 13",
+        "ISDBT_LAYERA_FEC = 2/3",
+        "ISDBT_LAYERA_MODULATION = QPSK",
+        "ISDBT_LAYERA_SEGMENT_COUNT = 1",
+        "ISDBT_LAYERA_TIME_INTERLEAVING = 4",
+        "ISDBT_LAYERB_FEC = 3/4",
+        "ISDBT_LAYERB_MODULATION = QAM/64",
+        "ISDBT_LAYERB_SEGMENT_COUNT = 12",
+        "ISDBT_LAYERB_TIME_INTERLEAVING = 2",
+        "ISDBT_LAYERC_FEC = AUTO",
+        "ISDBT_LAYERC_MODULATION = QAM/AUTO",
+        "ISDBT_LAYERC_SEGMENT_COUNT = 0",
+        "ISDBT_LAYERC_TIME_INTERLEAVING = 0",
+        "CODE_RATE_HP = 2/3",
+        "CODE_RATE_LP = NONE",
+        "GUARD_INTERVAL = 1/8",
+        "TRANSMISSION_MODE = 8K",
+        "HIERARCHY = NONE",
+        "ISDBT_LAYER_ENABLED = 7",
+        "STREAM_ID = 5",
+        "ATSCMH_FIC_VER = 1",
+        "ATSCMH_PARADE_ID = 2",
+        "ATSCMH_NOG = 3",
+        "ATSCMH_TNOG = 4",
+        "ATSCMH_SGN = 5",
+        "ATSCMH_PRC = 6",
+        "INTERLEAVING = 240",
+        "LNA = ON",
+    ];
+
+    #[test]
+    fn dtv_property_round_trip() {
+        for line in CHANNEL_LINES {
+            let property: DtvProperty = line.parse().unwrap();
+            assert_eq!(&property.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn dtv_property_case_insensitive() {
+        for line in CHANNEL_LINES {
+            let property: DtvProperty = line.to_ascii_lowercase().parse().unwrap();
+            assert_eq!(&property.to_string(), line);
+        }
+
+        let property: DtvProperty = "DELIVERY_SYSTEM = DVBS2".parse().unwrap();
+        assert!(matches!(property, DTV_DELIVERY_SYSTEM(d) if d.get().unwrap() == SYS_DVBS2));
+        let property: DtvProperty = "inversion = off".parse().unwrap();
+        assert!(matches!(property, DTV_INVERSION(d) if d.get().unwrap() == INVERSION_OFF));
+        assert!("UNKNOWN = 1".parse::<DtvProperty>().is_err());
+        assert!("MODULATION = QAM/7".parse::<DtvProperty>().is_err());
+    }
+
+    /// Sizes and alignment of the structures in linux/dvb/frontend.h
+    #[test]
+    fn kernel_abi() {
+        assert_eq!((mem::size_of::<FeInfo>(), mem::align_of::<FeInfo>()), (168, 4));
+        assert_eq!(
+            (mem::size_of::<DiseqcMasterCmd>(), mem::align_of::<DiseqcMasterCmd>()),
+            (7, 1)
+        );
+        assert_eq!(
+            (mem::size_of::<DiseqcSlaveReply>(), mem::align_of::<DiseqcSlaveReply>()),
+            (12, 4)
+        );
+        assert_eq!((mem::size_of::<FeParameters>(), mem::align_of::<FeParameters>()), (36, 4));
+        assert_eq!((mem::size_of::<FeEvent>(), mem::align_of::<FeEvent>()), (40, 4));
+        assert_eq!(mem::size_of::<fe_type>(), 4);
+    }
+
+    /// struct dtv_stats, dtv_fe_stats and dtv_property are packed
+    #[test]
+    fn dtv_property_abi() {
+        assert_eq!((mem::size_of::<DtvStat>(), mem::align_of::<DtvStat>()), (9, 1));
+        assert_eq!(
+            (mem::size_of::<DtvFrontendStats>(), mem::align_of::<DtvFrontendStats>()),
+            (37, 1)
+        );
+        assert_eq!(mem::size_of::<DtvPropertyRequestInt<u32>>(), 72);
+        assert_eq!(mem::size_of::<DtvPropertyRequestDeliverySystems>(), 72);
+        assert_eq!(mem::size_of::<DtvPropertyRequestFrontendStats>(), 72);
+
+        // cmd + request, array of properties is passed to the kernel without gaps
+        assert_eq!(mem::size_of::<DtvProperty>(), 76);
+        assert_eq!(mem::size_of::<[DtvProperty; 2]>(), 2 * 76);
+    }
+}
@@ -0,0 +1,198 @@
+use {
+    super::sys::*,
+    anyhow::{Context, Result},
+    std::{convert::TryFrom, fmt},
+};
+
+/// Standard channel bandwidth of terrestrial delivery systems
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bandwidth {
+    /// 1.712MHz. DVB-T2 only
+    Mhz1_712,
+    Mhz5,
+    Mhz6,
+    Mhz7,
+    Mhz8,
+    /// 10MHz. DVB-T2 only
+    Mhz10,
+}
+
+impl Bandwidth {
+    /// Returns bandwidth in Hz for the DTV_BANDWIDTH_HZ property
+    pub fn hz(self) -> u32 {
+        match self {
+            Bandwidth::Mhz1_712 => 1_712_000,
+            Bandwidth::Mhz5 => 5_000_000,
+            Bandwidth::Mhz6 => 6_000_000,
+            Bandwidth::Mhz7 => 7_000_000,
+            Bandwidth::Mhz8 => 8_000_000,
+            Bandwidth::Mhz10 => 10_000_000,
+        }
+    }
+
+    /// Returns standard bandwidth for the value in Hz
+    pub fn from_hz(hz: u32) -> Option<Self> {
+        match hz {
+            1_712_000 => Some(Bandwidth::Mhz1_712),
+            5_000_000 => Some(Bandwidth::Mhz5),
+            6_000_000 => Some(Bandwidth::Mhz6),
+            7_000_000 => Some(Bandwidth::Mhz7),
+            8_000_000 => Some(Bandwidth::Mhz8),
+            10_000_000 => Some(Bandwidth::Mhz10),
+            _ => None,
+        }
+    }
+
+    /// Checks that bandwidth is allowed for the delivery system.
+    /// Returns true for delivery systems without bandwidth restrictions
+    pub fn is_allowed(self, delivery_system: fe_delivery_system) -> bool {
+        use Bandwidth::*;
+
+        match delivery_system {
+            SYS_DVBT => matches!(self, Mhz5 | Mhz6 | Mhz7 | Mhz8),
+            SYS_DVBT2 => true,
+            SYS_ISDBT | SYS_DTMB => matches!(self, Mhz6 | Mhz7 | Mhz8),
+            SYS_DVBC2 => matches!(self, Mhz6 | Mhz8),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Bandwidth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bandwidth::Mhz1_712 => write!(f, "1.712MHz"),
+            v => write!(f, "{}MHz", v.hz() / 1_000_000),
+        }
+    }
+}
+
+/// Frequency with explicit units.
+/// Converted to kHz for satellite delivery systems and to Hz for others
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Frequency(u64);
+
+impl Frequency {
+    #[inline]
+    pub const fn from_hz(hz: u64) -> Self {
+        Frequency(hz)
+    }
+
+    #[inline]
+    pub const fn from_khz(khz: u64) -> Self {
+        Frequency(khz * 1_000)
+    }
+
+    #[inline]
+    pub const fn from_mhz(mhz: u64) -> Self {
+        Frequency(mhz * 1_000_000)
+    }
+
+    #[inline]
+    pub fn as_hz(self) -> u64 {
+        self.0
+    }
+
+    /// Returns frequency in kHz, rounded down
+    #[inline]
+    pub fn as_khz(self) -> u64 {
+        self.0 / 1_000
+    }
+
+    /// Converts DTV_FREQUENCY value of the delivery system
+    pub fn from_property(delivery_system: fe_delivery_system, value: u32) -> Self {
+        if delivery_system.is_satellite() {
+            Frequency::from_khz(u64::from(value))
+        } else {
+            Frequency::from_hz(u64::from(value))
+        }
+    }
+
+    /// Returns DTV_FREQUENCY value for the delivery system
+    pub fn to_property(self, delivery_system: fe_delivery_system) -> Result<u32> {
+        let value = if delivery_system.is_satellite() {
+            self.as_khz()
+        } else {
+            self.as_hz()
+        };
+
+        u32::try_from(value).ok().with_context(|| {
+            format!("FE: frequency {} is out of range for {}", self, delivery_system)
+        })
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.0 % 1_000_000, self.0 % 1_000) {
+            (0, _) => write!(f, "{}MHz", self.0 / 1_000_000),
+            (_, 0) => write!(f, "{}kHz", self.0 / 1_000),
+            _ => write!(f, "{}Hz", self.0),
+        }
+    }
+}
+
+/// Symbol rate with explicit units. DTV_SYMBOL_RATE value is in bauds
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SymbolRate(u32);
+
+impl SymbolRate {
+    #[inline]
+    pub const fn from_bauds(bauds: u32) -> Self {
+        SymbolRate(bauds)
+    }
+
+    /// Symbol rate in kSym/s, for example 27500
+    #[inline]
+    pub const fn from_ksps(ksps: u32) -> Self {
+        SymbolRate(ksps * 1_000)
+    }
+
+    #[inline]
+    pub fn as_bauds(self) -> u32 {
+        self.0
+    }
+
+    /// Returns symbol rate in kSym/s, rounded down
+    #[inline]
+    pub fn as_ksps(self) -> u32 {
+        self.0 / 1_000
+    }
+}
+
+impl fmt::Display for SymbolRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 % 1_000 {
+            0 => write!(f, "{}kBd", self.0 / 1_000),
+            _ => write!(f, "{}Bd", self.0),
+        }
+    }
+}
+
+/// Complete tuning state of the frontend
+#[derive(Debug, Clone, Default)]
+pub struct TuningConfig {
+    /// Tuning properties. `DTV_TUNE` is appended automatically
+    pub properties: Vec<DtvProperty>,
+    /// LNB power. Only for satellite delivery systems
+    pub voltage: Option<fe_sec_voltage>,
+    /// 22kHz tone. Only for satellite delivery systems
+    pub tone: Option<fe_sec_tone_mode>,
+}
+
+impl TuningConfig {
+    /// Creates config with tuning properties
+    pub fn new(properties: &[DtvProperty]) -> Self {
+        TuningConfig {
+            properties: properties.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets LNB power and 22kHz tone
+    pub fn with_lnb(mut self, voltage: fe_sec_voltage, tone: fe_sec_tone_mode) -> Self {
+        self.voltage = Some(voltage);
+        self.tone = Some(tone);
+        self
+    }
+}
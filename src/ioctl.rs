@@ -0,0 +1,140 @@
+//! Backend for the ioctl calls
+//!
+//! Frontend and demux devices call ioctl through the backend.
+//! `SysBackend` passes requests to the kernel. Own implementation could
+//! capture requests and return prepared data to test devices without hardware.
+
+use {
+    nix::{errno::Errno, libc, sys::ioctl::ioctl_num_type},
+    std::os::{
+        raw::{c_int, c_void},
+        unix::io::RawFd,
+    },
+};
+
+/// Request code of the ioctl call
+pub type IoctlRequest = ioctl_num_type;
+
+/// Number of retries of the ioctl call interrupted by a signal (EINTR)
+pub const IOCTL_EINTR_RETRIES: usize = 8;
+
+/// Calls `f` again while it fails with EINTR,
+/// no more than `IOCTL_EINTR_RETRIES` times
+pub(crate) fn retry_eintr<T>(mut f: impl FnMut() -> nix::Result<T>) -> nix::Result<T> {
+    let mut retries = IOCTL_EINTR_RETRIES;
+    loop {
+        match f() {
+            Err(Errno::EINTR) if retries > 0 => retries -= 1,
+            result => return result,
+        }
+    }
+}
+
+pub trait IoctlBackend {
+    /// Performs ioctl `request` on the `fd`.
+    /// `arg` is a pointer to the request data or integer value
+    ///
+    /// # Safety
+    ///
+    /// `arg` should be valid for the request: pointer to the data of
+    /// the size encoded in the request or integer for requests without data
+    unsafe fn ioctl(&self, fd: RawFd, request: IoctlRequest, arg: *mut c_void)
+        -> nix::Result<c_int>;
+}
+
+/// Backend for the Linux kernel.
+/// Calls interrupted by a signal are retried
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SysBackend;
+
+impl IoctlBackend for SysBackend {
+    #[inline]
+    unsafe fn ioctl(
+        &self,
+        fd: RawFd,
+        request: IoctlRequest,
+        arg: *mut c_void,
+    ) -> nix::Result<c_int> {
+        retry_eintr(|| Errno::result(libc::ioctl(fd, request, arg)))
+    }
+}
+
+/// Recording backend for the unit tests
+#[cfg(test)]
+pub(crate) mod mock {
+    use {
+        super::{IoctlBackend, IoctlRequest},
+        nix::errno::Errno,
+        std::{
+            fmt,
+            os::{
+                raw::{c_int, c_void},
+                unix::io::RawFd,
+            },
+            sync::Mutex,
+        },
+    };
+
+    type Handler = Box<dyn FnMut(*mut c_void) -> nix::Result<c_int> + Send>;
+
+    /// Records all requests. Requests without handler return `Ok(0)`
+    /// and leave the argument unchanged
+    #[derive(Default)]
+    pub struct MockBackend {
+        calls: Mutex<Vec<IoctlRequest>>,
+        handlers: Mutex<Vec<(IoctlRequest, Handler)>>,
+    }
+
+    impl fmt::Debug for MockBackend {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_struct("MockBackend")
+                .field("calls", &self.calls())
+                .finish()
+        }
+    }
+
+    impl MockBackend {
+        /// Returns recorded requests
+        pub fn calls(&self) -> Vec<IoctlRequest> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        /// Forgets recorded requests
+        pub fn clear(&self) {
+            self.calls.lock().unwrap().clear();
+        }
+
+        /// Sets `handler` for the `request`. Handler receives ioctl argument
+        pub fn on(
+            &self,
+            request: IoctlRequest,
+            handler: impl FnMut(*mut c_void) -> nix::Result<c_int> + Send + 'static,
+        ) {
+            let mut handlers = self.handlers.lock().unwrap();
+            handlers.retain(|(r, _)| *r != request);
+            handlers.push((request, Box::new(handler)));
+        }
+
+        /// Makes `request` fail with `errno`
+        pub fn fail(&self, request: IoctlRequest, errno: Errno) {
+            self.on(request, move |_| Err(errno));
+        }
+    }
+
+    impl IoctlBackend for MockBackend {
+        unsafe fn ioctl(
+            &self,
+            _fd: RawFd,
+            request: IoctlRequest,
+            arg: *mut c_void,
+        ) -> nix::Result<c_int> {
+            self.calls.lock().unwrap().push(request);
+
+            let mut handlers = self.handlers.lock().unwrap();
+            match handlers.iter_mut().find(|(r, _)| *r == request) {
+                Some((_, handler)) => handler(arg),
+                None => Ok(0),
+            }
+        }
+    }
+}
@@ -1,14 +1,60 @@
 #[macro_use]
 extern crate anyhow;
 
+/// Compile-time check of the structure size passed to the kernel.
+/// Size mismatch means ABI drift and corrupted ioctl calls
+macro_rules! assert_size {
+    ( $t:ty, $size:expr ) => {
+        const _: [(); $size] = [(); ::std::mem::size_of::<$t>()];
+    };
+}
+
 pub mod ca;
+pub mod crc;
+mod device;
+mod error;
 pub mod fe;
+mod ioctl;
 pub mod net;
 pub mod dmx;
+pub mod dvr;
+pub mod si;
+pub mod ts;
+mod util;
 
 pub use {
     ca::CaDevice,
     fe::{FeDevice, FeStatus},
     net::NetDevice,
     dmx::DmxDevice,
+    dvr::DvrDevice,
+    device::DvbDevice,
+    error::DvbError,
+    ioctl::{IoctlBackend, IoctlRequest, SysBackend, IOCTL_EINTR_RETRIES},
 };
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{mem, slice};
+
+    /// Returns `true` if all bytes of the `value` are zero
+    pub(crate) fn is_zeroed<T>(value: &T) -> bool {
+        let bytes =
+            unsafe { slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) };
+        bytes.iter().all(|&b| b == 0)
+    }
+
+    #[test]
+    fn assert_size() {
+        #[repr(C)]
+        struct Request {
+            id: u32,
+            flags: u16,
+        }
+
+        // padding is included
+        assert_size!(Request, 8);
+        assert!(is_zeroed(&Request { id: 0, flags: 0 }));
+        assert!(!is_zeroed(&Request { id: 0, flags: 1 }));
+    }
+}
@@ -13,6 +13,7 @@ use {
         },
     },
     sys::*,
+    crate::{ioctl, DvbDevice},
 };
 
 pub const EMPTY_MAC: &str = "00:00:00:00:00:00";
@@ -34,6 +35,18 @@ impl AsRawFd for NetDevice {
     }
 }
 
+impl DvbDevice for NetDevice {
+    #[inline]
+    fn adapter(&self) -> u32 {
+        self.adapter
+    }
+
+    #[inline]
+    fn device(&self) -> u32 {
+        self.device
+    }
+}
+
 impl NetDevice {
     /// Attempts to open a network device in read-write mode
     pub fn open(adapter: u32, device: u32) -> Result<NetDevice> {
@@ -70,7 +83,8 @@ impl NetDevice {
             52,
             DvbNetIf
         );
-        unsafe { ioctl_call(self.as_raw_fd(), &mut data as *mut _) }.context("NET: add if")?;
+        ioctl::retry_eintr(|| unsafe { ioctl_call(self.as_raw_fd(), &mut data as *mut _) })
+            .context("NET: add if")?;
 
         Ok(NetInterface {
             net: self,
@@ -86,7 +100,9 @@ impl NetDevice {
             ioctl_call,
             request_code_none!(b'o', 53)
         );
-        unsafe { ioctl_call(self.as_raw_fd(), i32::from(interface.if_num)) }
+        ioctl::retry_eintr(|| unsafe {
+            ioctl_call(self.as_raw_fd(), i32::from(interface.if_num))
+        })
             .context("NET: remove if")?;
 
         Ok(())
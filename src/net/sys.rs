@@ -19,3 +19,16 @@ pub struct DvbNetIf {
     /// Encapsulation type of the feed
     pub feedtype: u8,
 }
+
+assert_size!(DvbNetIf, 6);
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::mem};
+
+    /// Size and alignment of struct dvb_net_if in linux/dvb/net.h
+    #[test]
+    fn kernel_abi() {
+        assert_eq!((mem::size_of::<DvbNetIf>(), mem::align_of::<DvbNetIf>()), (6, 2));
+    }
+}
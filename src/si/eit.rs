@@ -0,0 +1,174 @@
+use {
+    super::{
+        decode_bcd_time, decode_dvb_string, decode_utc_time, parse_descriptors, split_loop,
+        Descriptor, SectionHeader,
+    },
+    anyhow::Result,
+    std::{
+        convert::TryInto,
+        ops::RangeInclusive,
+        time::{Duration, SystemTime},
+    },
+};
+
+/// PID of the Event Information Table
+pub const EIT_PID: u16 = 0x0012;
+/// Table identifier of the present/following EIT for the actual transport stream
+pub const EIT_PF_ACTUAL_TABLE_ID: u8 = 0x4E;
+/// Table identifier of the present/following EIT for other transport stream
+pub const EIT_PF_OTHER_TABLE_ID: u8 = 0x4F;
+/// Table identifiers of the schedule EIT for the actual transport stream
+pub const EIT_SCHEDULE_ACTUAL_TABLE_ID: RangeInclusive<u8> = 0x50..=0x5F;
+/// Table identifiers of the schedule EIT for other transport stream
+pub const EIT_SCHEDULE_OTHER_TABLE_ID: RangeInclusive<u8> = 0x60..=0x6F;
+/// Tag of the short_event_descriptor
+pub const SHORT_EVENT_DESCRIPTOR_TAG: u8 = 0x4D;
+/// Tag of the extended_event_descriptor
+pub const EXTENDED_EVENT_DESCRIPTOR_TAG: u8 = 0x4E;
+
+/// Event from the Event Information Table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EitEvent {
+    pub event_id: u16,
+    /// Start time in UTC. `None` if undefined, for example for the NVOD reference event
+    pub start_time: Option<SystemTime>,
+    pub duration: Duration,
+    /// 0 - undefined, 1 - not running, 2 - starts in a few seconds,
+    /// 3 - pausing, 4 - running, 5 - service off-air
+    pub running_status: u8,
+    /// Event is scrambled
+    pub free_ca_mode: bool,
+    /// ISO 639-2 language code from the short event descriptor
+    pub language: Option<String>,
+    /// Event name from the short event descriptor
+    pub title: Option<String>,
+    /// Event text from the short event descriptor
+    pub description: Option<String>,
+    /// Text of all extended event descriptors
+    pub extended_description: Option<String>,
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Event Information Table
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Eit {
+    pub table_id: u8,
+    pub service_id: u16,
+    pub transport_stream_id: u16,
+    pub original_network_id: u16,
+    pub version_number: u8,
+    pub events: Vec<EitEvent>,
+}
+
+impl Eit {
+    /// Returns true for the present/following table
+    #[inline]
+    pub fn is_present_following(&self) -> bool {
+        self.table_id == EIT_PF_ACTUAL_TABLE_ID || self.table_id == EIT_PF_OTHER_TABLE_ID
+    }
+
+    /// Appends events from the next section of the same table
+    pub fn extend(&mut self, other: Eit) {
+        self.events.extend(other.events);
+    }
+}
+
+/// Returns length prefixed field and rest of the data
+fn split_field(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    ensure!(!data.is_empty(), "SI: event descriptor is too short");
+    let size = 1 + usize::from(data[0]);
+    ensure!(data.len() >= size, "SI: invalid event descriptor");
+
+    Ok((&data[1..size], &data[size..]))
+}
+
+/// EN 300 468 6.2.37 Short event descriptor: language, event name and text
+fn parse_short_event(data: &[u8]) -> Result<(String, String, String)> {
+    ensure!(data.len() >= 3, "SI: short event descriptor is too short");
+    let language = String::from_utf8_lossy(&data[0..3]).into_owned();
+    let (name, rest) = split_field(&data[3..])?;
+    let (text, _) = split_field(rest)?;
+
+    Ok((language, decode_dvb_string(name), decode_dvb_string(text)))
+}
+
+/// EN 300 468 6.2.15 Extended event descriptor: text without items
+fn parse_extended_event(data: &[u8]) -> Result<String> {
+    ensure!(data.len() >= 4, "SI: extended event descriptor is too short");
+    let (_items, rest) = split_field(&data[4..])?;
+    let (text, _) = split_field(rest)?;
+
+    Ok(decode_dvb_string(text))
+}
+
+/// Parses single section of the Event Information Table.
+/// Accepts present/following and schedule tables
+pub fn parse_eit(section: &[u8]) -> Result<Eit> {
+    let (header, payload) = SectionHeader::parse(section)?;
+    let table_id = header.table_id;
+    ensure!(
+        table_id == EIT_PF_ACTUAL_TABLE_ID
+            || table_id == EIT_PF_OTHER_TABLE_ID
+            || EIT_SCHEDULE_ACTUAL_TABLE_ID.contains(&table_id)
+            || EIT_SCHEDULE_OTHER_TABLE_ID.contains(&table_id),
+        "SI: invalid EIT table_id"
+    );
+    ensure!(payload.len() >= 6, "SI: EIT is too short");
+
+    let mut eit = Eit {
+        table_id: header.table_id,
+        service_id: header.table_id_extension,
+        transport_stream_id: u16::from_be_bytes(payload[0..2].try_into().unwrap()),
+        original_network_id: u16::from_be_bytes(payload[2..4].try_into().unwrap()),
+        version_number: header.version_number,
+        events: Vec::new(),
+    };
+
+    let mut data = &payload[6..];
+    while !data.is_empty() {
+        ensure!(data.len() >= 12, "SI: EIT event is too short");
+
+        let event_id = u16::from_be_bytes(data[0..2].try_into().unwrap());
+        let start_time = decode_utc_time(&data[2..7])?;
+        let duration = decode_bcd_time(&data[7..10])?;
+        let running_status = data[10] >> 5;
+        let free_ca_mode = data[10] & 0x10 != 0;
+        let (descriptors, next) = split_loop(&data[10..])?;
+        let descriptors = parse_descriptors(descriptors)?;
+
+        let mut event = EitEvent {
+            event_id,
+            start_time,
+            duration,
+            running_status,
+            free_ca_mode,
+            language: None,
+            title: None,
+            description: None,
+            extended_description: None,
+            descriptors: Vec::new(),
+        };
+
+        for d in &descriptors {
+            match d.tag {
+                SHORT_EVENT_DESCRIPTOR_TAG if event.title.is_none() => {
+                    let (language, title, description) = parse_short_event(&d.data)?;
+                    event.language = Some(language);
+                    event.title = Some(title);
+                    event.description = Some(description);
+                }
+                EXTENDED_EVENT_DESCRIPTOR_TAG => {
+                    let text = parse_extended_event(&d.data)?;
+                    event.extended_description.get_or_insert_with(String::new).push_str(&text);
+                }
+                _ => {}
+            }
+        }
+
+        event.descriptors = descriptors;
+        eit.events.push(event);
+        data = next;
+    }
+
+    Ok(eit)
+}
@@ -0,0 +1,160 @@
+//! Service Information
+//!
+//! Parsers for the MPEG-TS PSI (ISO/IEC 13818-1) and DVB SI (EN 300 468) tables
+mod eit;
+mod nit;
+mod pat;
+mod pmt;
+mod reader;
+mod sdt;
+mod tdt;
+mod text;
+
+use {
+    crate::{crc::crc32_mpeg, util},
+    anyhow::{Context, Result},
+    std::{
+        convert::TryInto,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+pub use {
+    eit::*,
+    nit::*,
+    pat::*,
+    pmt::*,
+    reader::SectionReader,
+    sdt::*,
+    tdt::*,
+    text::decode_dvb_string,
+};
+
+/// Size of the section header before section_length field is applied
+pub const SECTION_HEADER_SIZE: usize = 3;
+/// Size of the long section header (with section_syntax_indicator)
+pub const LONG_SECTION_HEADER_SIZE: usize = 8;
+/// Size of the CRC-32 at the end of the long section
+pub const CRC32_SIZE: usize = 4;
+
+/// Common header of the long sections (section_syntax_indicator is 1)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SectionHeader {
+    pub table_id: u8,
+    pub table_id_extension: u16,
+    pub version_number: u8,
+    pub current_next_indicator: bool,
+    pub section_number: u8,
+    pub last_section_number: u8,
+}
+
+impl SectionHeader {
+    /// Parses section header and returns it with the section payload.
+    /// Payload is the data between header and CRC-32.
+    /// CRC-32 is checked in software, so sections could be received without `DMX_CHECK_CRC`
+    pub fn parse(section: &[u8]) -> Result<(SectionHeader, &[u8])> {
+        ensure!(
+            section.len() >= LONG_SECTION_HEADER_SIZE + CRC32_SIZE,
+            "SI: section is too short"
+        );
+        ensure!(section[1] & 0x80 != 0, "SI: section is not a long section");
+
+        let section_length = usize::from(u16::from_be_bytes(section[1..3].try_into().unwrap()) & 0x0FFF);
+        let size = SECTION_HEADER_SIZE + section_length;
+        ensure!(
+            section_length >= LONG_SECTION_HEADER_SIZE - SECTION_HEADER_SIZE + CRC32_SIZE && size <= section.len(),
+            "SI: invalid section length"
+        );
+        ensure!(crc32_mpeg(&section[..size]) == 0, "SI: invalid section CRC");
+
+        let header = SectionHeader {
+            table_id: section[0],
+            table_id_extension: u16::from_be_bytes(section[3..5].try_into().unwrap()),
+            version_number: (section[5] >> 1) & 0x1F,
+            current_next_indicator: section[5] & 0x01 != 0,
+            section_number: section[6],
+            last_section_number: section[7],
+        };
+        ensure!(
+            header.section_number <= header.last_section_number,
+            "SI: invalid section number"
+        );
+
+        Ok((header, &section[LONG_SECTION_HEADER_SIZE..size - CRC32_SIZE]))
+    }
+}
+
+/// Descriptor from the descriptors loop
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Descriptor {
+    pub tag: u8,
+    pub data: Vec<u8>,
+}
+
+/// Parses descriptors loop
+pub fn parse_descriptors(mut data: &[u8]) -> Result<Vec<Descriptor>> {
+    let mut result = Vec::new();
+
+    while !data.is_empty() {
+        ensure!(data.len() >= 2, "SI: descriptor is too short");
+        let size = 2 + usize::from(data[1]);
+        ensure!(data.len() >= size, "SI: invalid descriptor length");
+
+        result.push(Descriptor {
+            tag: data[0],
+            data: data[2..size].to_vec(),
+        });
+        data = &data[size..];
+    }
+
+    Ok(result)
+}
+
+/// Splits 12-bit length prefixed loop from the data.
+/// Returns loop data and rest of the data.
+fn split_loop(data: &[u8]) -> Result<(&[u8], &[u8])> {
+    ensure!(data.len() >= 2, "SI: loop is too short");
+    let size = 2 + usize::from(u16::from_be_bytes(data[0..2].try_into().unwrap()) & 0x0FFF);
+    ensure!(data.len() >= size, "SI: invalid loop length");
+
+    Ok((&data[2..size], &data[size..]))
+}
+
+/// Decodes first `digits` BCD digits of the data
+fn decode_bcd(data: &[u8], digits: usize) -> Result<u32> {
+    ensure!(data.len() * 2 >= digits, "SI: BCD value is too short");
+
+    let mut result = 0u32;
+    for v in data.iter().flat_map(|v| [v >> 4, v & 0x0F]).take(digits) {
+        ensure!(v <= 9, "SI: invalid BCD value");
+        result = result * 10 + u32::from(v);
+    }
+
+    Ok(result)
+}
+
+/// Decodes 24 bit BCD time `hhmmss` to the duration
+fn decode_bcd_time(data: &[u8]) -> Result<Duration> {
+    ensure!(data.len() >= 3, "SI: BCD time is too short");
+
+    let hours = decode_bcd(&data[0..1], 2)?;
+    let minutes = decode_bcd(&data[1..2], 2)?;
+    let seconds = decode_bcd(&data[2..3], 2)?;
+
+    Ok(Duration::from_secs(u64::from(hours * 3_600 + minutes * 60 + seconds)))
+}
+
+/// Decodes 40 bit UTC time: 16 bit MJD and 24 bit BCD time.
+/// Returns `None` if all bits are set (time is undefined)
+fn decode_utc_time(data: &[u8]) -> Result<Option<SystemTime>> {
+    ensure!(data.len() >= 5, "SI: UTC time is too short");
+
+    if data[..5].iter().all(|&v| v == 0xFF) {
+        return Ok(None);
+    }
+
+    let mjd = u16::from_be_bytes(data[0..2].try_into().unwrap());
+    let days = util::mjd_to_unix_days(mjd).context("SI: UTC time before 1970")?;
+
+    Ok(Some(UNIX_EPOCH + Duration::from_secs(days * 86_400) + decode_bcd_time(&data[2..5])?))
+}
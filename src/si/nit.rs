@@ -0,0 +1,271 @@
+use {
+    super::{
+        decode_bcd, decode_dvb_string, parse_descriptors, split_loop, Descriptor,
+        SectionHeader,
+    },
+    crate::{
+        dtv_property,
+        fe::{sys::*, Polarization, TuningConfig},
+    },
+    anyhow::Result,
+    std::convert::TryInto,
+};
+
+/// PID of the Network Information Table
+pub const NIT_PID: u16 = 0x0010;
+/// Table identifier of the Network Information Table for the actual network
+pub const NIT_ACTUAL_TABLE_ID: u8 = 0x40;
+/// Table identifier of the Network Information Table for other network
+pub const NIT_OTHER_TABLE_ID: u8 = 0x41;
+/// Tag of the network_name_descriptor
+pub const NETWORK_NAME_DESCRIPTOR_TAG: u8 = 0x40;
+/// Tag of the satellite_delivery_system_descriptor
+pub const SATELLITE_DELIVERY_DESCRIPTOR_TAG: u8 = 0x43;
+/// Tag of the cable_delivery_system_descriptor
+pub const CABLE_DELIVERY_DESCRIPTOR_TAG: u8 = 0x44;
+/// Tag of the terrestrial_delivery_system_descriptor
+pub const TERRESTRIAL_DELIVERY_DESCRIPTOR_TAG: u8 = 0x5A;
+
+/// Transport stream from the Network Information Table
+#[derive(Debug, Clone)]
+pub struct NitTransportStream {
+    pub transport_stream_id: u16,
+    pub original_network_id: u16,
+    /// Tuning configuration from the delivery system descriptor.
+    /// For satellite transponders frequency is not converted to the intermediate frequency
+    pub tuning: Option<TuningConfig>,
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Network Information Table
+#[derive(Debug, Default, Clone)]
+pub struct Nit {
+    pub network_id: u16,
+    pub version_number: u8,
+    /// Network name from the network_name_descriptor
+    pub network_name: Option<String>,
+    pub descriptors: Vec<Descriptor>,
+    pub transport_streams: Vec<NitTransportStream>,
+}
+
+impl Nit {
+    /// Appends transport streams from the next section of the same table
+    pub fn extend(&mut self, other: Nit) {
+        if other.network_name.is_some() {
+            self.network_name = other.network_name;
+        }
+        self.descriptors.extend(other.descriptors);
+        self.transport_streams.extend(other.transport_streams);
+    }
+}
+
+/// Converts FEC_inner of the satellite and cable delivery system descriptors
+fn inner_fec(v: u8) -> fe_code_rate {
+    match v {
+        1 => FEC_1_2,
+        2 => FEC_2_3,
+        3 => FEC_3_4,
+        4 => FEC_5_6,
+        5 => FEC_7_8,
+        6 => FEC_8_9,
+        7 => FEC_3_5,
+        8 => FEC_4_5,
+        9 => FEC_9_10,
+        15 => FEC_NONE,
+        _ => FEC_AUTO,
+    }
+}
+
+/// EN 300 468 6.2.13.2 Satellite delivery system descriptor
+fn parse_satellite_delivery(data: &[u8]) -> Result<TuningConfig> {
+    ensure!(data.len() >= 11, "SI: satellite delivery descriptor is too short");
+
+    // 10kHz units
+    let frequency = decode_bcd(&data[0..4], 8)? * 10;
+    // 100 symbol/s units
+    let symbol_rate = decode_bcd(&data[7..11], 7)? * 100;
+
+    let is_dvbs2 = data[6] & 0x04 != 0;
+    let modulation = match data[6] & 0x03 {
+        1 => QPSK,
+        2 => PSK_8,
+        3 => QAM_16,
+        _ => QPSK,
+    };
+
+    let mut properties = vec![
+        dtv_property!(DTV_DELIVERY_SYSTEM(if is_dvbs2 { SYS_DVBS2 } else { SYS_DVBS })),
+        dtv_property!(DTV_FREQUENCY(frequency)),
+        dtv_property!(DTV_SYMBOL_RATE(symbol_rate)),
+        dtv_property!(DTV_MODULATION(modulation)),
+        dtv_property!(DTV_INNER_FEC(inner_fec(data[10] & 0x0F))),
+    ];
+
+    if is_dvbs2 {
+        let rolloff = match (data[6] >> 3) & 0x03 {
+            0 => ROLLOFF_35,
+            1 => ROLLOFF_25,
+            2 => ROLLOFF_20,
+            _ => ROLLOFF_AUTO,
+        };
+        properties.push(dtv_property!(DTV_ROLLOFF(rolloff)));
+    }
+
+    let polarization = match (data[6] >> 5) & 0x03 {
+        0 => Polarization::Horizontal,
+        1 => Polarization::Vertical,
+        2 => Polarization::Left,
+        _ => Polarization::Right,
+    };
+
+    Ok(TuningConfig {
+        properties,
+        voltage: Some(polarization.to_voltage()),
+        tone: None,
+    })
+}
+
+/// EN 300 468 6.2.13.1 Cable delivery system descriptor
+fn parse_cable_delivery(data: &[u8]) -> Result<TuningConfig> {
+    ensure!(data.len() >= 11, "SI: cable delivery descriptor is too short");
+
+    // 100Hz units
+    let frequency = decode_bcd(&data[0..4], 8)? * 100;
+    let symbol_rate = decode_bcd(&data[7..11], 7)? * 100;
+
+    let modulation = match data[6] {
+        1 => QAM_16,
+        2 => QAM_32,
+        3 => QAM_64,
+        4 => QAM_128,
+        5 => QAM_256,
+        _ => QAM_AUTO,
+    };
+
+    Ok(TuningConfig::new(&[
+        dtv_property!(DTV_DELIVERY_SYSTEM(SYS_DVBC_ANNEX_A)),
+        dtv_property!(DTV_FREQUENCY(frequency)),
+        dtv_property!(DTV_SYMBOL_RATE(symbol_rate)),
+        dtv_property!(DTV_MODULATION(modulation)),
+        dtv_property!(DTV_INNER_FEC(inner_fec(data[10] & 0x0F))),
+    ]))
+}
+
+/// EN 300 468 6.2.13.4 Terrestrial delivery system descriptor
+fn parse_terrestrial_delivery(data: &[u8]) -> Result<TuningConfig> {
+    ensure!(data.len() >= 7, "SI: terrestrial delivery descriptor is too short");
+
+    // 10Hz units
+    let frequency = u32::from_be_bytes(data[0..4].try_into().unwrap()).saturating_mul(10);
+
+    let bandwidth: u32 = match data[4] >> 5 {
+        0 => 8_000_000,
+        1 => 7_000_000,
+        2 => 6_000_000,
+        3 => 5_000_000,
+        _ => 0,
+    };
+    let modulation = match data[5] >> 6 {
+        0 => QPSK,
+        1 => QAM_16,
+        2 => QAM_64,
+        _ => QAM_AUTO,
+    };
+    let hierarchy = match (data[5] >> 3) & 0x03 {
+        0 => HIERARCHY_NONE,
+        1 => HIERARCHY_1,
+        2 => HIERARCHY_2,
+        _ => HIERARCHY_4,
+    };
+    let code_rate = |v: u8| match v {
+        0 => FEC_1_2,
+        1 => FEC_2_3,
+        2 => FEC_3_4,
+        3 => FEC_5_6,
+        4 => FEC_7_8,
+        _ => FEC_AUTO,
+    };
+    let guard_interval = match (data[6] >> 3) & 0x03 {
+        0 => GUARD_INTERVAL_1_32,
+        1 => GUARD_INTERVAL_1_16,
+        2 => GUARD_INTERVAL_1_8,
+        _ => GUARD_INTERVAL_1_4,
+    };
+    let transmission_mode = match (data[6] >> 1) & 0x03 {
+        0 => TRANSMISSION_MODE_2K,
+        1 => TRANSMISSION_MODE_8K,
+        2 => TRANSMISSION_MODE_4K,
+        _ => TRANSMISSION_MODE_AUTO,
+    };
+
+    Ok(TuningConfig::new(&[
+        dtv_property!(DTV_DELIVERY_SYSTEM(SYS_DVBT)),
+        dtv_property!(DTV_FREQUENCY(frequency)),
+        dtv_property!(DTV_BANDWIDTH_HZ(bandwidth)),
+        dtv_property!(DTV_MODULATION(modulation)),
+        dtv_property!(DTV_HIERARCHY(hierarchy)),
+        dtv_property!(DTV_CODE_RATE_HP(code_rate(data[5] & 0x07))),
+        dtv_property!(DTV_CODE_RATE_LP(code_rate(data[6] >> 5))),
+        dtv_property!(DTV_GUARD_INTERVAL(guard_interval)),
+        dtv_property!(DTV_TRANSMISSION_MODE(transmission_mode)),
+    ]))
+}
+
+/// Returns tuning configuration from the first delivery system descriptor
+fn parse_delivery(descriptors: &[Descriptor]) -> Result<Option<TuningConfig>> {
+    for d in descriptors {
+        let tuning = match d.tag {
+            SATELLITE_DELIVERY_DESCRIPTOR_TAG => parse_satellite_delivery(&d.data)?,
+            CABLE_DELIVERY_DESCRIPTOR_TAG => parse_cable_delivery(&d.data)?,
+            TERRESTRIAL_DELIVERY_DESCRIPTOR_TAG => parse_terrestrial_delivery(&d.data)?,
+            _ => continue,
+        };
+        return Ok(Some(tuning));
+    }
+
+    Ok(None)
+}
+
+/// Parses single section of the Network Information Table
+pub fn parse_nit(section: &[u8]) -> Result<Nit> {
+    let (header, payload) = SectionHeader::parse(section)?;
+    ensure!(
+        header.table_id == NIT_ACTUAL_TABLE_ID || header.table_id == NIT_OTHER_TABLE_ID,
+        "SI: invalid NIT table_id"
+    );
+
+    let (descriptors, data) = split_loop(payload)?;
+    let descriptors = parse_descriptors(descriptors)?;
+    let network_name = descriptors
+        .iter()
+        .find(|d| d.tag == NETWORK_NAME_DESCRIPTOR_TAG)
+        .map(|d| decode_dvb_string(&d.data));
+
+    let mut nit = Nit {
+        network_id: header.table_id_extension,
+        version_number: header.version_number,
+        network_name,
+        descriptors,
+        transport_streams: Vec::new(),
+    };
+
+    let (mut data, _) = split_loop(data)?;
+    while !data.is_empty() {
+        ensure!(data.len() >= 6, "SI: NIT transport stream is too short");
+
+        let transport_stream_id = u16::from_be_bytes(data[0..2].try_into().unwrap());
+        let original_network_id = u16::from_be_bytes(data[2..4].try_into().unwrap());
+        let (descriptors, next) = split_loop(&data[4..])?;
+        let descriptors = parse_descriptors(descriptors)?;
+
+        nit.transport_streams.push(NitTransportStream {
+            transport_stream_id,
+            original_network_id,
+            tuning: parse_delivery(&descriptors)?,
+            descriptors,
+        });
+        data = next;
+    }
+
+    Ok(nit)
+}
@@ -0,0 +1,112 @@
+use {
+    super::SectionHeader,
+    anyhow::Result,
+    std::convert::TryInto,
+};
+
+/// PID of the Program Association Table
+pub const PAT_PID: u16 = 0x0000;
+/// Table identifier of the Program Association Table
+pub const PAT_TABLE_ID: u8 = 0x00;
+
+/// Program from the Program Association Table
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PatProgram {
+    pub program_number: u16,
+    /// PID of the Program Map Table
+    pub pid: u16,
+}
+
+/// Program Association Table
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Pat {
+    pub transport_stream_id: u16,
+    pub version_number: u8,
+    /// PID of the Network Information Table (program_number 0)
+    pub network_pid: Option<u16>,
+    pub programs: Vec<PatProgram>,
+}
+
+impl Pat {
+    /// Appends programs from the next section of the same table
+    pub fn extend(&mut self, other: Pat) {
+        if other.network_pid.is_some() {
+            self.network_pid = other.network_pid;
+        }
+        self.programs.extend(other.programs);
+    }
+
+    /// Returns PMT PID for the program
+    pub fn get_pmt_pid(&self, program_number: u16) -> Option<u16> {
+        self.programs
+            .iter()
+            .find(|p| p.program_number == program_number)
+            .map(|p| p.pid)
+    }
+}
+
+/// Parses single section of the Program Association Table
+pub fn parse_pat(section: &[u8]) -> Result<Pat> {
+    let (header, payload) = SectionHeader::parse(section)?;
+    ensure!(header.table_id == PAT_TABLE_ID, "SI: invalid PAT table_id");
+    ensure!(payload.len() % 4 == 0, "SI: invalid PAT length");
+
+    let mut pat = Pat {
+        transport_stream_id: header.table_id_extension,
+        version_number: header.version_number,
+        ..Default::default()
+    };
+
+    for item in payload.chunks_exact(4) {
+        let program_number = u16::from_be_bytes(item[0..2].try_into().unwrap());
+        let pid = u16::from_be_bytes(item[2..4].try_into().unwrap()) & 0x1FFF;
+
+        if program_number == 0 {
+            pat.network_pid = Some(pid);
+        } else {
+            pat.programs.push(PatProgram {
+                program_number,
+                pid,
+            });
+        }
+    }
+
+    Ok(pat)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::crc::crc32_mpeg};
+
+    /// PAT with NIT on PID 0x0010 and program 1 on PID 0x0100
+    fn pat_section() -> Vec<u8> {
+        let mut section = vec![PAT_TABLE_ID, 0xB0, 0x11, 0x00, 0x01, 0xC3, 0x00, 0x00];
+        section.extend_from_slice(&[0x00, 0x00, 0xE0, 0x10]);
+        section.extend_from_slice(&[0x00, 0x01, 0xE1, 0x00]);
+        let crc = crc32_mpeg(&section);
+        section.extend_from_slice(&crc.to_be_bytes());
+        section
+    }
+
+    #[test]
+    fn parse() {
+        let pat = parse_pat(&pat_section()).unwrap();
+
+        assert_eq!(pat.transport_stream_id, 1);
+        assert_eq!(pat.version_number, 1);
+        assert_eq!(pat.network_pid, Some(0x0010));
+        assert_eq!(pat.get_pmt_pid(1), Some(0x0100));
+    }
+
+    #[test]
+    fn invalid_crc() {
+        let mut section = pat_section();
+        section[14] ^= 0x01;
+        assert!(parse_pat(&section).is_err());
+
+        let mut section = pat_section();
+        let last = section.len() - 1;
+        section[last] ^= 0x01;
+        assert!(parse_pat(&section).is_err());
+    }
+}
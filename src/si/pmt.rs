@@ -0,0 +1,62 @@
+use {
+    super::{parse_descriptors, split_loop, Descriptor, SectionHeader},
+    anyhow::Result,
+    std::convert::TryInto,
+};
+
+/// Table identifier of the Program Map Table
+pub const PMT_TABLE_ID: u8 = 0x02;
+
+/// Elementary stream from the Program Map Table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PmtStream {
+    pub stream_type: u8,
+    pub pid: u16,
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Program Map Table
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Pmt {
+    pub program_number: u16,
+    pub version_number: u8,
+    pub pcr_pid: u16,
+    /// Program level descriptors
+    pub descriptors: Vec<Descriptor>,
+    pub streams: Vec<PmtStream>,
+}
+
+/// Parses Program Map Table
+pub fn parse_pmt(section: &[u8]) -> Result<Pmt> {
+    let (header, payload) = SectionHeader::parse(section)?;
+    ensure!(header.table_id == PMT_TABLE_ID, "SI: invalid PMT table_id");
+    ensure!(payload.len() >= 4, "SI: PMT is too short");
+
+    let pcr_pid = u16::from_be_bytes(payload[0..2].try_into().unwrap()) & 0x1FFF;
+    let (descriptors, mut data) = split_loop(&payload[2..])?;
+
+    let mut pmt = Pmt {
+        program_number: header.table_id_extension,
+        version_number: header.version_number,
+        pcr_pid,
+        descriptors: parse_descriptors(descriptors)?,
+        streams: Vec::new(),
+    };
+
+    while !data.is_empty() {
+        ensure!(data.len() >= 5, "SI: PMT stream is too short");
+
+        let stream_type = data[0];
+        let pid = u16::from_be_bytes(data[1..3].try_into().unwrap()) & 0x1FFF;
+        let (descriptors, next) = split_loop(&data[3..])?;
+
+        pmt.streams.push(PmtStream {
+            stream_type,
+            pid,
+            descriptors: parse_descriptors(descriptors)?,
+        });
+        data = next;
+    }
+
+    Ok(pmt)
+}
@@ -0,0 +1,187 @@
+use {
+    super::{
+        parse_eit, parse_nit, parse_pat, parse_pmt, parse_sdt, Eit, Nit, Pat, Pmt, Sdt,
+        SectionHeader, EIT_PF_ACTUAL_TABLE_ID, EIT_PID, NIT_ACTUAL_TABLE_ID, NIT_PID, PAT_PID,
+        PAT_TABLE_ID, PMT_TABLE_ID, SDT_ACTUAL_TABLE_ID, SDT_PID,
+    },
+    crate::{
+        dmx::{sys::*, DmxDevice},
+        DvbError, IoctlBackend, SysBackend,
+    },
+    anyhow::{Context, Result},
+    std::time::{Duration, Instant},
+};
+
+/// Default time to wait for the complete table
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reads and parses standard tables with the demux device.
+/// Sets section filter for each table, collects all sections of the table
+/// and stops filtering when the table is complete.
+pub struct SectionReader<'a, B = SysBackend> {
+    dmx: &'a DmxDevice<B>,
+    timeout: Duration,
+}
+
+impl<'a, B: IoctlBackend> SectionReader<'a, B> {
+    /// Creates a new reader over the demux device
+    pub fn new(dmx: &'a DmxDevice<B>) -> Self {
+        SectionReader {
+            dmx,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets maximum time to wait for the complete table. Default is 10 seconds
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Reads all sections of the table with the same version.
+    /// If `table_id_extension` is defined, only sections with it will be accepted.
+    /// Sections are returned in order of the section_number.
+    /// On timeout returns `DvbError::Timeout`.
+    pub fn read_table(
+        &self,
+        pid: u16,
+        table_id: u8,
+        table_id_extension: Option<u16>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let mut filter = DmxFilter::builder().table_id(table_id);
+        if let Some(ext) = table_id_extension {
+            filter = filter.table_id_extension(ext);
+        }
+        let filter = filter.build()?;
+
+        let params = DmxSctFilterParams {
+            pid,
+            filter,
+            timeout: 0,
+            flags: DmxFilterFlags::DMX_CHECK_CRC,
+        };
+
+        self.dmx.replace_section_filter(&params)?;
+        let result = self.collect_sections();
+        self.dmx.stop()?;
+
+        result.with_context(|| format!("SI: failed to read table 0x{:02X} on pid {}", table_id, pid))
+    }
+
+    fn collect_sections(&self) -> Result<Vec<Vec<u8>>> {
+        let deadline = Instant::now() + self.timeout;
+        let mut version = None;
+        let mut sections: Vec<Option<Vec<u8>>> = Vec::new();
+
+        loop {
+            let remain = deadline.saturating_duration_since(Instant::now());
+            if remain.as_millis() == 0 {
+                return Err(DvbError::Timeout.into());
+            }
+
+            let section = self.dmx.read_section(remain.as_millis() as u32)?;
+            let (header, _) = match SectionHeader::parse(&section) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            if !header.current_next_indicator {
+                continue;
+            }
+
+            // restart on version change
+            if version != Some(header.version_number)
+                || sections.len() != usize::from(header.last_section_number) + 1
+            {
+                version = Some(header.version_number);
+                sections.clear();
+                sections.resize(usize::from(header.last_section_number) + 1, None);
+            }
+
+            sections[usize::from(header.section_number)] = Some(section);
+
+            if sections.iter().all(Option::is_some) {
+                return Ok(sections.into_iter().flatten().collect());
+            }
+        }
+    }
+
+    /// Reads Program Association Table
+    pub fn read_pat(&self) -> Result<Pat> {
+        let mut result: Option<Pat> = None;
+
+        for section in self.read_table(PAT_PID, PAT_TABLE_ID, None)? {
+            let pat = parse_pat(&section)?;
+            match result.as_mut() {
+                Some(v) => v.extend(pat),
+                None => result = Some(pat),
+            }
+        }
+
+        result.context("SI: empty PAT")
+    }
+
+    /// Reads Program Map Table for the program.
+    /// PMT PID is resolved with the Program Association Table
+    pub fn read_pmt(&self, program: u16) -> Result<Pmt> {
+        let pid = self
+            .read_pat()?
+            .get_pmt_pid(program)
+            .with_context(|| format!("SI: program {} not found", program))?;
+
+        let sections = self.read_table(pid, PMT_TABLE_ID, Some(program))?;
+        parse_pmt(&sections[0])
+    }
+
+    /// Reads Service Description Table for the actual transport stream
+    pub fn read_sdt(&self) -> Result<Sdt> {
+        let mut result: Option<Sdt> = None;
+
+        for section in self.read_table(SDT_PID, SDT_ACTUAL_TABLE_ID, None)? {
+            let sdt = parse_sdt(&section)?;
+            match result.as_mut() {
+                Some(v) => v.extend(sdt),
+                None => result = Some(sdt),
+            }
+        }
+
+        result.context("SI: empty SDT")
+    }
+
+    /// Reads Network Information Table for the actual network.
+    /// NIT PID is resolved with the Program Association Table, default is 0x0010
+    pub fn read_nit(&self) -> Result<Nit> {
+        self.read_nit_with_pid(self.read_pat()?.network_pid.unwrap_or(NIT_PID))
+    }
+
+    /// Reads Network Information Table for the actual network from the given PID.
+    /// Useful when the Program Association Table is already known
+    pub fn read_nit_with_pid(&self, pid: u16) -> Result<Nit> {
+        let mut result: Option<Nit> = None;
+
+        for section in self.read_table(pid, NIT_ACTUAL_TABLE_ID, None)? {
+            let nit = parse_nit(&section)?;
+            match result.as_mut() {
+                Some(v) => v.extend(nit),
+                None => result = Some(nit),
+            }
+        }
+
+        result.context("SI: empty NIT")
+    }
+
+    /// Reads present/following Event Information Table of the service
+    /// in the actual transport stream
+    pub fn read_eit_present_following(&self, service_id: u16) -> Result<Eit> {
+        let mut result: Option<Eit> = None;
+
+        for section in self.read_table(EIT_PID, EIT_PF_ACTUAL_TABLE_ID, Some(service_id))? {
+            let eit = parse_eit(&section)?;
+            match result.as_mut() {
+                Some(v) => v.extend(eit),
+                None => result = Some(eit),
+            }
+        }
+
+        result.context("SI: empty EIT")
+    }
+}
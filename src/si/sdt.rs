@@ -0,0 +1,119 @@
+use {
+    super::{decode_dvb_string, parse_descriptors, split_loop, Descriptor, SectionHeader},
+    anyhow::Result,
+    std::convert::TryInto,
+};
+
+/// PID of the Service Description Table
+pub const SDT_PID: u16 = 0x0011;
+/// Table identifier of the Service Description Table for the actual transport stream
+pub const SDT_ACTUAL_TABLE_ID: u8 = 0x42;
+/// Table identifier of the Service Description Table for other transport stream
+pub const SDT_OTHER_TABLE_ID: u8 = 0x46;
+/// Tag of the service_descriptor
+pub const SERVICE_DESCRIPTOR_TAG: u8 = 0x48;
+
+/// Service from the Service Description Table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdtService {
+    pub service_id: u16,
+    pub eit_schedule: bool,
+    pub eit_present_following: bool,
+    /// 0 - undefined, 1 - not running, 2 - starts in a few seconds,
+    /// 3 - pausing, 4 - running, 5 - service off-air
+    pub running_status: u8,
+    /// Service is scrambled
+    pub free_ca_mode: bool,
+    /// Service type from the service descriptor, for example 0x01 - digital television
+    pub service_type: Option<u8>,
+    /// Provider name from the service descriptor
+    pub provider_name: Option<String>,
+    /// Service name from the service descriptor
+    pub service_name: Option<String>,
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Service descriptor: service_type, provider name and service name
+fn parse_service_descriptor(data: &[u8]) -> Result<(u8, String, String)> {
+    ensure!(data.len() >= 2, "SI: service descriptor is too short");
+    let provider_size = usize::from(data[1]);
+    ensure!(data.len() >= 3 + provider_size, "SI: invalid service descriptor");
+    let provider = &data[2..2 + provider_size];
+
+    let rest = &data[2 + provider_size..];
+    let name_size = usize::from(rest[0]);
+    ensure!(rest.len() > name_size, "SI: invalid service descriptor");
+    let name = &rest[1..1 + name_size];
+
+    Ok((data[0], decode_dvb_string(provider), decode_dvb_string(name)))
+}
+
+/// Service Description Table
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Sdt {
+    pub transport_stream_id: u16,
+    pub original_network_id: u16,
+    pub version_number: u8,
+    pub services: Vec<SdtService>,
+}
+
+impl Sdt {
+    /// Appends services from the next section of the same table
+    pub fn extend(&mut self, other: Sdt) {
+        self.services.extend(other.services);
+    }
+}
+
+/// Parses single section of the Service Description Table
+pub fn parse_sdt(section: &[u8]) -> Result<Sdt> {
+    let (header, payload) = SectionHeader::parse(section)?;
+    ensure!(
+        header.table_id == SDT_ACTUAL_TABLE_ID || header.table_id == SDT_OTHER_TABLE_ID,
+        "SI: invalid SDT table_id"
+    );
+    ensure!(payload.len() >= 3, "SI: SDT is too short");
+
+    let mut sdt = Sdt {
+        transport_stream_id: header.table_id_extension,
+        original_network_id: u16::from_be_bytes(payload[0..2].try_into().unwrap()),
+        version_number: header.version_number,
+        services: Vec::new(),
+    };
+
+    let mut data = &payload[3..];
+    while !data.is_empty() {
+        ensure!(data.len() >= 5, "SI: SDT service is too short");
+
+        let service_id = u16::from_be_bytes(data[0..2].try_into().unwrap());
+        let eit_schedule = data[2] & 0x02 != 0;
+        let eit_present_following = data[2] & 0x01 != 0;
+        let running_status = data[3] >> 5;
+        let free_ca_mode = data[3] & 0x10 != 0;
+        let (descriptors, next) = split_loop(&data[3..])?;
+        let descriptors = parse_descriptors(descriptors)?;
+
+        let service = match descriptors.iter().find(|d| d.tag == SERVICE_DESCRIPTOR_TAG) {
+            Some(d) => Some(parse_service_descriptor(&d.data)?),
+            None => None,
+        };
+        let (service_type, provider_name, service_name) = match service {
+            Some((t, provider, name)) => (Some(t), Some(provider), Some(name)),
+            None => (None, None, None),
+        };
+
+        sdt.services.push(SdtService {
+            service_id,
+            eit_schedule,
+            eit_present_following,
+            running_status,
+            free_ca_mode,
+            service_type,
+            provider_name,
+            service_name,
+            descriptors,
+        });
+        data = next;
+    }
+
+    Ok(sdt)
+}
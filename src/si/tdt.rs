@@ -0,0 +1,110 @@
+use {
+    super::{
+        decode_bcd, decode_utc_time, parse_descriptors, split_loop, Descriptor, CRC32_SIZE,
+        SECTION_HEADER_SIZE,
+    },
+    crate::crc::crc32_mpeg,
+    anyhow::{Context, Result},
+    std::{convert::TryInto, time::SystemTime},
+};
+
+/// PID of the Time and Date Table and the Time Offset Table
+pub const TDT_PID: u16 = 0x0014;
+/// Table identifier of the Time and Date Table
+pub const TDT_TABLE_ID: u8 = 0x70;
+/// Table identifier of the Time Offset Table
+pub const TOT_TABLE_ID: u8 = 0x73;
+/// Tag of the local_time_offset_descriptor
+pub const LOCAL_TIME_OFFSET_DESCRIPTOR_TAG: u8 = 0x58;
+
+/// Local time offset of the country region
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalTimeOffset {
+    /// ISO 3166 country code
+    pub country_code: String,
+    pub country_region_id: u8,
+    /// Offset from UTC in seconds, negative to the west of Greenwich
+    pub local_time_offset: i32,
+    /// Time of the next offset change in UTC
+    pub time_of_change: Option<SystemTime>,
+    /// Offset from UTC in seconds after the `time_of_change`
+    pub next_time_offset: i32,
+}
+
+/// Time Offset Table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tot {
+    pub utc_time: SystemTime,
+    /// Offsets from all local time offset descriptors
+    pub local_time_offsets: Vec<LocalTimeOffset>,
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Checks short section header (section_syntax_indicator is 0) and returns section data
+fn parse_short_section(section: &[u8], table_id: u8) -> Result<&[u8]> {
+    ensure!(section.len() >= SECTION_HEADER_SIZE, "SI: section is too short");
+    ensure!(section[0] == table_id, "SI: invalid table_id");
+
+    let section_length = u16::from_be_bytes(section[1..3].try_into().unwrap()) & 0x0FFF;
+    let size = SECTION_HEADER_SIZE + usize::from(section_length);
+    ensure!(size <= section.len(), "SI: invalid section length");
+
+    Ok(&section[SECTION_HEADER_SIZE..size])
+}
+
+/// Decodes 16 bit BCD time offset `hhmm` with polarity to seconds
+fn decode_time_offset(data: &[u8], negative: bool) -> Result<i32> {
+    let hours = decode_bcd(&data[0..1], 2)?;
+    let minutes = decode_bcd(&data[1..2], 2)?;
+    let offset = (hours * 3_600 + minutes * 60) as i32;
+
+    Ok(if negative { -offset } else { offset })
+}
+
+/// EN 300 468 6.2.20 Local time offset descriptor
+fn parse_local_time_offsets(data: &[u8]) -> Result<Vec<LocalTimeOffset>> {
+    let items = data.chunks_exact(13);
+    ensure!(items.remainder().is_empty(), "SI: invalid local time offset descriptor");
+
+    items
+        .map(|item| {
+            let negative = item[3] & 0x01 != 0;
+            Ok(LocalTimeOffset {
+                country_code: String::from_utf8_lossy(&item[0..3]).into_owned(),
+                country_region_id: item[3] >> 2,
+                local_time_offset: decode_time_offset(&item[4..6], negative)?,
+                time_of_change: decode_utc_time(&item[6..11])?,
+                next_time_offset: decode_time_offset(&item[11..13], negative)?,
+            })
+        })
+        .collect()
+}
+
+/// Parses Time and Date Table and returns current time in UTC
+pub fn parse_tdt(section: &[u8]) -> Result<SystemTime> {
+    let data = parse_short_section(section, TDT_TABLE_ID)?;
+    decode_utc_time(data)?.context("SI: undefined TDT time")
+}
+
+/// Parses Time Offset Table: current time in UTC and local time offsets
+pub fn parse_tot(section: &[u8]) -> Result<Tot> {
+    let data = parse_short_section(section, TOT_TABLE_ID)?;
+    ensure!(data.len() >= 5 + 2 + CRC32_SIZE, "SI: TOT is too short");
+    let size = SECTION_HEADER_SIZE + data.len();
+    ensure!(crc32_mpeg(&section[..size]) == 0, "SI: invalid section CRC");
+
+    let utc_time = decode_utc_time(&data[0..5])?.context("SI: undefined TOT time")?;
+    let (descriptors, _) = split_loop(&data[5..data.len() - CRC32_SIZE])?;
+    let descriptors = parse_descriptors(descriptors)?;
+
+    let mut local_time_offsets = Vec::new();
+    for d in descriptors.iter().filter(|d| d.tag == LOCAL_TIME_OFFSET_DESCRIPTOR_TAG) {
+        local_time_offsets.extend(parse_local_time_offsets(&d.data)?);
+    }
+
+    Ok(Tot {
+        utc_time,
+        local_time_offsets,
+        descriptors,
+    })
+}
@@ -0,0 +1,253 @@
+//! DVB text strings
+//!
+//! EN 300 468 Annex A. First byte of the string could select the character table,
+//! without selection bytes the default table is used.
+
+/// Character table of the string
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Charset {
+    /// Default table. ISO/IEC 6937 with the euro sign
+    Default,
+    /// ISO/IEC 8859 part
+    Iso8859(u8),
+    /// ISO/IEC 10646 Basic Multilingual Plane, 2 bytes per character
+    Ucs2,
+    Utf8,
+    /// Table not supported by the decoder
+    Unsupported,
+}
+
+/// Splits character table selection bytes from the string
+fn select_charset(data: &[u8]) -> (Charset, &[u8]) {
+    match data {
+        [] => (Charset::Default, data),
+        [v @ 0x01..=0x0B, rest @ ..] => (Charset::Iso8859(v + 4), rest),
+        [0x10, 0x00, v, rest @ ..] => (Charset::Iso8859(*v), rest),
+        [0x10, ..] => (Charset::Unsupported, &[]),
+        [0x11, rest @ ..] => (Charset::Ucs2, rest),
+        [0x15, rest @ ..] => (Charset::Utf8, rest),
+        [0x1F, _, rest @ ..] => (Charset::Unsupported, rest),
+        [0x00..=0x1F, rest @ ..] => (Charset::Unsupported, rest),
+        _ => (Charset::Default, data),
+    }
+}
+
+/// Maps single byte control codes: 0x8A is CR/LF, other are dropped
+fn control_code(v: u8) -> Option<char> {
+    match v {
+        0x8A => Some('\n'),
+        _ => None,
+    }
+}
+
+/// Maps control codes of the multi-byte tables: U+E080..U+E09F
+fn unicode_control_code(v: char) -> Option<char> {
+    match u32::from(v) {
+        v @ 0xE080..=0xE09F => control_code((v - 0xE000) as u8),
+        _ => Some(v),
+    }
+}
+
+/// Default table: characters 0xA0..=0xFF. Non-spacing diacritical marks
+/// 0xC1..=0xCF are handled with `ISO6937_DIACRITICS`, '\0' is unused position
+const ISO6937_TABLE: &str = concat!(
+    "\u{A0}¡¢£€¥\0§¤‘“«←↑→↓",
+    "°±²³×µ¶·÷’”»¼½¾¿",
+    "\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+    "―¹®©™♪¬¦\0\0\0\0⅛⅜⅝⅞",
+    "ΩÆĐªĦ\0ĲĿŁØŒºÞŦŊŉ",
+    "ĸæđðħıĳŀłøœßþŧŋ\u{AD}",
+);
+
+/// Non-spacing diacritical marks 0xC1..=0xCF: combining character,
+/// base letters and precomposed letters
+const ISO6937_DIACRITICS: [(char, &str, &str); 15] = [
+    ('\u{300}', "AEIOUaeiou", "ÀÈÌÒÙàèìòù"),
+    (
+        '\u{301}',
+        "ACEILNORSUYZacegilnorsuyz",
+        "ÁĆÉÍĹŃÓŔŚÚÝŹáćéǵíĺńóŕśúýź",
+    ),
+    (
+        '\u{302}',
+        "ACEGHIJOSUWYaceghijosuwy",
+        "ÂĈÊĜĤÎĴÔŜÛŴŶâĉêĝĥîĵôŝûŵŷ",
+    ),
+    ('\u{303}', "AINOUainou", "ÃĨÑÕŨãĩñõũ"),
+    ('\u{304}', "AEIOUaeiou", "ĀĒĪŌŪāēīōū"),
+    ('\u{306}', "AGUagu", "ĂĞŬăğŭ"),
+    ('\u{307}', "CEGIZcegz", "ĊĖĠİŻċėġż"),
+    ('\u{308}', "AEIOUYaeiouy", "ÄËÏÖÜŸäëïöüÿ"),
+    ('\0', "", ""),
+    ('\u{30A}', "AUau", "ÅŮåů"),
+    ('\u{327}', "CGKLNRSTcgklnrst", "ÇĢĶĻŅŖŞŢçģķļņŗşţ"),
+    ('\0', "", ""),
+    ('\u{30B}', "OUou", "ŐŰőű"),
+    ('\u{328}', "AEIUaeiu", "ĄĘĮŲąęįų"),
+    ('\u{30C}', "CDELNRSTZcdelnrstz", "ČĎĚĽŇŘŠŤŽčďěľňřšťž"),
+];
+
+/// Decodes string in the default table
+fn decode_iso6937(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len());
+    let mut iter = data.iter().copied().peekable();
+
+    while let Some(v) = iter.next() {
+        match v {
+            0x00..=0x7F => result.push(char::from(v)),
+            0x80..=0x9F => result.extend(control_code(v)),
+            0xC1..=0xCF => {
+                let (mark, bases, letters) = ISO6937_DIACRITICS[usize::from(v - 0xC1)];
+                if mark == '\0' {
+                    result.push(char::REPLACEMENT_CHARACTER);
+                    continue;
+                }
+                let base = match iter.next_if(|v| (0x20..=0x7F).contains(v)) {
+                    Some(base) => char::from(base),
+                    None => {
+                        result.push(char::REPLACEMENT_CHARACTER);
+                        continue;
+                    }
+                };
+                match bases.chars().position(|c| c == base) {
+                    Some(i) => result.extend(letters.chars().nth(i)),
+                    None => {
+                        result.push(base);
+                        result.push(mark);
+                    }
+                }
+            }
+            _ => match ISO6937_TABLE.chars().nth(usize::from(v - 0xA0)) {
+                Some('\0') | None => result.push(char::REPLACEMENT_CHARACTER),
+                Some(c) => result.push(c),
+            },
+        }
+    }
+
+    result
+}
+
+/// Returns character 0xA0..=0xFF of the ISO/IEC 8859 part
+/// or `None` if the part is not supported
+fn iso8859_char(part: u8, v: u8) -> Option<char> {
+    let c = match (part, v) {
+        (1, _) => char::from(v),
+        // Cyrillic
+        (5, 0xA0) => '\u{A0}',
+        (5, 0xAD) => '\u{AD}',
+        (5, 0xF0) => '№',
+        (5, 0xFD) => '§',
+        (5, _) => char::from_u32(u32::from(v) + 0x360)?,
+        // Latin-5, Turkish
+        (9, 0xD0) => 'Ğ',
+        (9, 0xDD) => 'İ',
+        (9, 0xDE) => 'Ş',
+        (9, 0xF0) => 'ğ',
+        (9, 0xFD) => 'ı',
+        (9, 0xFE) => 'ş',
+        (9, _) => char::from(v),
+        // Latin-9
+        (15, 0xA4) => '€',
+        (15, 0xA6) => 'Š',
+        (15, 0xA8) => 'š',
+        (15, 0xB4) => 'Ž',
+        (15, 0xB8) => 'ž',
+        (15, 0xBC) => 'Œ',
+        (15, 0xBD) => 'œ',
+        (15, 0xBE) => 'Ÿ',
+        (15, _) => char::from(v),
+        _ => return None,
+    };
+
+    Some(c)
+}
+
+/// Decodes DVB text string, for example service name from the service descriptor.
+///
+/// Supported tables: default ISO/IEC 6937, ISO/IEC 8859 parts 1, 5, 9 and 15,
+/// UCS-2 and UTF-8. Other tables are decoded as lossy UTF-8.
+/// Control code 0x8A is converted to the line feed, other control codes are dropped
+///
+/// ```
+/// use libdvb_rs::si::decode_dvb_string;
+///
+/// assert_eq!(decode_dvb_string(b"Caf\xC2e"), "Café");
+/// assert_eq!(decode_dvb_string(b"\x10\x00\x01Caf\xE9"), "Café");
+/// assert_eq!(decode_dvb_string(b"\x15Caf\xC3\xA9"), "Café");
+/// ```
+pub fn decode_dvb_string(data: &[u8]) -> String {
+    let (charset, data) = match select_charset(data) {
+        (Charset::Iso8859(part), data) if iso8859_char(part, 0xA0).is_none() => {
+            (Charset::Unsupported, data)
+        }
+        v => v,
+    };
+
+    match charset {
+        Charset::Default => decode_iso6937(data),
+        Charset::Iso8859(part) => data
+            .iter()
+            .filter_map(|&v| match v {
+                0x00..=0x7F => Some(char::from(v)),
+                0x80..=0x9F => control_code(v),
+                v => iso8859_char(part, v),
+            })
+            .collect(),
+        Charset::Ucs2 => {
+            let units: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|v| u16::from_be_bytes([v[0], v[1]]))
+                .collect();
+            String::from_utf16_lossy(&units).chars().filter_map(unicode_control_code).collect()
+        }
+        Charset::Utf8 | Charset::Unsupported => {
+            String::from_utf8_lossy(data).chars().filter_map(unicode_control_code).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_table() {
+        assert_eq!(decode_dvb_string(b"News 24"), "News 24");
+        assert_eq!(decode_dvb_string(b"\xA4 \xA8 \xE8"), "€ ¤ Ł");
+        assert_eq!(decode_dvb_string(b"Line\x8AText\x86"), "Line\nText");
+        assert_eq!(decode_dvb_string(b"\xA6"), "\u{FFFD}");
+    }
+
+    #[test]
+    fn default_table_diacritics() {
+        assert_eq!(decode_dvb_string(b"M\xC8unchen"), "München");
+        assert_eq!(decode_dvb_string(b"\xCFSkoda \xC1a"), "Škoda à");
+        // no precomposed letter, base with combining mark
+        assert_eq!(decode_dvb_string(b"\xC1x"), "x\u{300}");
+        // unused mark and mark without base letter
+        assert_eq!(decode_dvb_string(b"\xC9a"), "\u{FFFD}a");
+        assert_eq!(decode_dvb_string(b"a\xC2"), "a\u{FFFD}");
+    }
+
+    #[test]
+    fn iso8859_prefix() {
+        assert_eq!(decode_dvb_string(b"\x01\xBF\xD5\xE0\xD2\xEB\xD9"), "Первый");
+        assert_eq!(decode_dvb_string(b"\x10\x00\x05\xBF\xD5\xE0\xD2\xEB\xD9"), "Первый");
+        assert_eq!(decode_dvb_string(b"\x10\x00\x0F\xA4 \xA6"), "€ Š");
+        assert_eq!(decode_dvb_string(b"\x10\x00\x09\xDEi\xFEi"), "Şişi");
+        // unsupported part is decoded as UTF-8
+        assert_eq!(decode_dvb_string(b"\x10\x00\x03Text"), "Text");
+    }
+
+    #[test]
+    fn utf8_prefix() {
+        assert_eq!(decode_dvb_string(b"\x15Caf\xC3\xA9"), "Café");
+        // control code U+E08A is line feed
+        assert_eq!(decode_dvb_string(b"\x15A\xEE\x82\x8AB"), "A\nB");
+    }
+
+    #[test]
+    fn ucs2_prefix() {
+        assert_eq!(decode_dvb_string(b"\x11\x00C\x04\x10"), "CА");
+    }
+}
@@ -0,0 +1,171 @@
+mod pcr;
+mod pes;
+
+pub use {pcr::*, pes::*};
+
+use {
+    crate::{crc::crc32_mpeg, dmx::DMX_SECTION_SIZE_MAX},
+    anyhow::Result,
+};
+
+/// Size of the MPEG-TS packet
+pub const TS_PACKET_SIZE: usize = 188;
+
+/// MPEG-TS packet sync byte
+pub const TS_SYNC_BYTE: u8 = 0x47;
+
+/// Size of the MPEG-TS packet header
+pub const TS_HEADER_SIZE: usize = 4;
+
+/// Collects PSI/SI sections from the TS packets of one PID
+///
+/// ```text
+/// let mut assembler = SectionAssembler::new(PAT_PID);
+/// for packet in data.chunks(TS_PACKET_SIZE) {
+///     for section in assembler.push(packet)? {
+///         let pat = si::parse_pat(&section)?;
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SectionAssembler {
+    pid: u16,
+    check_crc: bool,
+    /// continuity counter of the last packet with payload
+    cc: Option<u8>,
+    /// incomplete section
+    buffer: Vec<u8>,
+}
+
+impl SectionAssembler {
+    /// Creates assembler for sections on `pid`. CRC check is enabled
+    pub fn new(pid: u16) -> Self {
+        SectionAssembler {
+            pid,
+            check_crc: true,
+            cc: None,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Enables or disables CRC check for sections with section_syntax_indicator
+    pub fn check_crc(mut self, check_crc: bool) -> Self {
+        self.check_crc = check_crc;
+        self
+    }
+
+    /// Returns PID of the assembler
+    #[inline]
+    pub fn pid(&self) -> u16 {
+        self.pid
+    }
+
+    /// Drops incomplete section and continuity state
+    pub fn reset(&mut self) {
+        self.cc = None;
+        self.buffer.clear();
+    }
+
+    /// Processes one TS packet and returns sections completed in it.
+    /// Packets with other PID are ignored.
+    /// On continuity counter error or transport error incomplete section is dropped.
+    /// Sections with invalid CRC are dropped.
+    pub fn push(&mut self, packet: &[u8]) -> Result<Vec<Vec<u8>>> {
+        ensure!(
+            packet.len() == TS_PACKET_SIZE && packet[0] == TS_SYNC_BYTE,
+            "TS: invalid packet"
+        );
+
+        let mut sections = Vec::new();
+
+        let pid = (u16::from(packet[1] & 0x1F) << 8) | u16::from(packet[2]);
+        if pid != self.pid {
+            return Ok(sections);
+        }
+
+        // transport_error_indicator
+        if packet[1] & 0x80 != 0 {
+            self.reset();
+            return Ok(sections);
+        }
+
+        let pusi = packet[1] & 0x40 != 0;
+        let adaptation_field_control = (packet[3] >> 4) & 0x03;
+        let cc = packet[3] & 0x0F;
+
+        if adaptation_field_control & 0x01 == 0 {
+            return Ok(sections);
+        }
+
+        match self.cc {
+            // duplicate packet
+            Some(v) if v == cc => return Ok(sections),
+            Some(v) if (v + 1) & 0x0F != cc => self.buffer.clear(),
+            _ => {}
+        }
+        self.cc = Some(cc);
+
+        let mut skip = TS_HEADER_SIZE;
+        if adaptation_field_control & 0x02 != 0 {
+            skip += 1 + usize::from(packet[TS_HEADER_SIZE]);
+        }
+        let payload = match packet.get(skip..) {
+            Some(v) if !v.is_empty() => v,
+            _ => return Ok(sections),
+        };
+
+        if pusi {
+            let pointer = usize::from(payload[0]);
+            let payload = &payload[1..];
+            if pointer > payload.len() {
+                self.buffer.clear();
+                return Ok(sections);
+            }
+
+            // tail of the previous section
+            if !self.buffer.is_empty() {
+                self.buffer.extend_from_slice(&payload[..pointer]);
+                self.collect(&mut sections);
+            }
+
+            self.buffer.clear();
+            self.buffer.extend_from_slice(&payload[pointer..]);
+            self.collect(&mut sections);
+        } else if !self.buffer.is_empty() {
+            self.buffer.extend_from_slice(payload);
+            self.collect(&mut sections);
+        }
+
+        Ok(sections)
+    }
+
+    /// Moves complete sections from the buffer to `sections`
+    fn collect(&mut self, sections: &mut Vec<Vec<u8>>) {
+        while self.buffer.len() >= 3 {
+            // stuffing after the last section
+            if self.buffer[0] == 0xFF {
+                self.buffer.clear();
+                break;
+            }
+
+            let size = 3 + ((usize::from(self.buffer[1] & 0x0F) << 8) | usize::from(self.buffer[2]));
+            if size > DMX_SECTION_SIZE_MAX {
+                self.buffer.clear();
+                break;
+            }
+            if self.buffer.len() < size {
+                break;
+            }
+
+            let section: Vec<u8> = self.buffer.drain(..size).collect();
+
+            // section_syntax_indicator
+            let is_long = section[1] & 0x80 != 0;
+            if self.check_crc && is_long && crc32_mpeg(&section) != 0 {
+                continue;
+            }
+
+            sections.push(section);
+        }
+    }
+}
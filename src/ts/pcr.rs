@@ -0,0 +1,192 @@
+use {
+    super::{TS_HEADER_SIZE, TS_PACKET_SIZE, TS_SYNC_BYTE},
+    std::collections::VecDeque,
+};
+
+/// PCR clock frequency, 27MHz
+pub const PCR_FREQUENCY: u64 = 27_000_000;
+
+/// Returns PCR from the adaptation field of the TS packet in 27MHz units:
+/// `program_clock_reference_base * 300 + program_clock_reference_extension`.
+/// Returns `None` if packet has no adaptation field or PCR_flag is not set
+pub fn extract_pcr(packet: &[u8; TS_PACKET_SIZE]) -> Option<u64> {
+    let adaptation_field_control = (packet[3] >> 4) & 0x03;
+    if packet[0] != TS_SYNC_BYTE || adaptation_field_control & 0x02 == 0 {
+        return None;
+    }
+
+    // adaptation_field_length, flags and 6 bytes of PCR
+    let adaptation_field_length = usize::from(packet[TS_HEADER_SIZE]);
+    if adaptation_field_length < 7 || packet[TS_HEADER_SIZE + 1] & 0x10 == 0 {
+        return None;
+    }
+
+    let pcr = &packet[TS_HEADER_SIZE + 2..TS_HEADER_SIZE + 8];
+    let base = (u64::from(pcr[0]) << 25)
+        | (u64::from(pcr[1]) << 17)
+        | (u64::from(pcr[2]) << 9)
+        | (u64::from(pcr[3]) << 1)
+        | (u64::from(pcr[4]) >> 7);
+    let extension = (u64::from(pcr[4] & 0x01) << 8) | u64::from(pcr[5]);
+
+    Some(base * 300 + extension)
+}
+
+/// PCR wraps around at 2^33 of the 90kHz base
+const PCR_WRAP: u64 = (1 << 33) * 300;
+
+/// Default number of PCR intervals in the rolling average
+const BITRATE_WINDOW: usize = 16;
+
+/// Estimates bitrate from the PCR deltas and number of bytes between them
+///
+/// Feed all packets of the multiplex to get the mux bitrate, or packets of one PID
+/// to get bitrate of that PID. PCR is taken from packets with `pcr_pid`.
+///
+/// ```text
+/// let mut estimator = BitrateEstimator::new(pmt.pcr_pid);
+/// for packet in data.chunks_exact(TS_PACKET_SIZE) {
+///     estimator.push(packet.try_into()?);
+/// }
+/// println!("{:?} bit/s", estimator.bitrate());
+/// ```
+#[derive(Debug)]
+pub struct BitrateEstimator {
+    pcr_pid: u16,
+    window: usize,
+    last_pcr: Option<u64>,
+    /// bytes since the last PCR
+    bytes: u64,
+    /// bytes and PCR ticks of the last intervals
+    intervals: VecDeque<(u64, u64)>,
+}
+
+impl BitrateEstimator {
+    /// Creates estimator with PCR from `pcr_pid`
+    pub fn new(pcr_pid: u16) -> Self {
+        BitrateEstimator {
+            pcr_pid,
+            window: BITRATE_WINDOW,
+            last_pcr: None,
+            bytes: 0,
+            intervals: VecDeque::new(),
+        }
+    }
+
+    /// Sets number of PCR intervals in the rolling average. Default is 16
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Drops collected intervals
+    pub fn reset(&mut self) {
+        self.last_pcr = None;
+        self.bytes = 0;
+        self.intervals.clear();
+    }
+
+    /// Processes one TS packet. Returns updated bitrate if packet has PCR
+    pub fn push(&mut self, packet: &[u8; TS_PACKET_SIZE]) -> Option<u64> {
+        self.bytes += TS_PACKET_SIZE as u64;
+
+        let pid = (u16::from(packet[1] & 0x1F) << 8) | u16::from(packet[2]);
+        if pid != self.pcr_pid {
+            return None;
+        }
+        let pcr = extract_pcr(packet)?;
+
+        // discontinuity_indicator
+        if packet[TS_HEADER_SIZE + 1] & 0x80 != 0 {
+            self.reset();
+        }
+
+        if let Some(last_pcr) = self.last_pcr.replace(pcr) {
+            let ticks = (pcr + PCR_WRAP - last_pcr) % PCR_WRAP;
+            // PCR interval should not exceed 100ms, larger gap is a discontinuity
+            if ticks == 0 || ticks > PCR_FREQUENCY / 10 {
+                self.intervals.clear();
+            } else {
+                if self.intervals.len() == self.window {
+                    self.intervals.pop_front();
+                }
+                self.intervals.push_back((self.bytes, ticks));
+            }
+        }
+        self.bytes = 0;
+
+        self.bitrate()
+    }
+
+    /// Returns rolling-average bitrate in bits per second
+    pub fn bitrate(&self) -> Option<u64> {
+        let (bytes, ticks) = self
+            .intervals
+            .iter()
+            .fold((0, 0), |(bytes, ticks), v| (bytes + v.0, ticks + v.1));
+
+        (bytes * 8 * PCR_FREQUENCY).checked_div(ticks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PCR_PID: u16 = 0x100;
+
+    /// Packet with PCR in the adaptation field
+    fn pcr_packet(pcr: u64) -> [u8; TS_PACKET_SIZE] {
+        let mut packet = [0xFF; TS_PACKET_SIZE];
+        let (base, extension) = (pcr / 300, pcr % 300);
+        packet[..TS_HEADER_SIZE + 8].copy_from_slice(&[
+            TS_SYNC_BYTE,
+            (PCR_PID >> 8) as u8,
+            PCR_PID as u8,
+            0x20,
+            (TS_PACKET_SIZE - TS_HEADER_SIZE - 1) as u8,
+            0x10,
+            (base >> 25) as u8,
+            (base >> 17) as u8,
+            (base >> 9) as u8,
+            (base >> 1) as u8,
+            ((base & 0x01) << 7) as u8 | 0x7E | (extension >> 8) as u8,
+            extension as u8,
+        ]);
+        packet
+    }
+
+    /// Pushes packets without PCR and the packet with `pcr`, 10 packets total
+    fn push(estimator: &mut BitrateEstimator, pcr: u64) -> Option<u64> {
+        let mut packet = [0xFF; TS_PACKET_SIZE];
+        packet[..TS_HEADER_SIZE].copy_from_slice(&[TS_SYNC_BYTE, 0x01, 0x01, 0x10]);
+        for _ in 0..9 {
+            estimator.push(&packet);
+        }
+        estimator.push(&pcr_packet(pcr))
+    }
+
+    #[test]
+    fn extract() {
+        assert_eq!(extract_pcr(&pcr_packet(PCR_WRAP - 1)), Some(PCR_WRAP - 1));
+        assert_eq!(extract_pcr(&pcr_packet(12_345_678)), Some(12_345_678));
+    }
+
+    #[test]
+    fn wraparound() {
+        let mut estimator = BitrateEstimator::new(PCR_PID);
+        // 40ms interval over the 2^33 wraparound
+        assert_eq!(push(&mut estimator, PCR_WRAP - 540_000), None);
+        assert_eq!(push(&mut estimator, 540_000), Some(10 * 188 * 8 * 25));
+    }
+
+    #[test]
+    fn gap() {
+        let mut estimator = BitrateEstimator::new(PCR_PID);
+        push(&mut estimator, 0);
+        assert_eq!(push(&mut estimator, PCR_FREQUENCY / 10), Some(10 * 188 * 8 * 10));
+        // 150ms gap is a discontinuity
+        assert_eq!(push(&mut estimator, PCR_FREQUENCY / 4), None);
+        assert_eq!(push(&mut estimator, PCR_FREQUENCY * 3 / 10), Some(10 * 188 * 8 * 20));
+    }
+}
@@ -0,0 +1,250 @@
+use anyhow::Result;
+
+/// PES packet start code prefix
+pub const PES_START_CODE: [u8; 3] = [0x00, 0x00, 0x01];
+
+/// Size of the PES packet header before the optional header
+pub const PES_HEADER_SIZE: usize = 6;
+
+/// Minimal stream_id of the PES packet. Lower values after the start code
+/// prefix are start codes of the elementary stream
+const PES_STREAM_ID_MIN: u8 = 0xBC;
+
+/// PES packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PesPacket {
+    pub stream_id: u8,
+    /// Presentation time stamp in 90kHz units
+    pub pts: Option<u64>,
+    /// Decoding time stamp in 90kHz units
+    pub dts: Option<u64>,
+    pub payload: Vec<u8>,
+}
+
+/// Returns true if the stream has no optional PES header
+fn is_header_less(stream_id: u8) -> bool {
+    matches!(
+        stream_id,
+        // program_stream_map, padding_stream, private_stream_2,
+        // ECM, EMM, DSMCC, H.222.1 type E, program_stream_directory
+        0xBC | 0xBE | 0xBF | 0xF0 | 0xF1 | 0xF2 | 0xF8 | 0xFF
+    )
+}
+
+/// Decodes 33-bit time stamp from 5 bytes with marker bits
+fn parse_timestamp(data: &[u8]) -> u64 {
+    (u64::from(data[0] >> 1) & 0x07) << 30
+        | u64::from(data[1]) << 22
+        | u64::from(data[2] >> 1) << 15
+        | u64::from(data[3]) << 7
+        | u64::from(data[4] >> 1)
+}
+
+/// Parses complete PES packet
+pub fn parse_pes(packet: &[u8]) -> Result<PesPacket> {
+    ensure!(
+        packet.len() >= PES_HEADER_SIZE && packet[..3] == PES_START_CODE,
+        "TS: invalid PES packet"
+    );
+
+    let stream_id = packet[3];
+    let mut result = PesPacket {
+        stream_id,
+        pts: None,
+        dts: None,
+        payload: Vec::new(),
+    };
+
+    if is_header_less(stream_id) {
+        result.payload = packet[PES_HEADER_SIZE..].to_vec();
+        return Ok(result);
+    }
+
+    ensure!(
+        packet.len() >= PES_HEADER_SIZE + 3 && packet[6] & 0xC0 == 0x80,
+        "TS: invalid PES header"
+    );
+
+    let pts_dts_flags = packet[7] >> 6;
+    let begin = PES_HEADER_SIZE + 3 + usize::from(packet[8]);
+    ensure!(begin <= packet.len(), "TS: invalid PES header length");
+
+    let header = &packet[PES_HEADER_SIZE + 3..begin];
+    match pts_dts_flags {
+        0b10 if header.len() >= 5 => {
+            result.pts = Some(parse_timestamp(&header[0..5]));
+        }
+        0b11 if header.len() >= 10 => {
+            result.pts = Some(parse_timestamp(&header[0..5]));
+            result.dts = Some(parse_timestamp(&header[5..10]));
+        }
+        0b00 => {}
+        _ => bail!("TS: invalid PES time stamps"),
+    }
+
+    result.payload = packet[begin..].to_vec();
+
+    Ok(result)
+}
+
+/// Reassembles PES packets from the byte stream,
+/// for example from the demux device with PES filter and `DMX_OUT_TAP` output
+///
+/// Packets with PES_packet_length 0 (video) are completed on the next packet start
+/// or with `flush`. Malformed packets are skipped and counted with `error_count`.
+///
+/// ```text
+/// let mut parser = PesParser::new();
+/// loop {
+///     let size = dmx.read(&mut buffer)?;
+///     for pes in parser.push(&buffer[..size]) {
+///         println!("{:02X} {:?}", pes.stream_id, pes.pts);
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct PesParser {
+    buffer: Vec<u8>,
+    /// Number of skipped malformed packets
+    error_count: u64,
+}
+
+impl PesParser {
+    /// Creates an empty parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops incomplete packet
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Returns number of malformed packets skipped by `push` and `flush`
+    #[inline]
+    pub fn error_count(&self) -> u64 {
+        self.error_count
+    }
+
+    /// Parses complete packet. Malformed packet is counted and dropped
+    fn parse(&mut self, packet: &[u8]) -> Option<PesPacket> {
+        match parse_pes(packet) {
+            Ok(v) => Some(v),
+            Err(_) => {
+                self.error_count += 1;
+                None
+            }
+        }
+    }
+
+    /// Appends data and returns completed packets.
+    /// Data before the start code and malformed packets are skipped
+    pub fn push(&mut self, data: &[u8]) -> Vec<PesPacket> {
+        self.buffer.extend_from_slice(data);
+
+        let mut packets = Vec::new();
+
+        loop {
+            // sync to the packet start
+            match find_start(&self.buffer, 0) {
+                Some(0) => {}
+                Some(skip) => {
+                    self.buffer.drain(..skip);
+                }
+                None => {
+                    // keep possible beginning of the start code
+                    let skip = self.buffer.len().saturating_sub(3);
+                    self.buffer.drain(..skip);
+                    break;
+                }
+            }
+
+            if self.buffer.len() < PES_HEADER_SIZE {
+                break;
+            }
+
+            let length = usize::from(u16::from_be_bytes([self.buffer[4], self.buffer[5]]));
+            let size = if length != 0 {
+                PES_HEADER_SIZE + length
+            } else {
+                match find_start(&self.buffer, PES_HEADER_SIZE) {
+                    Some(v) => v,
+                    None => break,
+                }
+            };
+
+            if self.buffer.len() < size {
+                break;
+            }
+
+            let packet: Vec<u8> = self.buffer.drain(..size).collect();
+            packets.extend(self.parse(&packet));
+        }
+
+        packets
+    }
+
+    /// Completes packet with unknown length, if any
+    pub fn flush(&mut self) -> Option<PesPacket> {
+        if self.buffer.len() < PES_HEADER_SIZE || self.buffer[..3] != PES_START_CODE {
+            self.buffer.clear();
+            return None;
+        }
+
+        let packet = std::mem::take(&mut self.buffer);
+        self.parse(&packet)
+    }
+}
+
+/// Returns position of the next PES packet start code from `offset`
+fn find_start(data: &[u8], offset: usize) -> Option<usize> {
+    data.get(offset..)?
+        .windows(4)
+        .position(|w| w[..3] == PES_START_CODE && w[3] >= PES_STREAM_ID_MIN)
+        .map(|v| v + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Audio packet with PTS
+    fn audio(pts: u8) -> Vec<u8> {
+        vec![
+            0x00, 0x00, 0x01, 0xC0, 0x00, 0x09, 0x80, 0x80, 0x05, 0x21, 0x00, 0x01, 0x00,
+            pts << 1 | 1, 0xAA,
+        ]
+    }
+
+    #[test]
+    fn skip_malformed() {
+        let mut stream = audio(1);
+        // invalid marker bits of the optional header
+        stream.extend_from_slice(&[0x00, 0x00, 0x01, 0xC0, 0x00, 0x03, 0x00, 0x00, 0x00]);
+        // header length out of the packet
+        stream.extend_from_slice(&[0x00, 0x00, 0x01, 0xC0, 0x00, 0x03, 0x80, 0x80, 0x05]);
+        stream.extend(audio(2));
+
+        let mut parser = PesParser::new();
+        let packets = parser.push(&stream);
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].pts, Some(1));
+        assert_eq!(packets[1].pts, Some(2));
+        assert_eq!(packets[1].payload, [0xAA]);
+        assert_eq!(parser.error_count(), 2);
+        assert!(parser.flush().is_none());
+    }
+
+    #[test]
+    fn flush_malformed() {
+        let mut parser = PesParser::new();
+
+        // unbounded video packet with invalid time stamps flags
+        assert!(parser
+            .push(&[0x00, 0x00, 0x01, 0xE0, 0x00, 0x00, 0x80, 0x40, 0x00, 0x00])
+            .is_empty());
+        assert!(parser.flush().is_none());
+        assert_eq!(parser.error_count(), 1);
+    }
+}
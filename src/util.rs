@@ -0,0 +1,80 @@
+use {
+    anyhow::{Context, Result},
+    nix::{
+        errno::Errno,
+        poll::{poll, PollFd, PollFlags},
+    },
+    std::{
+        os::unix::io::RawFd,
+        time::{Duration, Instant},
+    },
+};
+
+/// Modified Julian Date of the 1970-01-01
+pub const MJD_UNIX_EPOCH: u32 = 40587;
+
+/// Converts 16 bit Modified Julian Date to the number of days since 1970-01-01.
+/// Returns `None` for dates before 1970
+pub fn mjd_to_unix_days(mjd: u16) -> Option<u64> {
+    u32::from(mjd).checked_sub(MJD_UNIX_EPOCH).map(u64::from)
+}
+
+/// Converts number of days since 1970-01-01 to 16 bit Modified Julian Date.
+/// MJD field overflows in April 2038, higher bits are dropped
+pub fn unix_days_to_mjd(days: u64) -> u16 {
+    (u64::from(MJD_UNIX_EPOCH) + days) as u16
+}
+
+/// Builds device path from `template` with `{adapter}` and `{device}` placeholders
+pub fn device_path(template: &str, adapter: u32, device: u32) -> Result<String> {
+    ensure!(
+        template.contains("{adapter}") && template.contains("{device}"),
+        "path template {} should contain {{adapter}} and {{device}}",
+        template
+    );
+
+    Ok(template
+        .replace("{adapter}", &adapter.to_string())
+        .replace("{device}", &device.to_string()))
+}
+
+/// Waits until file descriptor is ready for reading or has an urgent event.
+/// Waits no more than `timeout` or infinitely if timeout is `None`.
+/// Returns `false` on timeout. Interrupted calls are restarted.
+pub fn wait_readable(fd: RawFd, timeout: Option<Duration>) -> Result<bool> {
+    let deadline = timeout.map(|v| Instant::now() + v);
+    let mut fds = [PollFd::new(fd, PollFlags::POLLIN | PollFlags::POLLPRI)];
+
+    loop {
+        let wait = match deadline {
+            Some(v) => v
+                .saturating_duration_since(Instant::now())
+                .as_millis()
+                .min(i32::MAX as u128) as i32,
+            None => -1,
+        };
+
+        match poll(&mut fds, wait) {
+            Ok(0) => return Ok(false),
+            Ok(_) => return Ok(true),
+            Err(Errno::EINTR) => continue,
+            Err(e) => return Err(e).context("poll failed"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mjd() {
+        assert_eq!(mjd_to_unix_days(40587), Some(0));
+        // 2000-01-01
+        assert_eq!(mjd_to_unix_days(51544), Some(10_957));
+        assert_eq!(mjd_to_unix_days(40586), None);
+
+        assert_eq!(unix_days_to_mjd(0), 40587);
+        assert_eq!(unix_days_to_mjd(10_957), 51544);
+    }
+}